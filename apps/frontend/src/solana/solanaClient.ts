@@ -20,8 +20,10 @@ import { WalletContextState } from '@solana/wallet-adapter-react';
 
 // Define constants that might be missing from imports
 const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey('ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL');
+const TOKEN_2022_PROGRAM_ID = new PublicKey('TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb');
 const SYSVAR_RENT_PUBKEY = new PublicKey('SysvarRent111111111111111111111111111111111');
 const SYSVAR_CLOCK_PUBKEY = new PublicKey('SysvarC1ock11111111111111111111111111111111');
+const SYSVAR_INSTRUCTIONS_PUBKEY = new PublicKey('Sysvar1nstructions1111111111111111111111111');
 
 // Define contract program IDs
 const MATERIAL_PROGRAM_ID = new PublicKey('Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS');
@@ -30,22 +32,36 @@ const CRAFTING_PROGRAM_ID = new PublicKey('Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476
 const GUILD_PROGRAM_ID = new PublicKey('Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS');
 const TOKEN_METADATA_PROGRAM_ID = new PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s');
 
-// Material type enum
+// Material type enum, shared by all programs
 export enum MaterialType {
-  Basic = 'Basic',
-  Rare = 'Rare',
-  Seasonal = 'Seasonal',
-  Mysterious = 'Mysterious'
+  Basic = 0,
+  Rare = 1,
+  Seasonal = 2,
+  Mysterious = 3,
+  Crafted = 4
 }
 
-// Rarity enum
+// Rarity enum, shared by all programs
 export enum Rarity {
-  Common = 'Common',
-  Rare = 'Rare',
-  Epic = 'Epic',
-  Legendary = 'Legendary'
+  Common = 0,
+  Uncommon = 1,
+  Rare = 2,
+  Epic = 3,
+  Legendary = 4
 }
 
+// Material mode enum: stackable units of one mint, or numbered prints of a master edition
+export enum MaterialMode {
+  Fungible = 0,
+  Unique = 1
+}
+
+// Transfer behaviour built into a Token-2022 material's mint
+export type TransferRule =
+  | { transferable: {} }
+  | { nonTransferable: {} }
+  | { transferHook: { programId: PublicKey } };
+
 // Recipe difficulty enum
 export enum RecipeDifficulty {
   Beginner = 0,
@@ -73,14 +89,27 @@ export enum GuildRole {
 // Material type interface
 export interface MaterialData {
   mint: PublicKey;
-  materialType: string;
-  rarity: string;
+  materialType: MaterialType;
+  rarity: Rarity;
   currentSupply: BN;
   maxSupply: BN;
   createdAt: BN;
   updatedAt: BN;
   authority: PublicKey;
   isActive: boolean;
+  mode: MaterialMode;
+}
+
+// One trait of a single print of a unique material
+export interface EditionAttributeData {
+  traitType: string;
+  value: string;
+}
+
+// Royalty split entry, shares of a material sum to 100
+export interface CreatorShareData {
+  address: PublicKey;
+  share: number;
 }
 
 // Recipe type interface
@@ -98,7 +127,7 @@ export interface RecipeData {
   updatedAt: BN;
   resultName: string;
   resultDescription: string;
-  resultRarity: string;
+  resultRarity: Rarity;
   timesCrafted: BN;
 }
 
@@ -214,7 +243,7 @@ class SolanaClient {
   // ========== Material system interface ==========
 
   // Initialize material system
-  async initializeMaterialSystem(): Promise<string> {
+  async initializeMaterialSystem(maxRoyaltyBps: number): Promise<string> {
     this.checkPrograms();
     
     const [materialAuthority, materialAuthorityBump] = await PublicKey.findProgramAddress(
@@ -225,6 +254,7 @@ class SolanaClient {
     try {
       const tx = await this.materialProgram!.rpc.initialize(
         materialAuthorityBump,
+        maxRoyaltyBps,
         {
           accounts: {
             materialAuthority,
@@ -242,15 +272,71 @@ class SolanaClient {
     }
   }
 
+  // Create the collection NFT that every material is verified into
+  async createMaterialCollection(
+    name: string,
+    symbol: string,
+    uri: string
+  ): Promise<string> {
+    this.checkPrograms();
+    
+    const [materialAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('material_authority')],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const { collectionMint, collectionMetadata, collectionMasterEdition } =
+      await this.getCollectionAccounts();
+    
+    const collectionTokenAccount = await getAssociatedTokenAddress(
+      collectionMint,
+      materialAuthority,
+      true
+    );
+    
+    try {
+      const tx = await this.materialProgram!.rpc.createMaterialCollection(
+        name,
+        symbol,
+        uri,
+        {
+          accounts: {
+            materialAuthority,
+            collectionMint,
+            collectionTokenAccount,
+            collectionMetadata,
+            collectionMasterEdition,
+            authority: this.wallet!.publicKey!,
+            payer: this.wallet!.publicKey!,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+            rent: SYSVAR_RENT_PUBKEY
+          }
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to create material collection:', error);
+      throw error;
+    }
+  }
+
   // Create material NFT
   async createMaterial(
     mint: PublicKey,
     name: string,
     symbol: string,
     uri: string,
-    materialType: string,
-    rarity: string,
-    maxSupply: number
+    materialType: MaterialType,
+    rarity: Rarity,
+    maxSupply: number,
+    mode: MaterialMode,
+    sellerFeeBasisPoints: number,
+    creators: CreatorShareData[]
   ): Promise<string> {
     this.checkPrograms();
     
@@ -265,6 +351,15 @@ class SolanaClient {
     );
     
     const metadataAddress = await this.findMetadataAddress(mint);
+    const { collectionMint, collectionMetadata, collectionMasterEdition } =
+      await this.getCollectionAccounts();
+    
+    // Unique materials keep their single master token with the material authority
+    const isUnique = mode === MaterialMode.Unique;
+    const masterEdition = isUnique ? await this.findEditionAddress(mint) : null;
+    const masterTokenAccount = isUnique
+      ? await getAssociatedTokenAddress(mint, materialAuthority)
+      : null;
     
     try {
       const tx = await this.materialProgram!.rpc.createMaterial(
@@ -274,16 +369,27 @@ class SolanaClient {
         materialType,
         rarity,
         new BN(maxSupply),
+        mode,
+        sellerFeeBasisPoints,
+        creators,
         {
           accounts: {
             material,
             materialAuthority,
             mint,
             metadata: metadataAddress,
+            collectionMint,
+            collectionMetadata,
+            collectionMasterEdition,
+            masterEdition,
+            masterTokenAccount,
             authority: this.wallet!.publicKey!,
+            payer: this.wallet!.publicKey!,
             tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
+            sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
             rent: SYSVAR_RENT_PUBKEY
           }
         }
@@ -296,10 +402,167 @@ class SolanaClient {
     }
   }
 
+  // Create a Token-2022 material whose metadata and transfer rule live on the mint itself
+  async createMaterial2022(
+    mint: Keypair,
+    name: string,
+    symbol: string,
+    uri: string,
+    materialType: MaterialType,
+    rarity: Rarity,
+    maxSupply: number,
+    transferRule: TransferRule = { transferable: {} }
+  ): Promise<string> {
+    this.checkPrograms();
+    
+    const [materialAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('material_authority')],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const [material] = await PublicKey.findProgramAddress(
+      [Buffer.from('material'), mint.publicKey.toBuffer()],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    try {
+      const tx = await this.materialProgram!.rpc.createMaterial2022(
+        name,
+        symbol,
+        uri,
+        materialType,
+        rarity,
+        new BN(maxSupply),
+        transferRule,
+        {
+          accounts: {
+            material,
+            materialAuthority,
+            mint: mint.publicKey,
+            authority: this.wallet!.publicKey!,
+            payer: this.wallet!.publicKey!,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: SystemProgram.programId
+          },
+          signers: [mint]
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to create Token-2022 material:', error);
+      throw error;
+    }
+  }
+
+  // Sign a material's metadata as its co-creator
+  async verifyCreator(materialMint: PublicKey): Promise<string> {
+    this.checkPrograms();
+    
+    const [material] = await PublicKey.findProgramAddress(
+      [Buffer.from('material'), materialMint.toBuffer()],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const metadata = await this.findMetadataAddress(materialMint);
+    
+    try {
+      const tx = await this.materialProgram!.rpc.verifyCreator({
+        accounts: {
+          material,
+          metadata,
+          creator: this.wallet!.publicKey!,
+          tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY
+        }
+      });
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to verify creator:', error);
+      throw error;
+    }
+  }
+
+  // Change a material's royalty basis points and creator split
+  async updateMaterialRoyalties(
+    materialMint: PublicKey,
+    sellerFeeBasisPoints: number,
+    creators: CreatorShareData[]
+  ): Promise<string> {
+    this.checkPrograms();
+    
+    const [material] = await PublicKey.findProgramAddress(
+      [Buffer.from('material'), materialMint.toBuffer()],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const [materialAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('material_authority')],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const metadata = await this.findMetadataAddress(materialMint);
+    
+    try {
+      const tx = await this.materialProgram!.rpc.updateMaterialRoyalties(
+        sellerFeeBasisPoints,
+        creators,
+        {
+          accounts: {
+            material,
+            materialAuthority,
+            mint: materialMint,
+            metadata,
+            authority: this.wallet!.publicKey!,
+            payer: this.wallet!.publicKey!,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY
+          }
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to update material royalties:', error);
+      throw error;
+    }
+  }
+
+  // Set the platform-wide royalty cap
+  async setMaxRoyalty(maxRoyaltyBps: number): Promise<string> {
+    this.checkPrograms();
+    
+    const [materialAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('material_authority')],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    try {
+      const tx = await this.materialProgram!.rpc.setMaxRoyalty(
+        maxRoyaltyBps,
+        {
+          accounts: {
+            materialAuthority,
+            authority: this.wallet!.publicKey!
+          }
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to set royalty cap:', error);
+      throw error;
+    }
+  }
+
   // Mint material NFT
   async mintMaterial(
     materialMint: PublicKey,
-    amount: number
+    amount: number,
+    tokenProgram: PublicKey = TOKEN_PROGRAM_ID
   ): Promise<string> {
     this.checkPrograms();
     
@@ -313,9 +576,12 @@ class SolanaClient {
       MATERIAL_PROGRAM_ID
     );
     
+    // Token-2022 materials hold their balances in Token-2022 associated accounts
     const tokenAccount = await getAssociatedTokenAddress(
       materialMint,
-      this.wallet!.publicKey!
+      this.wallet!.publicKey!,
+      false,
+      tokenProgram
     );
     
     try {
@@ -328,7 +594,10 @@ class SolanaClient {
             mint: materialMint,
             tokenAccount,
             receiver: this.wallet!.publicKey!,
-            tokenProgram: TOKEN_PROGRAM_ID,
+            minter: this.wallet!.publicKey!,
+            minterPermit: null,
+            payer: this.wallet!.publicKey!,
+            tokenProgram,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
             rent: SYSVAR_RENT_PUBKEY
@@ -343,6 +612,73 @@ class SolanaClient {
     }
   }
 
+  // Print the next numbered edition of a unique material
+  async printMaterial(
+    materialMint: PublicKey,
+    attributes: EditionAttributeData[]
+  ): Promise<string> {
+    this.checkPrograms();
+    
+    const [material] = await PublicKey.findProgramAddress(
+      [Buffer.from('material'), materialMint.toBuffer()],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const [materialAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('material_authority')],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    // Editions are numbered from 1 in print order
+    const materialData = await this.getMaterial(materialMint);
+    const editionNumber = materialData.currentSupply.toNumber() + 1;
+    
+    const editionMint = Keypair.generate();
+    const editionAccounts = await this.getPrintAccounts(
+      materialMint,
+      editionMint.publicKey,
+      editionNumber,
+      this.wallet!.publicKey!
+    );
+    
+    try {
+      const tx = await this.materialProgram!.rpc.printMaterial(
+        attributes,
+        {
+          accounts: {
+            material,
+            materialAuthority,
+            mint: materialMint,
+            masterMetadata: await this.findMetadataAddress(materialMint),
+            masterEdition: editionAccounts.masterEdition,
+            masterTokenAccount: editionAccounts.masterTokenAccount,
+            editionMint: editionMint.publicKey,
+            editionMetadata: editionAccounts.editionMetadata,
+            edition: editionAccounts.edition,
+            editionMarker: editionAccounts.editionMarker,
+            editionTokenAccount: editionAccounts.editionTokenAccount,
+            receiver: this.wallet!.publicKey!,
+            materialEdition: editionAccounts.materialEdition,
+            minter: this.wallet!.publicKey!,
+            minterPermit: null,
+            payer: this.wallet!.publicKey!,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY
+          },
+          signers: [editionMint]
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to print material:', error);
+      throw error;
+    }
+  }
+
   // Get material information
   async getMaterial(materialMint: PublicKey): Promise<MaterialData> {
     this.checkPrograms();
@@ -400,10 +736,13 @@ class SolanaClient {
     difficulty: RecipeDifficulty,
     craftingTime: number,
     successRate: number,
-    ingredients: { materialMint: PublicKey, quantity: number }[],
+    ingredients: { materialMint: PublicKey, quantity: number, sourceRecipe?: PublicKey }[],
     resultName: string,
     resultDescription: string,
-    resultRarity: string
+    resultRarity: Rarity,
+    resultQuantity: number = 1,
+    refundPercentage: number = 0,
+    stageRecipes: PublicKey[] = []
   ): Promise<string> {
     this.checkPrograms();
     
@@ -414,7 +753,8 @@ class SolanaClient {
     
     const formattedIngredients = ingredients.map(ing => ({
       materialMint: ing.materialMint,
-      quantity: new BN(ing.quantity)
+      quantity: new BN(ing.quantity),
+      sourceRecipe: ing.sourceRecipe ?? null
     }));
     
     try {
@@ -428,6 +768,8 @@ class SolanaClient {
         resultName,
         resultDescription,
         resultRarity,
+        new BN(resultQuantity),
+        refundPercentage,
         {
           accounts: {
             recipe: recipeKey.publicKey,
@@ -436,6 +778,8 @@ class SolanaClient {
             systemProgram: SystemProgram.programId,
             rent: SYSVAR_RENT_PUBKEY
           },
+          // Every recipe reachable through intermediate ingredients
+          remainingAccounts: stageRecipes.map(pubkey => ({ pubkey, isWritable: false, isSigner: false })),
           signers: [recipeKey]
         }
       );
@@ -448,7 +792,11 @@ class SolanaClient {
   }
 
   // Approve recipe
-  async approveRecipe(recipeAddress: PublicKey, isApproved: boolean): Promise<string> {
+  async approveRecipe(
+    recipeAddress: PublicKey,
+    isApproved: boolean,
+    stageRecipes: PublicKey[] = []
+  ): Promise<string> {
     this.checkPrograms();
     
     const [recipeAuthority] = await PublicKey.findProgramAddress(
@@ -465,7 +813,8 @@ class SolanaClient {
             recipeAuthority,
             authority: this.wallet!.publicKey!,
             systemProgram: SystemProgram.programId
-          }
+          },
+          remainingAccounts: stageRecipes.map(pubkey => ({ pubkey, isWritable: false, isSigner: false }))
         }
       );
       
@@ -580,48 +929,48 @@ class SolanaClient {
   // Start advanced crafting
   async startAdvancedCrafting(
     recipeAddress: PublicKey,
-    recipeCraftingAddress: PublicKey,
+    nonce: number,
+    randomnessRequest: PublicKey,
     materialInputs: { materialMint: PublicKey, amount: number }[]
   ): Promise<string> {
     this.checkPrograms();
     
+    // Sessions are keyed by a per-crafter nonce so a recipe can be crafted repeatedly
+    const nonceBuffer = new BN(nonce).toArrayLike(Buffer, 'le', 8);
+    
     const [craftingRecordAddress] = await PublicKey.findProgramAddress(
-      [Buffer.from('crafting_record'), recipeAddress.toBuffer(), this.wallet!.publicKey!.toBuffer()],
+      [Buffer.from('crafting_record'), recipeAddress.toBuffer(), this.wallet!.publicKey!.toBuffer(), nonceBuffer],
       CRAFTING_PROGRAM_ID
     );
     
-    const remainingAccounts = materialInputs.flatMap(input => {
-      const materialMintPubkey = input.materialMint;
-      const amountKey = new Keypair();
-      // Use a Keypair's public key to store quantity (this is a hack, and not recommended in practice)
-      // In a real implementation, a more appropriate way to pass parameters should be used
-      const amountBytes = Buffer.alloc(32);
-      amountBytes.writeBigUInt64LE(BigInt(input.amount), 0);
-      // @ts-ignore
-      amountKey.publicKey._bn = [...amountBytes];
-      
-      return [
-        {
-          pubkey: materialMintPubkey,
-          isWritable: false,
-          isSigner: false
-        },
-        {
-          pubkey: amountKey.publicKey,
-          isWritable: false,
-          isSigner: false
-        }
-      ];
-    });
+    const [recipeCraftingAddress] = await PublicKey.findProgramAddress(
+      [Buffer.from('crafting'), recipeAddress.toBuffer(), this.wallet!.publicKey!.toBuffer(), nonceBuffer],
+      RECIPE_PROGRAM_ID
+    );
+    
+    const materials = materialInputs.map(input => ({
+      materialMint: input.materialMint,
+      amount: new BN(input.amount)
+    }));
+    
+    // Prints of unique materials are matched to their recipe ingredient through their edition record
+    const remainingAccounts = (await Promise.all(materialInputs.map(async input => {
+      const materialEdition = await this.findMaterialEditionAddress(input.materialMint);
+      const editionInfo = await this.connection.getAccountInfo(materialEdition);
+      return editionInfo ? [{ pubkey: materialEdition, isWritable: false, isSigner: false }] : [];
+    }))).flat();
     
     try {
       const tx = await this.craftingProgram!.rpc.startCrafting(
         recipeAddress,
+        new BN(nonce),
+        materials,
         {
           accounts: {
             craftingRecord: craftingRecordAddress,
             recipeCrafting: recipeCraftingAddress,
             recipe: recipeAddress,
+            randomnessRequest,
             recipeProgram: RECIPE_PROGRAM_ID,
             crafter: this.wallet!.publicKey!,
             systemProgram: SystemProgram.programId,
@@ -680,34 +1029,41 @@ class SolanaClient {
   // Consume materials
   async consumeMaterials(
     craftingRecordAddress: PublicKey,
-    materialTransfers: { fromAccount: PublicKey, toAccount: PublicKey }[]
+    randomnessRequest: PublicKey,
+    materialTransfers: { fromAccount: PublicKey, materialMint: PublicKey }[]
   ): Promise<string> {
     this.checkPrograms();
     
-    const remainingAccounts = materialTransfers.flatMap(transfer => [
-      {
-        pubkey: transfer.fromAccount,
-        isWritable: true,
-        isSigner: false
-      },
-      {
-        pubkey: transfer.toAccount,
-        isWritable: true,
-        isSigner: false
-      },
-      {
-        pubkey: TOKEN_PROGRAM_ID,
-        isWritable: false,
-        isSigner: false
-      }
-    ]);
+    const remainingAccounts = (await Promise.all(materialTransfers.map(async transfer => {
+      const vault = await this.findMaterialVault(transfer.materialMint);
+      return [
+        {
+          pubkey: transfer.materialMint,
+          isWritable: false,
+          isSigner: false
+        },
+        {
+          pubkey: transfer.fromAccount,
+          isWritable: true,
+          isSigner: false
+        },
+        {
+          pubkey: vault,
+          isWritable: true,
+          isSigner: false
+        }
+      ];
+    }))).flat();
     
     try {
       const tx = await this.craftingProgram!.rpc.consumeMaterials(
         {
           accounts: {
             craftingRecord: craftingRecordAddress,
+            randomnessRequest,
             crafter: this.wallet!.publicKey!,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            token2022Program: TOKEN_2022_PROGRAM_ID,
             systemProgram: SystemProgram.programId
           },
           remainingAccounts
@@ -721,20 +1077,53 @@ class SolanaClient {
     }
   }
 
+  // Claim the refund share of escrowed materials after a failed craft
+  async claimCraftingRefund(
+    craftingRecordAddress: PublicKey,
+    recipeAddress: PublicKey,
+    materialMints: PublicKey[]
+  ): Promise<string> {
+    this.checkPrograms();
+    
+    const [craftingAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('crafting_authority')],
+      CRAFTING_PROGRAM_ID
+    );
+    
+    const remainingAccounts = await this.getEscrowAccounts(materialMints);
+    
+    try {
+      const tx = await this.craftingProgram!.rpc.claimRefund(
+        {
+          accounts: {
+            craftingRecord: craftingRecordAddress,
+            recipe: recipeAddress,
+            craftingAuthority,
+            crafter: this.wallet!.publicKey!,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            token2022Program: TOKEN_2022_PROGRAM_ID
+          },
+          remainingAccounts
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to claim crafting refund:', error);
+      throw error;
+    }
+  }
+
   // Complete advanced crafting
   async completeAdvancedCrafting(
     craftingRecordAddress: PublicKey,
     recipeCraftingAddress: PublicKey,
     recipeAddress: PublicKey,
-    resultParams: {
-      materialAuthority: PublicKey;
-      resultMaterial: PublicKey;
-      resultMint: PublicKey;
-      resultMetadata: PublicKey;
-      resultEdition: PublicKey;
-      resultTokenAccount: PublicKey;
-    },
-    resultUri: string
+    randomnessRequest: PublicKey,
+    materialMints: PublicKey[],
+    resultUri: string,
+    resultMode: MaterialMode = MaterialMode.Fungible,
+    resultTokenProgram: PublicKey = TOKEN_PROGRAM_ID
   ): Promise<string> {
     this.checkPrograms();
     
@@ -743,6 +1132,59 @@ class SolanaClient {
       CRAFTING_PROGRAM_ID
     );
     
+    const [materialAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('material_authority')],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const [minterPermit] = await PublicKey.findProgramAddress(
+      [Buffer.from('minter'), craftingAuthority.toBuffer()],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    // Each recipe mints its result through a single program-derived mint
+    const [resultMint] = await PublicKey.findProgramAddress(
+      [Buffer.from('result_mint'), recipeAddress.toBuffer()],
+      CRAFTING_PROGRAM_ID
+    );
+    
+    const [resultMaterial] = await PublicKey.findProgramAddress(
+      [Buffer.from('material'), resultMint.toBuffer()],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const resultMetadata = await this.findMetadataAddress(resultMint);
+    const { collectionMint, collectionMetadata, collectionMasterEdition } =
+      await this.getCollectionAccounts();
+    
+    // Unique results (legendary recipes) arrive as a fresh print instead of units of the result mint
+    let editionMint: Keypair | null = null;
+    let editionAccounts = null;
+    let resultTokenAccount = await getAssociatedTokenAddress(
+      resultMint,
+      this.wallet!.publicKey!,
+      false,
+      resultTokenProgram
+    );
+    if (resultMode === MaterialMode.Unique) {
+      const resultInfo = await this.connection.getAccountInfo(resultMaterial);
+      const editionNumber = resultInfo
+        ? (await this.getMaterial(resultMint)).currentSupply.toNumber() + 1
+        : 1;
+      
+      editionMint = Keypair.generate();
+      editionAccounts = await this.getPrintAccounts(
+        resultMint,
+        editionMint.publicKey,
+        editionNumber,
+        this.wallet!.publicKey!
+      );
+      resultTokenAccount = editionAccounts.editionTokenAccount;
+    }
+    
+    // Escrowed inputs are burned when the craft succeeds
+    const remainingAccounts = await this.getEscrowAccounts(materialMints);
+    
     try {
       const tx = await this.craftingProgram!.rpc.completeCrafting(
         resultUri,
@@ -751,21 +1193,38 @@ class SolanaClient {
             craftingRecord: craftingRecordAddress,
             recipeCrafting: recipeCraftingAddress,
             recipe: recipeAddress,
+            randomnessRequest,
             craftingAuthority,
-            materialAuthority: resultParams.materialAuthority,
-            resultMaterial: resultParams.resultMaterial,
-            resultMint: resultParams.resultMint,
-            resultMetadata: resultParams.resultMetadata,
-            resultEdition: resultParams.resultEdition,
-            resultTokenAccount: resultParams.resultTokenAccount,
+            materialAuthority,
+            minterPermit,
+            resultMaterial,
+            resultMint,
+            resultMetadata,
+            collectionMint,
+            collectionMetadata,
+            collectionMasterEdition,
+            resultTokenAccount,
+            resultMasterEdition: editionAccounts?.masterEdition ?? null,
+            resultMasterTokenAccount: editionAccounts?.masterTokenAccount ?? null,
+            editionMint: editionMint?.publicKey ?? null,
+            editionMetadata: editionAccounts?.editionMetadata ?? null,
+            edition: editionAccounts?.edition ?? null,
+            editionMarker: editionAccounts?.editionMarker ?? null,
+            materialEdition: editionAccounts?.materialEdition ?? null,
             recipeProgram: RECIPE_PROGRAM_ID,
             materialProgram: MATERIAL_PROGRAM_ID,
             crafter: this.wallet!.publicKey!,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
+            token2022Program: TOKEN_2022_PROGRAM_ID,
+            resultTokenProgram,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
             rent: SYSVAR_RENT_PUBKEY
-          }
+          },
+          remainingAccounts,
+          signers: editionMint ? [editionMint] : []
         }
       );
       
@@ -776,6 +1235,213 @@ class SolanaClient {
     }
   }
 
+  // Cancel a crafting session whose outcome is not known yet, returning escrowed materials
+  async cancelAdvancedCrafting(
+    craftingRecordAddress: PublicKey,
+    recipeCraftingAddress: PublicKey,
+    randomnessRequest: PublicKey,
+    materialMints: PublicKey[]
+  ): Promise<string> {
+    this.checkPrograms();
+    
+    const [craftingAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('crafting_authority')],
+      CRAFTING_PROGRAM_ID
+    );
+    
+    const remainingAccounts = await this.getEscrowAccounts(materialMints);
+    
+    try {
+      const tx = await this.craftingProgram!.rpc.cancelCrafting(
+        {
+          accounts: {
+            craftingRecord: craftingRecordAddress,
+            recipeCrafting: recipeCraftingAddress,
+            randomnessRequest,
+            craftingAuthority,
+            recipeProgram: RECIPE_PROGRAM_ID,
+            crafter: this.wallet!.publicKey!,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            token2022Program: TOKEN_2022_PROGRAM_ID
+          },
+          remainingAccounts
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to cancel crafting:', error);
+      throw error;
+    }
+  }
+
+  // Close a finished crafting session and reclaim its rent
+  async closeAdvancedCrafting(
+    craftingRecordAddress: PublicKey,
+    recipeCraftingAddress: PublicKey
+  ): Promise<string> {
+    this.checkPrograms();
+    
+    try {
+      const tx = await this.craftingProgram!.rpc.closeCrafting(
+        {
+          accounts: {
+            craftingRecord: craftingRecordAddress,
+            recipeCrafting: recipeCraftingAddress,
+            recipeProgram: RECIPE_PROGRAM_ID,
+            crafter: this.wallet!.publicKey!
+          }
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to close crafting:', error);
+      throw error;
+    }
+  }
+
+  // Escrow materials for `count` copies of a recipe in one instruction
+  async craftBatch(
+    recipeAddress: PublicKey,
+    nonce: number,
+    count: number,
+    randomnessRequest: PublicKey,
+    materialTokenAccounts: { materialMint: PublicKey, tokenAccount: PublicKey }[]
+  ): Promise<string> {
+    this.checkPrograms();
+    
+    const [craftingRecordAddress] = await PublicKey.findProgramAddress(
+      [
+        Buffer.from('crafting_record'),
+        recipeAddress.toBuffer(),
+        this.wallet!.publicKey!.toBuffer(),
+        new BN(nonce).toArrayLike(Buffer, 'le', 8)
+      ],
+      CRAFTING_PROGRAM_ID
+    );
+    
+    const remainingAccounts = (await Promise.all(materialTokenAccounts.map(async material => [
+      { pubkey: material.materialMint, isWritable: false, isSigner: false },
+      { pubkey: material.tokenAccount, isWritable: true, isSigner: false },
+      { pubkey: await this.findMaterialVault(material.materialMint), isWritable: true, isSigner: false }
+    ]))).flat();
+    
+    try {
+      const tx = await this.craftingProgram!.rpc.craftBatch(
+        recipeAddress,
+        new BN(nonce),
+        count,
+        {
+          accounts: {
+            craftingRecord: craftingRecordAddress,
+            recipe: recipeAddress,
+            randomnessRequest,
+            crafter: this.wallet!.publicKey!,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            token2022Program: TOKEN_2022_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY
+          },
+          remainingAccounts
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to start batch crafting:', error);
+      throw error;
+    }
+  }
+
+  // Roll every item in a batch, mint the successes and settle the escrow
+  async completeBatch(
+    craftingRecordAddress: PublicKey,
+    recipeAddress: PublicKey,
+    randomnessRequest: PublicKey,
+    materialMints: PublicKey[],
+    resultUri: string,
+    resultTokenProgram: PublicKey = TOKEN_PROGRAM_ID
+  ): Promise<string> {
+    this.checkPrograms();
+    
+    const [craftingAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('crafting_authority')],
+      CRAFTING_PROGRAM_ID
+    );
+    
+    const [materialAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('material_authority')],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const [minterPermit] = await PublicKey.findProgramAddress(
+      [Buffer.from('minter'), craftingAuthority.toBuffer()],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const [resultMint] = await PublicKey.findProgramAddress(
+      [Buffer.from('result_mint'), recipeAddress.toBuffer()],
+      CRAFTING_PROGRAM_ID
+    );
+    
+    const [resultMaterial] = await PublicKey.findProgramAddress(
+      [Buffer.from('material'), resultMint.toBuffer()],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    const resultMetadata = await this.findMetadataAddress(resultMint);
+    const { collectionMint, collectionMetadata, collectionMasterEdition } =
+      await this.getCollectionAccounts();
+    const resultTokenAccount = await getAssociatedTokenAddress(
+      resultMint,
+      this.wallet!.publicKey!,
+      false,
+      resultTokenProgram
+    );
+    
+    const remainingAccounts = await this.getEscrowAccounts(materialMints);
+    
+    try {
+      const tx = await this.craftingProgram!.rpc.completeBatch(
+        resultUri,
+        {
+          accounts: {
+            craftingRecord: craftingRecordAddress,
+            recipe: recipeAddress,
+            randomnessRequest,
+            craftingAuthority,
+            materialAuthority,
+            minterPermit,
+            resultMaterial,
+            resultMint,
+            resultMetadata,
+            collectionMint,
+            collectionMetadata,
+            collectionMasterEdition,
+            resultTokenAccount,
+            materialProgram: MATERIAL_PROGRAM_ID,
+            crafter: this.wallet!.publicKey!,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            token2022Program: TOKEN_2022_PROGRAM_ID,
+            resultTokenProgram,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+            rent: SYSVAR_RENT_PUBKEY
+          },
+          remainingAccounts
+        }
+      );
+      
+      return tx;
+    } catch (error) {
+      console.error('Failed to complete batch crafting:', error);
+      throw error;
+    }
+  }
+
   // Get crafting record
   async getCraftingRecord(craftingRecordAddress: PublicKey): Promise<CraftingData> {
     this.checkPrograms();
@@ -1124,6 +1790,87 @@ class SolanaClient {
     )[0];
   }
 
+  // Find the crafting program's escrow vault for a material
+  async findMaterialVault(materialMint: PublicKey): Promise<PublicKey> {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('vault'), materialMint.toBuffer()],
+      CRAFTING_PROGRAM_ID
+    )[0];
+  }
+
+  // Mint, vault and crafter token account for each escrowed material, in recipe input order
+  async getEscrowAccounts(materialMints: PublicKey[]) {
+    return (await Promise.all(materialMints.map(async materialMint => {
+      const vault = await this.findMaterialVault(materialMint);
+      const tokenAccount = await getAssociatedTokenAddress(
+        materialMint,
+        this.wallet!.publicKey!
+      );
+      return [
+        { pubkey: materialMint, isWritable: true, isSigner: false },
+        { pubkey: vault, isWritable: true, isSigner: false },
+        { pubkey: tokenAccount, isWritable: true, isSigner: false }
+      ];
+    }))).flat();
+  }
+
+  // Collection mint, metadata and master edition shared by every material
+  async getCollectionAccounts() {
+    const [collectionMint] = await PublicKey.findProgramAddress(
+      [Buffer.from('collection_mint')],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    return {
+      collectionMint,
+      collectionMetadata: await this.findMetadataAddress(collectionMint),
+      collectionMasterEdition: await this.findEditionAddress(collectionMint)
+    };
+  }
+
+  // Find the material program's record of a unique material's print
+  async findMaterialEditionAddress(editionMint: PublicKey): Promise<PublicKey> {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('edition'), editionMint.toBuffer()],
+      MATERIAL_PROGRAM_ID
+    )[0];
+  }
+
+  // Master edition, edition marker and new print accounts for one print of a unique material
+  async getPrintAccounts(
+    masterMint: PublicKey,
+    editionMint: PublicKey,
+    editionNumber: number,
+    receiver: PublicKey
+  ) {
+    const [materialAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('material_authority')],
+      MATERIAL_PROGRAM_ID
+    );
+    
+    // Each marker account tracks 248 edition numbers
+    const [editionMarker] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('metadata'),
+        TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+        masterMint.toBuffer(),
+        Buffer.from('edition'),
+        Buffer.from(Math.floor(editionNumber / 248).toString()),
+      ],
+      TOKEN_METADATA_PROGRAM_ID
+    );
+    
+    return {
+      masterEdition: await this.findEditionAddress(masterMint),
+      masterTokenAccount: await getAssociatedTokenAddress(masterMint, materialAuthority),
+      editionMetadata: await this.findMetadataAddress(editionMint),
+      edition: await this.findEditionAddress(editionMint),
+      editionMarker,
+      editionTokenAccount: await getAssociatedTokenAddress(editionMint, receiver),
+      materialEdition: await this.findMaterialEditionAddress(editionMint)
+    };
+  }
+
   // Find Edition address
   async findEditionAddress(mint: PublicKey): Promise<PublicKey> {
     return PublicKey.findProgramAddressSync(
@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+
+// Rarity shared by every MagicVial program, ordered from most to least common
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    pub const LEN: usize = 1;
+
+    // Level on the 1-5 scale used by the earlier u8 encoding
+    pub fn level(&self) -> u8 {
+        *self as u8 + 1
+    }
+
+    pub fn from_level(level: u8) -> Option<Self> {
+        match level {
+            1 => Some(Rarity::Common),
+            2 => Some(Rarity::Uncommon),
+            3 => Some(Rarity::Rare),
+            4 => Some(Rarity::Epic),
+            5 => Some(Rarity::Legendary),
+            _ => None,
+        }
+    }
+
+    // Parses the free-form names stored by the earlier String encoding
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "common" => Some(Rarity::Common),
+            "uncommon" => Some(Rarity::Uncommon),
+            "rare" => Some(Rarity::Rare),
+            "epic" => Some(Rarity::Epic),
+            "legendary" => Some(Rarity::Legendary),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rarity::Common => "Common",
+            Rarity::Uncommon => "Uncommon",
+            Rarity::Rare => "Rare",
+            Rarity::Epic => "Epic",
+            Rarity::Legendary => "Legendary",
+        }
+    }
+
+    // The next rarity up, or None at Legendary
+    pub fn next(&self) -> Option<Self> {
+        Self::from_level(self.level() + 1)
+    }
+}
+
+// Material category shared by every MagicVial program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaterialCategory {
+    Basic,
+    Rare,
+    Seasonal,
+    Mysterious,
+    Crafted,
+}
+
+impl MaterialCategory {
+    pub const LEN: usize = 1;
+
+    // Code on the 1-based scale used by the earlier u8 encoding
+    pub fn code(&self) -> u8 {
+        *self as u8 + 1
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(MaterialCategory::Basic),
+            2 => Some(MaterialCategory::Rare),
+            3 => Some(MaterialCategory::Seasonal),
+            4 => Some(MaterialCategory::Mysterious),
+            5 => Some(MaterialCategory::Crafted),
+            _ => None,
+        }
+    }
+
+    // Parses the free-form names stored by the earlier String encoding
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "basic" => Some(MaterialCategory::Basic),
+            "rare" => Some(MaterialCategory::Rare),
+            "seasonal" => Some(MaterialCategory::Seasonal),
+            "mysterious" => Some(MaterialCategory::Mysterious),
+            "crafted" => Some(MaterialCategory::Crafted),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MaterialCategory::Basic => "Basic",
+            MaterialCategory::Rare => "Rare",
+            MaterialCategory::Seasonal => "Seasonal",
+            MaterialCategory::Mysterious => "Mysterious",
+            MaterialCategory::Crafted => "Crafted",
+        }
+    }
+}
+
+// How a material's tokens are issued: stackable units of one mint, or numbered prints of a master edition
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaterialMode {
+    Fungible,
+    Unique,
+}
+
+impl MaterialMode {
+    pub const LEN: usize = 1;
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MaterialMode::Fungible => "Fungible",
+            MaterialMode::Unique => "Unique",
+        }
+    }
+}
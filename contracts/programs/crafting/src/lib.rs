@@ -1,14 +1,28 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token};
+use anchor_spl::token_2022::{self, Token2022};
+use anchor_spl::token_interface::{self, Burn, TokenInterface, TransferChecked};
 use solana_program::sysvar::clock::Clock;
 use material_nft::program::MaterialNft as MaterialNftProgram;
-use material_nft::cpi::accounts::MintMaterial;
+use material_nft::cpi::accounts::{
+    CreateMaterial, CreateMaterial2022, MintMaterial, PrintMaterial, VerifyCreator,
+};
+use material_nft::{CreatorShare, EditionAttribute, TransferRule};
 use recipe::program::Recipe as RecipeProgram;
-use recipe::cpi::accounts::{CompleteCrafting, StartCrafting};
-use recipe::CraftingStatus;
+use recipe::cpi::accounts::{CancelCrafting, CloseCrafting, CompleteCrafting, StartCrafting};
+use recipe::{CraftingStatus, Ingredient};
+use randomness::{RandomnessRequest, RequestStatus};
+use magicvial_types::{MaterialCategory, MaterialMode};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
+// Metadata used when a recipe's result material is registered on first success
+const CRAFTED_MATERIAL_SYMBOL: &str = "MVCRAFT";
+const CRAFTED_MATERIAL_ROYALTY_BPS: u16 = 500;
+const CRAFTED_MATERIAL_PLATFORM_SHARE: u8 = 5;
+
 #[program]
 pub mod crafting {
     use super::*;
@@ -25,43 +39,61 @@ pub mod crafting {
         
         Ok(())
     }
+    
+    pub fn init_material_vault(
+        ctx: Context<InitMaterialVault>,
+    ) -> Result<()> {
+        msg!("Material vault created for mint: {}", ctx.accounts.mint.key());
+        
+        Ok(())
+    }
 
     pub fn start_crafting(
         ctx: Context<StartCraftingProcess>,
         recipe_id: Pubkey,
+        nonce: u64,
+        materials: Vec<MaterialInput>,
     ) -> Result<()> {
+        let recipe = &ctx.accounts.recipe;
+        
+        // Inputs must cover the recipe's ingredients exactly
+        // Remaining accounts: the MaterialEdition of each input that is a print of a unique material
+        validate_material_inputs(&materials, &recipe.ingredients, ctx.remaining_accounts)?;
+        
+        // Every unique result is its own print, so such recipes yield exactly one
+        require!(
+            recipe.result_mode == MaterialMode::Fungible ||
+            recipe.result_quantity == 1,
+            CraftingError::UniqueResultQuantity
+        );
+        
         // Call to the recipe program to start crafting
         let cpi_program = ctx.accounts.recipe_program.to_account_info();
         let cpi_accounts = StartCrafting {
             crafting: ctx.accounts.recipe_crafting.to_account_info(),
             recipe: ctx.accounts.recipe.to_account_info(),
+            randomness_request: ctx.accounts.randomness_request.to_account_info(),
             crafter: ctx.accounts.crafter.to_account_info(),
             system_program: ctx.accounts.system_program.to_account_info(),
             rent: ctx.accounts.rent.to_account_info(),
         };
         
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        recipe::cpi::start_crafting(cpi_ctx)?;
+        recipe::cpi::start_crafting(cpi_ctx, nonce)?;
         
         // Create local crafting record
         let crafting_record = &mut ctx.accounts.crafting_record;
         crafting_record.recipe = recipe_id;
         crafting_record.recipe_crafting = ctx.accounts.recipe_crafting.key();
+        crafting_record.randomness_request = ctx.accounts.randomness_request.key();
         crafting_record.crafter = ctx.accounts.crafter.key();
+        crafting_record.nonce = nonce;
         crafting_record.start_time = Clock::get()?.unix_timestamp;
         crafting_record.status = CraftingStatus::InProgress;
+        crafting_record.batch_size = 1;
         
         // Store input materials for verification during completion
-        for material_input in ctx.remaining_accounts.chunks(2) {
-            let material_mint = material_input[0].key();
-            let material_amount = material_input[1].key().to_bytes(); // Hacky way to pass amount
-            let amount = u64::from_le_bytes(material_amount[0..8].try_into().unwrap());
-            
-            crafting_record.input_materials.push(MaterialInput {
-                material_mint,
-                amount,
-            });
-        }
+        crafting_record.input_materials = materials;
         
         msg!("Crafting process started for recipe: {}", recipe_id);
         
@@ -89,7 +121,8 @@ pub mod crafting {
             // Find the material in our requirements
             if let Some(pos) = remaining_materials.iter().position(|m| m.material_mint == material_mint) {
                 // Verify the token account belongs to the crafter
-                let token_account = Account::<TokenAccount>::try_from(material_token_account)?;
+                let token_account =
+                    InterfaceAccount::<token_interface::TokenAccount>::try_from(material_token_account)?;
                 require!(
                     token_account.owner == ctx.accounts.crafter.key(),
                     CraftingError::InvalidTokenAccount
@@ -134,37 +167,25 @@ pub mod crafting {
             CraftingError::MaterialsNotVerified
         );
         
-        // Consume the materials by transferring tokens to the vault
-        for (i, material_info) in ctx.remaining_accounts.chunks(3).enumerate() {
-            let material_token_account = &material_info[0];
-            let destination_token_account = &material_info[1];
-            let token_program = &material_info[2];
-            
-            let amount = crafting_record.input_materials[i].amount;
-            
-            // Transfer tokens to vault
-            let cpi_accounts = Transfer {
-                from: material_token_account.to_account_info(),
-                to: destination_token_account.to_account_info(),
-                authority: ctx.accounts.crafter.to_account_info(),
-            };
-            let cpi_program = token_program.to_account_info();
-            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-            token::transfer(cpi_ctx, amount)?;
-        }
+        // Consume the materials by transferring tokens into escrow
+        escrow_materials(
+            &TokenPrograms::new(&ctx.accounts.token_program, &ctx.accounts.token_2022_program),
+            &ctx.accounts.crafter.to_account_info(),
+            ctx.remaining_accounts,
+            &crafting_record.input_materials,
+        )?;
         
         // Update crafting record
         let mutable_record = &mut ctx.accounts.crafting_record;
         mutable_record.materials_consumed = true;
         
-        msg!("Materials consumed for crafting: {}", crafting_record.recipe);
+        msg!("Materials consumed for crafting: {}", mutable_record.recipe);
         
         Ok(())
     }
     
     pub fn complete_crafting(
         ctx: Context<CompleteCraftingProcess>,
-        result_uri: String,
     ) -> Result<()> {
         let crafting_record = &ctx.accounts.crafting_record;
         
@@ -177,12 +198,14 @@ pub mod crafting {
             crafting_record.materials_consumed,
             CraftingError::MaterialsNotConsumed
         );
+        let recipe_key = crafting_record.recipe;
         
         // Call to the recipe program to complete crafting
         let cpi_program = ctx.accounts.recipe_program.to_account_info();
         let cpi_accounts = CompleteCrafting {
             crafting: ctx.accounts.recipe_crafting.to_account_info(),
             recipe: ctx.accounts.recipe.to_account_info(),
+            randomness_request: ctx.accounts.randomness_request.to_account_info(),
             crafter: ctx.accounts.crafter.to_account_info(),
             system_program: ctx.accounts.system_program.to_account_info(),
         };
@@ -190,72 +213,861 @@ pub mod crafting {
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         recipe::cpi::complete_crafting(cpi_ctx)?;
         
-        // Check if crafting was successful
-        let recipe_crafting = ctx.accounts.recipe_crafting.to_account_info();
-        let crafting_data = recipe_crafting.data.borrow();
-        
-        // In a real implementation, we'd deserialize the account
-        // For now, we'll use a simplification and just check if the status byte at a specific position is 1 (Completed)
-        // let crafting: recipe::Crafting = recipe::Crafting::try_deserialize(&mut data.as_ref())?;
-        let crafting_status_successful = crafting_data[80] == 1; // Assuming status enum is at position 80
+        // Reload the session so we see the status written by the recipe program
+        ctx.accounts.recipe_crafting.reload()?;
+        let crafting_status_successful =
+            ctx.accounts.recipe_crafting.status == CraftingStatus::Completed;
         
         // Update our crafting record
         let mutable_record = &mut ctx.accounts.crafting_record;
-        mutable_record.status = if crafting_status_successful {
-            CraftingStatus::Completed
-        } else {
-            CraftingStatus::Failed
-        };
+        mutable_record.status = ctx.accounts.recipe_crafting.status;
         mutable_record.completion_time = Some(Clock::get()?.unix_timestamp);
         
         // If successful, mint the result item
         if crafting_status_successful {
-            // Get result metadata from recipe
-            let recipe_data = ctx.accounts.recipe.try_borrow_data()?;
+            let recipe = &ctx.accounts.recipe;
+            
+            mint_crafted_result(
+                CraftedResultAccounts {
+                    crafting_authority: ctx.accounts.crafting_authority.to_account_info(),
+                    material_authority: ctx.accounts.material_authority.to_account_info(),
+                    result_material: ctx.accounts.result_material.to_account_info(),
+                    result_mint: ctx.accounts.result_mint.to_account_info(),
+                    result_metadata: ctx.accounts.result_metadata.to_account_info(),
+                    collection_mint: ctx.accounts.collection_mint.to_account_info(),
+                    collection_metadata: ctx.accounts.collection_metadata.to_account_info(),
+                    collection_master_edition: ctx.accounts.collection_master_edition.to_account_info(),
+                    result_token_account: ctx.accounts.result_token_account.to_account_info(),
+                    material_program: ctx.accounts.material_program.to_account_info(),
+                    crafter: ctx.accounts.crafter.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.result_token_program.to_account_info(),
+                    associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                    token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+                    sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                    editions: ctx.accounts.edition_accounts(),
+                },
+                ctx.accounts.crafting_authority.bump,
+                ctx.bumps.result_mint,
+                recipe,
+                recipe.result_quantity,
+            )?;
+            
+            // The escrowed materials were used up by the craft
+            settle_escrow(
+                &TokenPrograms::new(&ctx.accounts.token_program, &ctx.accounts.token_2022_program),
+                &ctx.accounts.crafting_authority.to_account_info(),
+                ctx.accounts.crafting_authority.bump,
+                ctx.remaining_accounts,
+                &ctx.accounts.crafting_record.input_materials,
+                |_| 0,
+                ctx.accounts.crafter.key(),
+            )?;
+            ctx.accounts.crafting_record.materials_settled = true;
+            
+            msg!(
+                "Crafting completed successfully: {} x{} ({})",
+                recipe.result_name,
+                recipe.result_quantity,
+                recipe.result_rarity.name()
+            );
+        } else {
+            // Escrowed materials stay in the vault until the crafter claims the refund
+            msg!("Crafting failed: {}", recipe_key);
+        }
+        
+        Ok(())
+    }
+    
+    pub fn claim_refund(
+        ctx: Context<ClaimRefund>,
+    ) -> Result<()> {
+        let crafting_record = &ctx.accounts.crafting_record;
+        
+        // Only failed crafts are refunded
+        require!(
+            crafting_record.status == CraftingStatus::Failed,
+            CraftingError::InvalidCraftingStatus
+        );
+        
+        // Claiming twice is a no-op
+        if crafting_record.materials_settled {
+            msg!("Refund already claimed for crafting: {}", crafting_record.recipe);
+            return Ok(());
+        }
+        
+        // Return the recipe's refund share to the crafter and burn the rest
+        settle_escrow(
+            &TokenPrograms::new(&ctx.accounts.token_program, &ctx.accounts.token_2022_program),
+            &ctx.accounts.crafting_authority.to_account_info(),
+            ctx.accounts.crafting_authority.bump,
+            ctx.remaining_accounts,
+            &crafting_record.input_materials,
+            |amount| refund_share(amount, ctx.accounts.recipe.refund_percentage),
+            ctx.accounts.crafter.key(),
+        )?;
+        
+        let mutable_record = &mut ctx.accounts.crafting_record;
+        mutable_record.materials_settled = true;
+        
+        msg!(
+            "Refunded {}% of materials for crafting: {}",
+            ctx.accounts.recipe.refund_percentage,
+            mutable_record.recipe
+        );
+        
+        Ok(())
+    }
+    
+    pub fn cancel_crafting(
+        ctx: Context<CancelCraftingProcess>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.crafting_record.status == CraftingStatus::InProgress,
+            CraftingError::InvalidCraftingStatus
+        );
+        
+        // Escrowed materials only come back while the outcome is unknown; a session that
+        // never escrowed anything can be cancelled at any time
+        if ctx.accounts.crafting_record.materials_consumed {
+            let randomness_request: Account<RandomnessRequest> =
+                Account::try_from(&ctx.accounts.randomness_request.to_account_info())?;
+            require!(
+                randomness_request.status == RequestStatus::Pending,
+                CraftingError::RandomnessAlreadyFulfilled
+            );
+        }
+        if !ctx.accounts.crafting_record.is_batch {
+            let cpi_program = ctx.accounts.recipe_program.to_account_info();
+            let cpi_accounts = CancelCrafting {
+                crafting: ctx.accounts.recipe_crafting.to_account_info(),
+                randomness_request: ctx.accounts.randomness_request.to_account_info(),
+                crafter: ctx.accounts.crafter.to_account_info(),
+            };
+            
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            recipe::cpi::cancel_crafting(cpi_ctx)?;
+        }
+        
+        // Return everything held in escrow
+        let crafting_record = &ctx.accounts.crafting_record;
+        if crafting_record.materials_consumed && !crafting_record.materials_settled {
+            settle_escrow(
+                &TokenPrograms::new(&ctx.accounts.token_program, &ctx.accounts.token_2022_program),
+                &ctx.accounts.crafting_authority.to_account_info(),
+                ctx.accounts.crafting_authority.bump,
+                ctx.remaining_accounts,
+                &crafting_record.input_materials,
+                |amount| amount,
+                ctx.accounts.crafter.key(),
+            )?;
+        }
+        
+        let mutable_record = &mut ctx.accounts.crafting_record;
+        mutable_record.status = CraftingStatus::Cancelled;
+        mutable_record.completion_time = Some(Clock::get()?.unix_timestamp);
+        mutable_record.materials_settled = mutable_record.materials_consumed;
+        
+        msg!("Crafting cancelled: {}", mutable_record.recipe);
+        
+        Ok(())
+    }
+    
+    pub fn close_crafting(
+        ctx: Context<CloseCraftingProcess>,
+    ) -> Result<()> {
+        let crafting_record = &ctx.accounts.crafting_record;
+        
+        // Escrow must be released before the record disappears
+        require!(
+            crafting_record.status != CraftingStatus::InProgress,
+            CraftingError::InvalidCraftingStatus
+        );
+        require!(
+            !crafting_record.materials_consumed || crafting_record.materials_settled,
+            CraftingError::EscrowNotSettled
+        );
+        
+        // Batches have no recipe program session to close
+        if !crafting_record.is_batch {
+            let cpi_program = ctx.accounts.recipe_program.to_account_info();
+            let cpi_accounts = CloseCrafting {
+                crafting: ctx.accounts.recipe_crafting.to_account_info(),
+                crafter: ctx.accounts.crafter.to_account_info(),
+            };
             
-            // In a real implementation, we'd properly deserialize the recipe
-            // For now, we'll simulate that we got the result info from the recipe
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            recipe::cpi::close_crafting(cpi_ctx)?;
+        }
+        
+        msg!("Crafting record closed: {}", crafting_record.recipe);
+        
+        Ok(())
+    }
+    
+    pub fn craft_batch(
+        ctx: Context<CraftBatch>,
+        recipe_id: Pubkey,
+        nonce: u64,
+        count: u16,
+    ) -> Result<()> {
+        let recipe = &ctx.accounts.recipe;
+        
+        require!(
+            count > 0 && count <= CraftingRecord::MAX_BATCH_SIZE,
+            CraftingError::InvalidBatchSize
+        );
+        require!(recipe.is_approved && recipe.is_enabled, CraftingError::RecipeUnavailable);
+        validate_intermediate_mints(&recipe.ingredients)?;
+        
+        // Batches mint many units of one result, which only fungible results support
+        require!(
+            recipe.result_mode == MaterialMode::Fungible,
+            CraftingError::UniqueResultInBatch
+        );
+        
+        // Each ingredient is needed once per item in the batch; prints of unique
+        // materials are separate mints, so recipes using them are crafted one at a time
+        let mut materials = Vec::with_capacity(recipe.ingredients.len());
+        for ingredient in &recipe.ingredients {
+            materials.push(MaterialInput {
+                material_mint: ingredient.material_mint,
+                amount: ingredient
+                    .quantity
+                    .checked_mul(count as u64)
+                    .ok_or(CraftingError::ArithmeticOverflow)?,
+            });
+        }
+        
+        // Everything goes into escrow before the outcomes are known
+        escrow_materials(
+            &TokenPrograms::new(&ctx.accounts.token_program, &ctx.accounts.token_2022_program),
+            &ctx.accounts.crafter.to_account_info(),
+            ctx.remaining_accounts,
+            &materials,
+        )?;
+        
+        let crafting_record = &mut ctx.accounts.crafting_record;
+        crafting_record.recipe = recipe_id;
+        crafting_record.recipe_crafting = Pubkey::default();
+        crafting_record.randomness_request = ctx.accounts.randomness_request.key();
+        crafting_record.crafter = ctx.accounts.crafter.key();
+        crafting_record.nonce = nonce;
+        crafting_record.start_time = Clock::get()?.unix_timestamp;
+        crafting_record.status = CraftingStatus::InProgress;
+        crafting_record.materials_verified = true;
+        crafting_record.materials_consumed = true;
+        crafting_record.input_materials = materials;
+        crafting_record.is_batch = true;
+        crafting_record.batch_size = count;
+        
+        msg!("Batch crafting started: {} x{}", recipe.name, count);
+        
+        Ok(())
+    }
+    
+    pub fn complete_batch(
+        ctx: Context<CompleteBatch>,
+    ) -> Result<()> {
+        let crafting_record = &ctx.accounts.crafting_record;
+        let recipe = &ctx.accounts.recipe;
+        
+        require!(
+            crafting_record.status == CraftingStatus::InProgress,
+            CraftingError::InvalidCraftingStatus
+        );
+        
+        // The whole batch brews for one recipe crafting time
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= crafting_record.start_time + recipe.crafting_time as i64,
+            CraftingError::CraftingNotReady
+        );
+        
+        // Roll every item independently from the one committed request
+        let randomness = ctx.accounts.randomness_request.value()?;
+        let count = crafting_record.batch_size;
+        let mut batch_results = Vec::with_capacity(count as usize);
+        let mut domain = b"crafting_batch_success".to_vec();
+        let prefix_len = domain.len();
+        for i in 0..count {
+            domain.truncate(prefix_len);
+            domain.extend_from_slice(&i.to_le_bytes());
+            batch_results.push(randomness::roll_percent(&randomness, &domain) < recipe.success_rate);
+        }
+        
+        let successes = batch_results.iter().filter(|&&success| success).count() as u64;
+        let failures = count as u64 - successes;
+        
+        if successes > 0 {
+            let amount = recipe
+                .result_quantity
+                .checked_mul(successes)
+                .ok_or(CraftingError::ArithmeticOverflow)?;
             
-            // Call to material NFT program to mint the result
-            let seeds = &[
-                b"crafting_authority".as_ref(),
-                &[ctx.accounts.crafting_authority.bump],
+            mint_crafted_result(
+                CraftedResultAccounts {
+                    crafting_authority: ctx.accounts.crafting_authority.to_account_info(),
+                    material_authority: ctx.accounts.material_authority.to_account_info(),
+                    result_material: ctx.accounts.result_material.to_account_info(),
+                    result_mint: ctx.accounts.result_mint.to_account_info(),
+                    result_metadata: ctx.accounts.result_metadata.to_account_info(),
+                    collection_mint: ctx.accounts.collection_mint.to_account_info(),
+                    collection_metadata: ctx.accounts.collection_metadata.to_account_info(),
+                    collection_master_edition: ctx.accounts.collection_master_edition.to_account_info(),
+                    result_token_account: ctx.accounts.result_token_account.to_account_info(),
+                    material_program: ctx.accounts.material_program.to_account_info(),
+                    crafter: ctx.accounts.crafter.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.result_token_program.to_account_info(),
+                    associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                    token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+                    sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                    editions: None,
+                },
+                ctx.accounts.crafting_authority.bump,
+                ctx.bumps.result_mint,
+                recipe,
+                amount,
+            )?;
+        }
+        
+        // Successful items use up their materials; failed items get the recipe's refund share
+        settle_escrow(
+            &TokenPrograms::new(&ctx.accounts.token_program, &ctx.accounts.token_2022_program),
+            &ctx.accounts.crafting_authority.to_account_info(),
+            ctx.accounts.crafting_authority.bump,
+            ctx.remaining_accounts,
+            &crafting_record.input_materials,
+            |amount| refund_share(amount / count as u64 * failures, recipe.refund_percentage),
+            ctx.accounts.crafter.key(),
+        )?;
+        
+        let mutable_record = &mut ctx.accounts.crafting_record;
+        mutable_record.status = if successes > 0 {
+            CraftingStatus::Completed
+        } else {
+            CraftingStatus::Failed
+        };
+        mutable_record.completion_time = Some(current_time);
+        mutable_record.materials_settled = true;
+        mutable_record.batch_results = batch_results;
+        
+        msg!(
+            "Batch crafting completed: {} of {} succeeded for {}",
+            successes,
+            count,
+            recipe.name
+        );
+        
+        Ok(())
+    }
+}
+
+// Helper function to derive the escrow vault for a material mint
+fn vault_address(material_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"vault".as_ref(), material_mint.as_ref()], &crate::ID).0
+}
+
+// The two token programs a material's mint may belong to
+struct TokenPrograms<'info> {
+    token: AccountInfo<'info>,
+    token_2022: AccountInfo<'info>,
+}
+
+impl<'info> TokenPrograms<'info> {
+    fn new(token: &Program<'info, Token>, token_2022: &Program<'info, Token2022>) -> Self {
+        Self {
+            token: token.to_account_info(),
+            token_2022: token_2022.to_account_info(),
+        }
+    }
+    
+    // The program that owns `mint`, so every transfer and burn goes where the mint lives
+    fn for_mint(&self, mint: &AccountInfo<'info>) -> Result<AccountInfo<'info>> {
+        if *mint.owner == self.token.key() {
+            Ok(self.token.clone())
+        } else if *mint.owner == self.token_2022.key() {
+            Ok(self.token_2022.clone())
+        } else {
+            err!(CraftingError::InvalidTokenProgram)
+        }
+    }
+}
+
+// Helper function to move input materials from the crafter into escrow
+// Remaining accounts: (mint, crafter token account, vault) per input material
+// Transfer-hook mints need their hook's extra accounts, so they cannot be escrowed here
+fn escrow_materials<'info>(
+    token_programs: &TokenPrograms<'info>,
+    crafter: &AccountInfo<'info>,
+    escrow_accounts: &[AccountInfo<'info>],
+    materials: &[MaterialInput],
+) -> Result<()> {
+    require!(
+        escrow_accounts.len() == materials.len() * 3,
+        CraftingError::MissingMaterials
+    );
+    
+    for (material, accounts) in materials.iter().zip(escrow_accounts.chunks(3)) {
+        let mint = &accounts[0];
+        let material_token_account = &accounts[1];
+        let vault = &accounts[2];
+        
+        require!(mint.key() == material.material_mint, CraftingError::MaterialMismatch);
+        let token_program = token_programs.for_mint(mint)?;
+        let decimals = InterfaceAccount::<token_interface::Mint>::try_from(mint)?.decimals;
+        
+        // Tokens must come from the crafter's account for this material
+        let token_account =
+            InterfaceAccount::<token_interface::TokenAccount>::try_from(material_token_account)?;
+        require!(token_account.owner == crafter.key(), CraftingError::InvalidTokenAccount);
+        require!(token_account.mint == material.material_mint, CraftingError::MaterialMismatch);
+        require!(
+            token_account.amount >= material.amount,
+            CraftingError::InsufficientMaterialBalance
+        );
+        
+        // And go only to this program's vault for the same mint
+        require!(vault.key() == vault_address(&material.material_mint), CraftingError::InvalidVault);
+        
+        let cpi_accounts = TransferChecked {
+            from: material_token_account.clone(),
+            mint: mint.clone(),
+            to: vault.clone(),
+            authority: crafter.clone(),
+        };
+        let cpi_ctx = CpiContext::new(token_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, material.amount, decimals)?;
+    }
+    
+    Ok(())
+}
+
+// Accounts needed to register and mint a recipe's result material
+struct CraftedResultAccounts<'info> {
+    crafting_authority: AccountInfo<'info>,
+    material_authority: AccountInfo<'info>,
+    result_material: AccountInfo<'info>,
+    result_mint: AccountInfo<'info>,
+    result_metadata: AccountInfo<'info>,
+    collection_mint: AccountInfo<'info>,
+    collection_metadata: AccountInfo<'info>,
+    collection_master_edition: AccountInfo<'info>,
+    result_token_account: AccountInfo<'info>,
+    material_program: AccountInfo<'info>,
+    crafter: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    associated_token_program: AccountInfo<'info>,
+    token_metadata_program: AccountInfo<'info>,
+    sysvar_instructions: AccountInfo<'info>,
+    rent: AccountInfo<'info>,
+    editions: Option<EditionAccounts<'info>>,
+}
+
+// Accounts needed to print a unique result material
+struct EditionAccounts<'info> {
+    master_edition: AccountInfo<'info>,
+    master_token_account: AccountInfo<'info>,
+    edition_mint: AccountInfo<'info>,
+    edition_metadata: AccountInfo<'info>,
+    edition: AccountInfo<'info>,
+    edition_marker: AccountInfo<'info>,
+    material_edition: AccountInfo<'info>,
+}
+
+// Helper function to create a recipe's result mint before its first success
+// A unique result hands mint authority to its master edition, so the mint is only checked by address afterwards
+fn init_result_mint<'info>(
+    accounts: &CraftedResultAccounts<'info>,
+    recipe: &Pubkey,
+    result_mint_bump: u8,
+) -> Result<()> {
+    let seeds = &[
+        b"result_mint".as_ref(),
+        recipe.as_ref(),
+        &[result_mint_bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let cpi_accounts = system_program::CreateAccount {
+        from: accounts.crafter.clone(),
+        to: accounts.result_mint.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(accounts.system_program.clone(), cpi_accounts, signer);
+    system_program::create_account(
+        cpi_ctx,
+        Rent::get()?.minimum_balance(Mint::LEN),
+        Mint::LEN as u64,
+        &token::ID,
+    )?;
+    
+    let cpi_accounts = token::InitializeMint2 {
+        mint: accounts.result_mint.clone(),
+    };
+    let cpi_ctx = CpiContext::new(accounts.token_program.clone(), cpi_accounts);
+    token::initialize_mint2(cpi_ctx, 0, &accounts.material_authority.key(), None)
+}
+
+// Helper function to mint a recipe's result, registering the material on first success
+fn mint_crafted_result<'info>(
+    accounts: CraftedResultAccounts<'info>,
+    crafting_authority_bump: u8,
+    result_mint_bump: u8,
+    recipe: &Account<'info, recipe::Recipe>,
+    amount: u64,
+) -> Result<()> {
+    // The crafting authority signs for the material program
+    let seeds = &[
+        b"crafting_authority".as_ref(),
+        &[crafting_authority_bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let recipe_key = recipe.key();
+    
+    let mode = if accounts.result_material.data_is_empty() {
+        let mode = recipe.result_mode;
+        
+        // The platform keeps its share of secondary royalties and the recipe creator earns
+        // the rest, within the current royalty cap. The crafting authority stays on as a
+        // zero-share co-creator so crafted materials can be told apart
+        let material_authority: Account<material_nft::MaterialAuthority> =
+            Account::try_from(&accounts.material_authority)?;
+        let seller_fee_basis_points = CRAFTED_MATERIAL_ROYALTY_BPS.min(material_authority.max_royalty_bps);
+        let creators = vec![
+            CreatorShare {
+                address: accounts.material_authority.key(),
+                share: CRAFTED_MATERIAL_PLATFORM_SHARE,
+            },
+            CreatorShare {
+                address: recipe.creator,
+                share: 100 - CRAFTED_MATERIAL_PLATFORM_SHARE,
+            },
+            CreatorShare {
+                address: accounts.crafting_authority.key(),
+                share: 0,
+            },
+        ];
+        
+        if accounts.token_program.key() == token_2022::ID {
+            // Token-2022 results keep their metadata, collection and royalty split on the mint,
+            // which the material program creates with its extensions; the result mint PDA signs
+            // for its own creation and the crafting authority is verified as it signs too
+            require!(mode == MaterialMode::Fungible, CraftingError::UniqueResultOnToken2022);
+            let result_mint_seeds = &[
+                b"result_mint".as_ref(),
+                recipe_key.as_ref(),
+                &[result_mint_bump],
             ];
-            let signer = &[&seeds[..]];
+            let signers = &[&seeds[..], &result_mint_seeds[..]];
+            
+            let cpi_accounts = CreateMaterial2022 {
+                material: accounts.result_material.clone(),
+                material_authority: accounts.material_authority.clone(),
+                mint: accounts.result_mint.clone(),
+                collection_mint: accounts.collection_mint.clone(),
+                authority: accounts.crafting_authority.clone(),
+                payer: accounts.crafter.clone(),
+                token_program: accounts.token_program.clone(),
+                system_program: accounts.system_program.clone(),
+            };
+            
+            let cpi_ctx = CpiContext::new_with_signer(accounts.material_program.clone(), cpi_accounts, signers);
+            material_nft::cpi::create_material_2022(
+                cpi_ctx,
+                recipe.result_name.clone(),
+                CRAFTED_MATERIAL_SYMBOL.to_string(),
+                recipe.result_uri.clone(),
+                MaterialCategory::Crafted,
+                recipe.result_rarity,
+                u64::MAX,
+                TransferRule::Transferable,
+                seller_fee_basis_points,
+                creators,
+            )?;
+        } else {
+            // One result mint per recipe, controlled by the material authority
+            if accounts.result_mint.data_is_empty() {
+                init_result_mint(&accounts, &recipe_key, result_mint_bump)?;
+            }
             
-            let cpi_program = ctx.accounts.material_program.to_account_info();
+            let cpi_accounts = CreateMaterial {
+                material: accounts.result_material.clone(),
+                material_authority: accounts.material_authority.clone(),
+                mint: accounts.result_mint.clone(),
+                metadata: accounts.result_metadata.clone(),
+                collection_mint: accounts.collection_mint.clone(),
+                collection_metadata: accounts.collection_metadata.clone(),
+                collection_master_edition: accounts.collection_master_edition.clone(),
+                master_edition: accounts.editions.as_ref().map(|editions| editions.master_edition.clone()),
+                master_token_account: accounts.editions.as_ref().map(|editions| editions.master_token_account.clone()),
+                authority: accounts.crafting_authority.clone(),
+                payer: accounts.crafter.clone(),
+                token_program: accounts.token_program.clone(),
+                associated_token_program: accounts.associated_token_program.clone(),
+                token_metadata_program: accounts.token_metadata_program.clone(),
+                system_program: accounts.system_program.clone(),
+                sysvar_instructions: accounts.sysvar_instructions.clone(),
+                rent: accounts.rent.clone(),
+            };
+            
+            let cpi_ctx = CpiContext::new_with_signer(accounts.material_program.clone(), cpi_accounts, signer);
+            material_nft::cpi::create_material(
+                cpi_ctx,
+                recipe.result_name.clone(),
+                CRAFTED_MATERIAL_SYMBOL.to_string(),
+                recipe.result_uri.clone(),
+                MaterialCategory::Crafted,
+                recipe.result_rarity,
+                u64::MAX,
+                mode,
+                seller_fee_basis_points,
+                creators,
+            )?;
+            
+            // The crafting authority is the co-creator of crafted materials, so sign for it now
+            let cpi_accounts = VerifyCreator {
+                material: accounts.result_material.clone(),
+                metadata: accounts.result_metadata.clone(),
+                creator: accounts.crafting_authority.clone(),
+                token_metadata_program: accounts.token_metadata_program.clone(),
+                system_program: accounts.system_program.clone(),
+                sysvar_instructions: accounts.sysvar_instructions.clone(),
+            };
+            
+            let cpi_ctx = CpiContext::new_with_signer(accounts.material_program.clone(), cpi_accounts, signer);
+            material_nft::cpi::verify_creator(cpi_ctx)?;
+        }
+        
+        mode
+    } else {
+        // An existing result material must match what the recipe produces
+        let result_material: Account<material_nft::Material> =
+            Account::try_from(&accounts.result_material)?;
+        require!(
+            result_material.rarity == recipe.result_rarity,
+            CraftingError::ResultMaterialMismatch
+        );
+        
+        // And keeps the token program its mint was created under
+        require_keys_eq!(
+            *accounts.result_mint.owner,
+            accounts.token_program.key(),
+            CraftingError::InvalidTokenProgram
+        );
+        
+        result_material.mode
+    };
+    
+    match mode {
+        MaterialMode::Fungible => {
+            // Mint the result item to the crafter; the crafting authority registered the
+            // material, so it mints as the material's own authority rather than through a permit
             let cpi_accounts = MintMaterial {
-                material: ctx.accounts.result_material.to_account_info(),
-                mint: ctx.accounts.result_mint.to_account_info(),
-                metadata: ctx.accounts.result_metadata.to_account_info(),
-                edition: ctx.accounts.result_edition.to_account_info(),
-                token_account: ctx.accounts.result_token_account.to_account_info(),
-                material_authority: ctx.accounts.material_authority.to_account_info(),
-                payer: ctx.accounts.crafter.to_account_info(),
-                system_program: ctx.accounts.system_program.to_account_info(),
-                token_program: ctx.accounts.token_program.to_account_info(),
-                token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
-                rent: ctx.accounts.rent.to_account_info(),
+                material: accounts.result_material,
+                material_authority: accounts.material_authority,
+                mint: accounts.result_mint,
+                token_account: accounts.result_token_account,
+                receiver: accounts.crafter.clone(),
+                minter: accounts.crafting_authority,
+                minter_permit: None,
+                payer: accounts.crafter,
+                token_program: accounts.token_program,
+                associated_token_program: accounts.associated_token_program,
+                system_program: accounts.system_program,
+                rent: accounts.rent,
             };
             
-            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            let cpi_ctx = CpiContext::new_with_signer(accounts.material_program, cpi_accounts, signer);
+            material_nft::cpi::mint_material(cpi_ctx, amount)
+        }
+        MaterialMode::Unique => {
+            // Print one numbered edition that records where it came from
+            require!(amount == 1, CraftingError::UniqueResultQuantity);
+            let editions = accounts.editions.ok_or(CraftingError::MissingEditionAccounts)?;
+            let attributes = vec![
+                EditionAttribute {
+                    trait_type: "Recipe".to_string(),
+                    value: recipe.name.clone(),
+                },
+                EditionAttribute {
+                    trait_type: "Crafter".to_string(),
+                    value: accounts.crafter.key().to_string(),
+                },
+            ];
             
-            // In a real implementation, we'd pass the correct amount
-            let mint_amount = 1;
+            let cpi_accounts = PrintMaterial {
+                material: accounts.result_material,
+                material_authority: accounts.material_authority,
+                mint: accounts.result_mint,
+                master_metadata: accounts.result_metadata,
+                master_edition: editions.master_edition,
+                master_token_account: editions.master_token_account,
+                edition_mint: editions.edition_mint,
+                edition_metadata: editions.edition_metadata,
+                edition: editions.edition,
+                edition_marker: editions.edition_marker,
+                edition_token_account: accounts.result_token_account,
+                receiver: accounts.crafter.clone(),
+                material_edition: editions.material_edition,
+                minter: accounts.crafting_authority,
+                minter_permit: None,
+                payer: accounts.crafter,
+                token_program: accounts.token_program,
+                associated_token_program: accounts.associated_token_program,
+                token_metadata_program: accounts.token_metadata_program,
+                system_program: accounts.system_program,
+                sysvar_instructions: accounts.sysvar_instructions,
+            };
             
-            // Mint the result item
-            material_nft::cpi::mint_material(cpi_ctx, mint_amount)?;
+            let cpi_ctx = CpiContext::new_with_signer(accounts.material_program, cpi_accounts, signer);
+            material_nft::cpi::print_material(cpi_ctx, attributes)
+        }
+    }
+}
+
+// Helper function to compute the refunded part of an escrowed amount
+fn refund_share(amount: u64, refund_percentage: u8) -> u64 {
+    (amount as u128 * refund_percentage as u128 / 100) as u64
+}
+
+// Helper function to release escrowed materials: refund a share to the crafter, burn the rest
+// Remaining accounts: (mint, vault, crafter token account) per input material
+fn settle_escrow<'info>(
+    token_programs: &TokenPrograms<'info>,
+    crafting_authority: &AccountInfo<'info>,
+    crafting_authority_bump: u8,
+    escrow_accounts: &[AccountInfo<'info>],
+    materials: &[MaterialInput],
+    refund_for: impl Fn(u64) -> u64,
+    crafter: Pubkey,
+) -> Result<()> {
+    require!(
+        escrow_accounts.len() == materials.len() * 3,
+        CraftingError::MissingMaterials
+    );
+    
+    let seeds = &[
+        b"crafting_authority".as_ref(),
+        &[crafting_authority_bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    for (material, accounts) in materials.iter().zip(escrow_accounts.chunks(3)) {
+        let mint = &accounts[0];
+        let vault = &accounts[1];
+        let destination = &accounts[2];
+        
+        require!(mint.key() == material.material_mint, CraftingError::MaterialMismatch);
+        require!(vault.key() == vault_address(&material.material_mint), CraftingError::InvalidVault);
+        let token_program = token_programs.for_mint(mint)?;
+        
+        let refund_amount = refund_for(material.amount).min(material.amount);
+        let burn_amount = material.amount - refund_amount;
+        
+        if refund_amount > 0 {
+            let token_account =
+                InterfaceAccount::<token_interface::TokenAccount>::try_from(destination)?;
+            require!(token_account.owner == crafter, CraftingError::InvalidTokenAccount);
+            require!(token_account.mint == material.material_mint, CraftingError::MaterialMismatch);
+            let decimals = InterfaceAccount::<token_interface::Mint>::try_from(mint)?.decimals;
             
-            msg!("Crafting completed successfully: {}", crafting_record.recipe);
-        } else {
-            // Refund a portion of materials on failure (could be implemented in a separate function)
-            msg!("Crafting failed: {}", crafting_record.recipe);
+            let cpi_accounts = TransferChecked {
+                from: vault.clone(),
+                mint: mint.clone(),
+                to: destination.clone(),
+                authority: crafting_authority.clone(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, refund_amount, decimals)?;
         }
         
-        Ok(())
+        if burn_amount > 0 {
+            let cpi_accounts = Burn {
+                mint: mint.clone(),
+                from: vault.clone(),
+                authority: crafting_authority.clone(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer);
+            token_interface::burn(cpi_ctx, burn_amount)?;
+        }
+    }
+    
+    Ok(())
+}
+
+// Helper function to check that intermediate ingredients name their source recipe's result mint
+fn validate_intermediate_mints(ingredients: &[Ingredient]) -> Result<()> {
+    for ingredient in ingredients {
+        if let Some(source_recipe) = ingredient.source_recipe {
+            require!(
+                ingredient.material_mint == recipe::result_mint_address(&source_recipe),
+                CraftingError::InvalidIntermediateMaterial
+            );
+        }
+    }
+    
+    Ok(())
+}
+
+// Helper function to resolve an input to the material mint a recipe ingredient names
+// Prints of a unique material resolve to their master mint through their MaterialEdition
+fn input_material_mint(material: &MaterialInput, editions: &[AccountInfo]) -> Result<Pubkey> {
+    let edition_address = Pubkey::find_program_address(
+        &[b"edition".as_ref(), material.material_mint.as_ref()],
+        &material_nft::ID,
+    ).0;
+    
+    match editions.iter().find(|edition| edition.key() == edition_address) {
+        Some(edition) => {
+            let edition: Account<material_nft::MaterialEdition> = Account::try_from(edition)?;
+            Ok(edition.master_mint)
+        }
+        None => Ok(material.material_mint),
     }
 }
 
+// Helper function to check that the supplied inputs match the recipe's ingredients
+fn validate_material_inputs(
+    materials: &[MaterialInput],
+    ingredients: &[Ingredient],
+    editions: &[AccountInfo],
+) -> Result<()> {
+    require!(
+        materials.len() <= CraftingRecord::MAX_INPUT_MATERIALS,
+        CraftingError::TooManyMaterials
+    );
+    validate_intermediate_mints(ingredients)?;
+    
+    // Every input must feed an ingredient; several prints can add up to one unique ingredient
+    let mut supplied = vec![0u64; ingredients.len()];
+    for material in materials {
+        let material_mint = input_material_mint(material, editions)?;
+        let pos = ingredients
+            .iter()
+            .position(|ingredient| ingredient.material_mint == material_mint)
+            .ok_or(CraftingError::MaterialMismatch)?;
+        
+        supplied[pos] = supplied[pos]
+            .checked_add(material.amount)
+            .ok_or(CraftingError::ArithmeticOverflow)?;
+    }
+    
+    // And every ingredient must be covered in full
+    for (ingredient, amount) in ingredients.iter().zip(supplied) {
+        require!(
+            amount >= ingredient.quantity,
+            CraftingError::InsufficientMaterialQuantity
+        );
+    }
+    
+    Ok(())
+}
+
 #[derive(Accounts)]
 #[instruction(authority_bump: u8)]
 pub struct Initialize<'info> {
@@ -276,13 +1088,43 @@ pub struct Initialize<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(recipe_id: Pubkey)]
+pub struct InitMaterialVault<'info> {
+    // Escrow for one material mint, shared by all crafting sessions
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"vault".as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = crafting_authority,
+        token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    #[account(
+        seeds = [b"crafting_authority".as_ref()],
+        bump = crafting_authority.bump,
+    )]
+    pub crafting_authority: Account<'info, CraftingAuthority>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipe_id: Pubkey, nonce: u64)]
 pub struct StartCraftingProcess<'info> {
     #[account(
         init,
         payer = crafter,
         space = 8 + CraftingRecord::LEN,
-        seeds = [b"crafting_record".as_ref(), recipe_id.as_ref(), crafter.key().as_ref()],
+        seeds = [b"crafting_record".as_ref(), recipe_id.as_ref(), crafter.key().as_ref(), nonce.to_le_bytes().as_ref()],
         bump,
     )]
     pub crafting_record: Account<'info, CraftingRecord>,
@@ -291,9 +1133,14 @@ pub struct StartCraftingProcess<'info> {
     #[account(mut)]
     pub recipe_crafting: UncheckedAccount<'info>,
     
+    #[account(
+        mut,
+        constraint = recipe.key() == recipe_id @ CraftingError::InvalidRecipe,
+    )]
+    pub recipe: Account<'info, recipe::Recipe>,
+    
     /// CHECK: This account is checked in the CPI call
-    #[account(mut)]
-    pub recipe: UncheckedAccount<'info>,
+    pub randomness_request: UncheckedAccount<'info>,
     
     pub recipe_program: Program<'info, RecipeProgram>,
     
@@ -327,9 +1174,18 @@ pub struct ConsumeMaterials<'info> {
     )]
     pub crafting_record: Account<'info, CraftingRecord>,
     
+    // Materials must be committed before the outcome is known
+    #[account(
+        constraint = crafting_record.randomness_request == randomness_request.key() @ CraftingError::InvalidRandomnessRequest,
+        constraint = randomness_request.status == RequestStatus::Pending @ CraftingError::RandomnessAlreadyFulfilled,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
     #[account(mut)]
     pub crafter: Signer<'info>,
     
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
 
@@ -342,18 +1198,24 @@ pub struct CompleteCraftingProcess<'info> {
     )]
     pub crafting_record: Account<'info, CraftingRecord>,
     
-    /// CHECK: This is the crafting record in the recipe program
     #[account(
         mut,
         constraint = crafting_record.recipe_crafting == recipe_crafting.key() @ CraftingError::InvalidRecipeCrafting,
+        constraint = recipe_crafting.recipe == recipe.key() @ CraftingError::InvalidRecipe,
+        constraint = recipe_crafting.crafter == crafter.key() @ CraftingError::Unauthorized,
     )]
-    pub recipe_crafting: UncheckedAccount<'info>,
+    pub recipe_crafting: Account<'info, recipe::Crafting>,
     
-    /// CHECK: This is the recipe in the recipe program
     #[account(
         constraint = crafting_record.recipe == recipe.key() @ CraftingError::InvalidRecipe,
     )]
-    pub recipe: UncheckedAccount<'info>,
+    pub recipe: Account<'info, recipe::Recipe>,
+    
+    /// CHECK: This account is checked in the CPI call
+    #[account(
+        constraint = crafting_record.randomness_request == randomness_request.key() @ CraftingError::InvalidRandomnessRequest,
+    )]
+    pub randomness_request: UncheckedAccount<'info>,
     
     #[account(
         seeds = [b"crafting_authority".as_ref()],
@@ -361,29 +1223,76 @@ pub struct CompleteCraftingProcess<'info> {
     )]
     pub crafting_authority: Account<'info, CraftingAuthority>,
     
-    /// CHECK: This is the material authority in the material-nft program
-    pub material_authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"material_authority".as_ref()],
+        bump = material_authority.bump,
+        seeds::program = material_program.key(),
+    )]
+    pub material_authority: Account<'info, material_nft::MaterialAuthority>,
     
-    /// CHECK: This is the result material account
-    #[account(mut)]
+    /// CHECK: Created or validated by the material program
+    #[account(
+        mut,
+        seeds = [b"material".as_ref(), result_mint.key().as_ref()],
+        bump,
+        seeds::program = material_program.key(),
+    )]
     pub result_material: UncheckedAccount<'info>,
     
-    /// CHECK: This is the result mint account
-    #[account(mut)]
+    /// CHECK: One result mint per recipe, created on the first success by mint_crafted_result
+    #[account(
+        mut,
+        seeds = [b"result_mint".as_ref(), recipe.key().as_ref()],
+        bump,
+    )]
     pub result_mint: UncheckedAccount<'info>,
     
-    /// CHECK: This is the result metadata account
+    /// CHECK: This is the result metadata account, checked by the token metadata program
     #[account(mut)]
     pub result_metadata: UncheckedAccount<'info>,
     
-    /// CHECK: This is the result edition account
+    /// CHECK: The material collection mint, checked by the material program
+    pub collection_mint: UncheckedAccount<'info>,
+    
+    /// CHECK: The collection metadata, checked by the token metadata program
     #[account(mut)]
-    pub result_edition: UncheckedAccount<'info>,
+    pub collection_metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: The collection master edition, checked by the token metadata program
+    pub collection_master_edition: UncheckedAccount<'info>,
     
-    /// CHECK: This is the result token account
+    /// CHECK: This is the crafter's result token account, created by the material program
     #[account(mut)]
     pub result_token_account: UncheckedAccount<'info>,
     
+    // Unique results only: the master edition accounts and the print minted from them
+    /// CHECK: The result's master edition, checked by the token metadata program
+    #[account(mut)]
+    pub result_master_edition: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: The material authority's master token account, checked by the material program
+    #[account(mut)]
+    pub result_master_token_account: Option<UncheckedAccount<'info>>,
+    
+    #[account(mut)]
+    pub edition_mint: Option<Signer<'info>>,
+    
+    /// CHECK: Created and checked by the token metadata program
+    #[account(mut)]
+    pub edition_metadata: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Created and checked by the token metadata program
+    #[account(mut)]
+    pub edition: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Created and checked by the token metadata program
+    #[account(mut)]
+    pub edition_marker: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Created by the material program
+    #[account(mut)]
+    pub material_edition: Option<UncheckedAccount<'info>>,
+    
     pub recipe_program: Program<'info, RecipeProgram>,
     pub material_program: Program<'info, MaterialNftProgram>,
     
@@ -392,11 +1301,241 @@ pub struct CompleteCraftingProcess<'info> {
     
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    // The program the recipe's result mint is created under
+    #[account(
+        constraint = result_token_program.key() == recipe.result_token_program @ CraftingError::InvalidTokenProgram,
+    )]
+    pub result_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     /// CHECK: This is the token metadata program
     pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, read by the token metadata program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+impl<'info> CompleteCraftingProcess<'info> {
+    // The print accounts, present only when the recipe's result is a unique material
+    fn edition_accounts(&self) -> Option<EditionAccounts<'info>> {
+        Some(EditionAccounts {
+            master_edition: self.result_master_edition.as_ref()?.to_account_info(),
+            master_token_account: self.result_master_token_account.as_ref()?.to_account_info(),
+            edition_mint: self.edition_mint.as_ref()?.to_account_info(),
+            edition_metadata: self.edition_metadata.as_ref()?.to_account_info(),
+            edition: self.edition.as_ref()?.to_account_info(),
+            edition_marker: self.edition_marker.as_ref()?.to_account_info(),
+            material_edition: self.material_edition.as_ref()?.to_account_info(),
+        })
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(recipe_id: Pubkey, nonce: u64)]
+pub struct CraftBatch<'info> {
+    #[account(
+        init,
+        payer = crafter,
+        space = 8 + CraftingRecord::LEN,
+        seeds = [b"crafting_record".as_ref(), recipe_id.as_ref(), crafter.key().as_ref(), nonce.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub crafting_record: Account<'info, CraftingRecord>,
+    
+    #[account(
+        constraint = recipe.key() == recipe_id @ CraftingError::InvalidRecipe,
+    )]
+    pub recipe: Account<'info, recipe::Recipe>,
+    
+    // Must be requested for this record before the oracle answers
+    #[account(
+        constraint = randomness_request.requester == crafter.key() @ CraftingError::InvalidRandomnessRequest,
+        constraint = randomness_request.consumer == crafting_record.key() @ CraftingError::InvalidRandomnessRequest,
+        constraint = randomness_request.status == RequestStatus::Pending @ CraftingError::RandomnessAlreadyFulfilled,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
+    #[account(mut)]
+    pub crafter: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteBatch<'info> {
+    #[account(
+        mut,
+        constraint = crafting_record.crafter == crafter.key() @ CraftingError::Unauthorized,
+        constraint = crafting_record.is_batch @ CraftingError::NotABatch,
+    )]
+    pub crafting_record: Account<'info, CraftingRecord>,
+    
+    #[account(
+        constraint = crafting_record.recipe == recipe.key() @ CraftingError::InvalidRecipe,
+    )]
+    pub recipe: Account<'info, recipe::Recipe>,
+    
+    #[account(
+        constraint = crafting_record.randomness_request == randomness_request.key() @ CraftingError::InvalidRandomnessRequest,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
+    #[account(
+        seeds = [b"crafting_authority".as_ref()],
+        bump = crafting_authority.bump,
+    )]
+    pub crafting_authority: Account<'info, CraftingAuthority>,
+    
+    #[account(
+        seeds = [b"material_authority".as_ref()],
+        bump = material_authority.bump,
+        seeds::program = material_program.key(),
+    )]
+    pub material_authority: Account<'info, material_nft::MaterialAuthority>,
+    
+    /// CHECK: Created or validated by the material program
+    #[account(
+        mut,
+        seeds = [b"material".as_ref(), result_mint.key().as_ref()],
+        bump,
+        seeds::program = material_program.key(),
+    )]
+    pub result_material: UncheckedAccount<'info>,
+    
+    /// CHECK: One result mint per recipe, created on the first success by mint_crafted_result
+    #[account(
+        mut,
+        seeds = [b"result_mint".as_ref(), recipe.key().as_ref()],
+        bump,
+    )]
+    pub result_mint: UncheckedAccount<'info>,
+    
+    /// CHECK: This is the result metadata account, checked by the token metadata program
+    #[account(mut)]
+    pub result_metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: The material collection mint, checked by the material program
+    pub collection_mint: UncheckedAccount<'info>,
+    
+    /// CHECK: The collection metadata, checked by the token metadata program
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: The collection master edition, checked by the token metadata program
+    pub collection_master_edition: UncheckedAccount<'info>,
+    
+    /// CHECK: This is the crafter's result token account, created by the material program
+    #[account(mut)]
+    pub result_token_account: UncheckedAccount<'info>,
+    
+    pub material_program: Program<'info, MaterialNftProgram>,
+    
+    #[account(mut)]
+    pub crafter: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    // The program the recipe's result mint is created under
+    #[account(
+        constraint = result_token_program.key() == recipe.result_token_program @ CraftingError::InvalidTokenProgram,
+    )]
+    pub result_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is the token metadata program
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, read by the token metadata program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(
+        mut,
+        constraint = crafting_record.crafter == crafter.key() @ CraftingError::Unauthorized,
+    )]
+    pub crafting_record: Account<'info, CraftingRecord>,
+    
+    #[account(
+        constraint = crafting_record.recipe == recipe.key() @ CraftingError::InvalidRecipe,
+    )]
+    pub recipe: Account<'info, recipe::Recipe>,
+    
+    #[account(
+        seeds = [b"crafting_authority".as_ref()],
+        bump = crafting_authority.bump,
+    )]
+    pub crafting_authority: Account<'info, CraftingAuthority>,
+    
+    pub crafter: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct CancelCraftingProcess<'info> {
+    #[account(
+        mut,
+        constraint = crafting_record.crafter == crafter.key() @ CraftingError::Unauthorized,
+    )]
+    pub crafting_record: Account<'info, CraftingRecord>,
+    
+    /// CHECK: This account is checked in the CPI call
+    #[account(
+        mut,
+        constraint = crafting_record.recipe_crafting == recipe_crafting.key() @ CraftingError::InvalidRecipeCrafting,
+    )]
+    pub recipe_crafting: UncheckedAccount<'info>,
+    
+    /// CHECK: This account is checked in the CPI call
+    #[account(
+        constraint = crafting_record.randomness_request == randomness_request.key() @ CraftingError::InvalidRandomnessRequest,
+    )]
+    pub randomness_request: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"crafting_authority".as_ref()],
+        bump = crafting_authority.bump,
+    )]
+    pub crafting_authority: Account<'info, CraftingAuthority>,
+    
+    pub recipe_program: Program<'info, RecipeProgram>,
+    
+    pub crafter: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCraftingProcess<'info> {
+    #[account(
+        mut,
+        constraint = crafting_record.crafter == crafter.key() @ CraftingError::Unauthorized,
+        close = crafter,
+    )]
+    pub crafting_record: Account<'info, CraftingRecord>,
+    
+    /// CHECK: This account is checked in the CPI call
+    #[account(
+        mut,
+        constraint = crafting_record.recipe_crafting == recipe_crafting.key() @ CraftingError::InvalidRecipeCrafting,
+    )]
+    pub recipe_crafting: UncheckedAccount<'info>,
+    
+    pub recipe_program: Program<'info, RecipeProgram>,
+    
+    #[account(mut)]
+    pub crafter: Signer<'info>,
+}
+
 #[account]
 pub struct CraftingAuthority {
     pub authority: Pubkey,
@@ -411,25 +1550,42 @@ impl CraftingAuthority {
 pub struct CraftingRecord {
     pub recipe: Pubkey,
     pub recipe_crafting: Pubkey,
+    pub randomness_request: Pubkey,
     pub crafter: Pubkey,
+    pub nonce: u64,
     pub start_time: i64,
     pub completion_time: Option<i64>,
     pub status: CraftingStatus,
     pub materials_verified: bool,
     pub materials_consumed: bool,
+    pub materials_settled: bool,
     pub input_materials: Vec<MaterialInput>,
+    pub is_batch: bool,
+    pub batch_size: u16,
+    pub batch_results: Vec<bool>, // per-item success, filled when a batch completes
 }
 
 impl CraftingRecord {
+    pub const MAX_INPUT_MATERIALS: usize = 10;
+    
+    // Keeps per-item rolls and result storage well inside compute and account limits
+    pub const MAX_BATCH_SIZE: u16 = 100;
+    
     pub const LEN: usize = 32 + // recipe
                           32 + // recipe_crafting
+                          32 + // randomness_request
                           32 + // crafter
+                          8 +  // nonce
                           8 +  // start_time
                           9 +  // completion_time (Option<i64>)
                           1 +  // status enum
                           1 +  // materials_verified
                           1 +  // materials_consumed
-                          4 + (10 * MaterialInput::LEN); // vec of up to 10 materials
+                          1 +  // materials_settled
+                          4 + (Self::MAX_INPUT_MATERIALS * MaterialInput::LEN) + // vec of up to 10 materials
+                          1 +  // is_batch
+                          2 +  // batch_size
+                          4 + Self::MAX_BATCH_SIZE as usize; // vec of up to 100 batch results
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
@@ -463,12 +1619,69 @@ pub enum CraftingError {
     #[msg("Missing required materials")]
     MissingMaterials,
     
+    #[msg("Materials do not match recipe ingredients")]
+    MaterialMismatch,
+    
+    #[msg("Material quantity is below the recipe requirement")]
+    InsufficientMaterialQuantity,
+    
+    #[msg("Too many input materials, maximum is 10")]
+    TooManyMaterials,
+    
     #[msg("Invalid recipe")]
     InvalidRecipe,
     
     #[msg("Invalid recipe crafting record")]
     InvalidRecipeCrafting,
     
+    #[msg("Randomness request is not bound to this crafting record")]
+    InvalidRandomnessRequest,
+    
+    #[msg("Randomness was fulfilled before materials were consumed")]
+    RandomnessAlreadyFulfilled,
+    
+    #[msg("Intermediate ingredient is not its source recipe's result")]
+    InvalidIntermediateMaterial,
+    
+    #[msg("Batch size must be between 1 and 100")]
+    InvalidBatchSize,
+    
+    #[msg("Recipe is not approved or is disabled")]
+    RecipeUnavailable,
+    
+    #[msg("Crafting is not ready to be completed")]
+    CraftingNotReady,
+    
+    #[msg("Crafting record is not a batch")]
+    NotABatch,
+    
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    
+    #[msg("Escrowed materials must be refunded or burned first")]
+    EscrowNotSettled,
+    
+    #[msg("Vault does not belong to this material")]
+    InvalidVault,
+    
+    #[msg("Result material does not match the recipe output")]
+    ResultMaterialMismatch,
+    
+    #[msg("Unique results are printed one per craft")]
+    UniqueResultQuantity,
+    
+    #[msg("Recipes with unique results cannot be batch crafted")]
+    UniqueResultInBatch,
+    
+    #[msg("Unique results need their master and print edition accounts")]
+    MissingEditionAccounts,
+    
+    #[msg("Mint does not belong to the given token program")]
+    InvalidTokenProgram,
+    
+    #[msg("Unique results are printed under SPL Token, not Token-2022")]
+    UniqueResultOnToken2022,
+    
     #[msg("Unauthorized access")]
     Unauthorized,
 } 
\ No newline at end of file
@@ -1,20 +1,40 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{Mint, Token, TokenAccount},
+    token_2022::{self, Token2022},
+    token_interface::{self, TokenInterface},
 };
 use mpl_token_metadata::{
-    instruction as token_instruction,
-    state::{Creator, DataV2},
+    accounts::Metadata,
+    instructions::{
+        CreateV1CpiBuilder, PrintV1CpiBuilder, UpdateV1CpiBuilder,
+        VerifyCollectionV1CpiBuilder, VerifyCreatorV1CpiBuilder,
+    },
+    types::{Collection, CollectionDetails, Creator, Data, PrintSupply, TokenStandard},
 };
+use magicvial_types::{MaterialCategory, MaterialMode, Rarity};
 use solana_program::{
-    program::invoke_signed,
+    program::{invoke, invoke_signed},
+    pubkey,
     pubkey::Pubkey,
-    sysvar::clock::Clock,
+    sysvar::{self, clock::Clock},
 };
+use spl_token_2022::extension::{metadata_pointer, transfer_hook, ExtensionType};
+use spl_token_metadata_interface::state::Field;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
+// Royalty cap given to authorities created before the cap existed (10%)
+pub const DEFAULT_MAX_ROYALTY_BPS: u16 = 1000;
+
+// The token metadata program accepts at most five creators
+pub const MAX_CREATORS: usize = 5;
+
+// Crafting program that creates recipe results; it depends on this program, so its ID is pinned here
+pub const CRAFTING_PROGRAM_ID: Pubkey = pubkey!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
 #[program]
 pub mod material_nft {
     use super::*;
@@ -22,33 +42,125 @@ pub mod material_nft {
     pub fn initialize(
         ctx: Context<Initialize>,
         authority_bump: u8,
+        max_royalty_bps: u16,
     ) -> Result<()> {
+        require!(max_royalty_bps <= 10_000, ErrorCode::InvalidRoyaltyCap);
+        
         let material_authority = &mut ctx.accounts.material_authority;
         material_authority.authority = ctx.accounts.authority.key();
         material_authority.bump = authority_bump;
+        material_authority.max_royalty_bps = max_royalty_bps;
         
         msg!("Material NFT program initialized");
         
         Ok(())
     }
 
+    pub fn set_max_royalty(
+        ctx: Context<SetMaxRoyalty>,
+        max_royalty_bps: u16,
+    ) -> Result<()> {
+        require!(max_royalty_bps <= 10_000, ErrorCode::InvalidRoyaltyCap);
+        
+        // Lowering the cap does not touch existing metadata; it applies to later updates
+        ctx.accounts.material_authority.max_royalty_bps = max_royalty_bps;
+        
+        msg!("Royalty cap set to {} bps", max_royalty_bps);
+        
+        Ok(())
+    }
+    
+    pub fn create_material_collection(
+        ctx: Context<CreateMaterialCollection>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(!name.is_empty(), ErrorCode::EmptyName);
+        require!(!symbol.is_empty(), ErrorCode::EmptySymbol);
+        require!(!uri.is_empty(), ErrorCode::EmptyUri);
+        
+        let authority_bump = ctx.accounts.material_authority.bump;
+        let authority_seeds = &[
+            b"material_authority".as_ref(),
+            &[authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+        
+        // The collection NFT is a single token held by the material authority
+        let cpi_accounts = anchor_spl::token::MintTo {
+            mint: ctx.accounts.collection_mint.to_account_info(),
+            to: ctx.accounts.collection_token_account.to_account_info(),
+            authority: ctx.accounts.material_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        anchor_spl::token::mint_to(cpi_ctx, 1)?;
+        
+        let creators = vec![
+            Creator {
+                address: ctx.accounts.material_authority.key(),
+                verified: true,
+                share: 100,
+            },
+        ];
+        
+        // Sized, zero-print master edition: a one-of-one NFT that keeps an on-chain member count
+        CreateV1CpiBuilder::new(&ctx.accounts.token_metadata_program.to_account_info())
+            .metadata(&ctx.accounts.collection_metadata.to_account_info())
+            .master_edition(Some(&ctx.accounts.collection_master_edition.to_account_info()))
+            .mint(&ctx.accounts.collection_mint.to_account_info(), false)
+            .authority(&ctx.accounts.material_authority.to_account_info())
+            .payer(&ctx.accounts.payer.to_account_info())
+            .update_authority(&ctx.accounts.material_authority.to_account_info(), true)
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .sysvar_instructions(&ctx.accounts.sysvar_instructions.to_account_info())
+            .spl_token_program(Some(&ctx.accounts.token_program.to_account_info()))
+            .name(name.clone())
+            .symbol(symbol)
+            .uri(uri)
+            .seller_fee_basis_points(0) // No royalties on the collection itself
+            .creators(creators)
+            .is_mutable(true)
+            .token_standard(TokenStandard::NonFungible)
+            .collection_details(CollectionDetails::V1 { size: 0 })
+            .print_supply(PrintSupply::Zero)
+            .invoke_signed(signer_seeds)?;
+        
+        msg!("Material collection created: {}", name);
+        
+        Ok(())
+    }
+    
     pub fn create_material(
         ctx: Context<CreateMaterial>,
         name: String,
         symbol: String,
         uri: String,
-        material_type: String,
-        rarity: String,
+        material_type: MaterialCategory,
+        rarity: Rarity,
         max_supply: u64,
+        mode: MaterialMode,
+        seller_fee_basis_points: u16,
+        creators: Vec<CreatorShare>,
     ) -> Result<()> {
         // Validate inputs
         require!(!name.is_empty(), ErrorCode::EmptyName);
         require!(!symbol.is_empty(), ErrorCode::EmptySymbol);
         require!(!uri.is_empty(), ErrorCode::EmptyUri);
-        require!(!material_type.is_empty(), ErrorCode::EmptyType);
-        require!(!rarity.is_empty(), ErrorCode::EmptyRarity);
         require!(max_supply > 0, ErrorCode::InvalidMaxSupply);
         
+        let creators = royalty_creators(
+            seller_fee_basis_points,
+            &creators,
+            ctx.accounts.material_authority.max_royalty_bps,
+            ctx.accounts.material_authority.key(),
+            &[],
+        )?;
+        
         // Create on-chain metadata for the material
         let authority_bump = ctx.accounts.material_authority.bump;
         let authority_seeds = &[
@@ -57,79 +169,265 @@ pub mod material_nft {
         ];
         let signer_seeds = &[&authority_seeds[..]];
         
-        // Create the metadata for the NFT
-        let metadata_infos = vec![
-            ctx.accounts.metadata.to_account_info(),
-            ctx.accounts.mint.to_account_info(),
-            ctx.accounts.material_authority.to_account_info(),
-            ctx.accounts.authority.to_account_info(),
-            ctx.accounts.token_metadata_program.to_account_info(),
-            ctx.accounts.system_program.to_account_info(),
-            ctx.accounts.rent.to_account_info(),
+        let token_program = ctx.accounts.token_program.to_account_info();
+        
+        // A unique material's single master token is held by the material authority;
+        // each unit is then a numbered print edition minted by print_material
+        let master_edition = match mode {
+            MaterialMode::Fungible => None,
+            MaterialMode::Unique => {
+                let master_edition = ctx.accounts.master_edition.as_ref()
+                    .ok_or(ErrorCode::MissingEditionAccounts)?;
+                let master_token_account = ctx.accounts.master_token_account.as_ref()
+                    .ok_or(ErrorCode::MissingEditionAccounts)?;
+                
+                anchor_spl::associated_token::create(CpiContext::new(
+                    ctx.accounts.associated_token_program.to_account_info(),
+                    anchor_spl::associated_token::Create {
+                        payer: ctx.accounts.payer.to_account_info(),
+                        associated_token: master_token_account.to_account_info(),
+                        authority: ctx.accounts.material_authority.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        token_program: token_program.clone(),
+                    },
+                ))?;
+                
+                let cpi_accounts = anchor_spl::token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: master_token_account.to_account_info(),
+                    authority: ctx.accounts.material_authority.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    token_program.clone(),
+                    cpi_accounts,
+                    signer_seeds,
+                );
+                anchor_spl::token::mint_to(cpi_ctx, 1)?;
+                
+                Some(master_edition.to_account_info())
+            }
+        };
+        
+        let token_metadata_program = ctx.accounts.token_metadata_program.to_account_info();
+        let metadata = ctx.accounts.metadata.to_account_info();
+        let mint = ctx.accounts.mint.to_account_info();
+        let material_authority = ctx.accounts.material_authority.to_account_info();
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let sysvar_instructions = ctx.accounts.sysvar_instructions.to_account_info();
+        
+        // Traits live in the JSON at `uri`, and type and rarity are also kept on-chain in `Material`
+        let mut create_cpi = CreateV1CpiBuilder::new(&token_metadata_program);
+        create_cpi
+            .metadata(&metadata)
+            .mint(&mint, false)
+            .authority(&material_authority)
+            .payer(&payer)
+            .update_authority(&material_authority, true)
+            .system_program(&system_program)
+            .sysvar_instructions(&sysvar_instructions)
+            .spl_token_program(Some(&token_program))
+            .name(name.clone())
+            .symbol(symbol)
+            .uri(uri)
+            .seller_fee_basis_points(seller_fee_basis_points)
+            .creators(creators)
+            .collection(Collection {
+                verified: false,
+                key: ctx.accounts.collection_mint.key(),
+            })
+            .is_mutable(true);
+        match mode {
+            // Semi-fungible: decimals 0, any supply, one metadata account per mint
+            MaterialMode::Fungible => create_cpi.token_standard(TokenStandard::FungibleAsset),
+            // One-of-one master whose prints are capped by the material's max supply
+            MaterialMode::Unique => create_cpi
+                .master_edition(master_edition.as_ref())
+                .token_standard(TokenStandard::NonFungible)
+                .print_supply(PrintSupply::Limited(max_supply)),
+        };
+        create_cpi.invoke_signed(signer_seeds)?;
+        
+        // The material authority owns the collection, so it can verify membership right away
+        VerifyCollectionV1CpiBuilder::new(&ctx.accounts.token_metadata_program.to_account_info())
+            .authority(&ctx.accounts.material_authority.to_account_info())
+            .metadata(&ctx.accounts.metadata.to_account_info())
+            .collection_mint(&ctx.accounts.collection_mint.to_account_info())
+            .collection_metadata(Some(&ctx.accounts.collection_metadata.to_account_info()))
+            .collection_master_edition(Some(&ctx.accounts.collection_master_edition.to_account_info()))
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .sysvar_instructions(&ctx.accounts.sysvar_instructions.to_account_info())
+            .invoke_signed(signer_seeds)?;
+        
+        // Create the material account
+        let material = &mut ctx.accounts.material;
+        material.mint = ctx.accounts.mint.key();
+        material.material_type = material_type;
+        material.rarity = rarity;
+        material.current_supply = 0;
+        material.max_supply = max_supply;
+        material.created_at = Clock::get()?.unix_timestamp;
+        material.updated_at = material.created_at;
+        material.authority = ctx.accounts.authority.key();
+        material.is_active = true;
+        material.mode = mode;
+        
+        msg!("Material created successfully: {} ({})", name, mode.name());
+        
+        Ok(())
+    }
+    
+    pub fn create_material_2022(
+        ctx: Context<CreateMaterial2022>,
+        name: String,
+        symbol: String,
+        uri: String,
+        material_type: MaterialCategory,
+        rarity: Rarity,
+        max_supply: u64,
+        transfer_rule: TransferRule,
+        seller_fee_basis_points: u16,
+        creators: Vec<CreatorShare>,
+    ) -> Result<()> {
+        // Validate inputs
+        require!(!name.is_empty(), ErrorCode::EmptyName);
+        require!(!symbol.is_empty(), ErrorCode::EmptySymbol);
+        require!(!uri.is_empty(), ErrorCode::EmptyUri);
+        require!(max_supply > 0, ErrorCode::InvalidMaxSupply);
+        
+        let authority_bump = ctx.accounts.material_authority.bump;
+        let authority_seeds = &[
+            b"material_authority".as_ref(),
+            &[authority_bump],
         ];
+        let signer_seeds = &[&authority_seeds[..]];
         
-        // Create metadata creators array
-        let creators = vec![
-            // Material authority as first creator (verified)
-            Creator {
-                address: ctx.accounts.material_authority.key(),
-                verified: true,
-                share: 5,
-            },
-            // Original creator as second creator (not verified, will need to be verified later)
-            Creator {
+        // Royalties follow the same cap and split rules as token metadata materials. The
+        // authority signs this instruction, so like the material authority it is verified directly
+        let creators = royalty_creators(
+            seller_fee_basis_points,
+            &creators,
+            ctx.accounts.material_authority.max_royalty_bps,
+            ctx.accounts.material_authority.key(),
+            &[Creator {
                 address: ctx.accounts.authority.key(),
-                verified: false,
-                share: 95,
-            },
+                verified: true,
+                share: 0,
+            }],
+        )?;
+        
+        let mint = ctx.accounts.mint.to_account_info();
+        let material_authority = ctx.accounts.material_authority.to_account_info();
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let token_program_id = token_program.key();
+        
+        // Type and rarity ride along as extra metadata fields, as they do in `Material`. There is
+        // no token metadata account, so the collection and royalty split are kept the same way
+        let fields = vec![
+            ("material_type".to_string(), material_type.name().to_string()),
+            ("rarity".to_string(), rarity.name().to_string()),
+            ("collection".to_string(), ctx.accounts.collection_mint.key().to_string()),
+            ("seller_fee_basis_points".to_string(), seller_fee_basis_points.to_string()),
+            ("creators".to_string(), creators_field(&creators)),
         ];
         
-        // Additional attributes for the material
-        let mut attributes = format!(
-            "{{\"attributes\":[{{\"trait_type\":\"Type\",\"value\":\"{}\"}},{{\"trait_type\":\"Rarity\",\"value\":\"{}\"}}]}}",
-            material_type, rarity
-        );
+        // Extensions must be in place before the mint is initialized, so the mint is created here.
+        // Rent covers the on-mint metadata the token program appends afterwards
+        let mint_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(
+            &transfer_rule.extensions(),
+        )?;
+        let lamports = Rent::get()?
+            .minimum_balance(mint_len + token_metadata_len(&name, &symbol, &uri, &fields));
+        system_program::create_account(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: mint.clone(),
+                },
+            ),
+            lamports,
+            mint_len as u64,
+            &token_program_id,
+        )?;
         
-        // Create the metadata
-        let data = DataV2 {
-            name,
-            symbol,
-            uri,
-            seller_fee_basis_points: 500, // 5% royalty
-            creators: Some(creators),
-            collection: None,
-            uses: None,
-        };
+        // The mint is its own metadata account
+        invoke(
+            &metadata_pointer::instruction::initialize(
+                &token_program_id,
+                &mint.key(),
+                Some(material_authority.key()),
+                Some(mint.key()),
+            )?,
+            &[mint.clone(), token_program.clone()],
+        )?;
         
-        // Invoke the metadata instruction
-        let create_metadata_ix = token_instruction::create_metadata_accounts_v2(
-            ctx.accounts.token_metadata_program.key(),
-            ctx.accounts.metadata.key(),
-            ctx.accounts.mint.key(),
-            ctx.accounts.material_authority.key(),
-            ctx.accounts.authority.key(),
-            ctx.accounts.material_authority.key(), // Update authority
-            data.name.clone(),
-            data.symbol.clone(),
-            data.uri.clone(),
-            Some(data.creators.unwrap()),
-            data.seller_fee_basis_points,
-            true, // Update authority is signer
-            true, // Is mutable
-            None, // Collection
-            None, // Uses
-            Some(attributes), // Additional attributes
-        );
+        match transfer_rule {
+            TransferRule::Transferable => {}
+            TransferRule::NonTransferable => invoke(
+                &spl_token_2022::instruction::initialize_non_transferable_mint(
+                    &token_program_id,
+                    &mint.key(),
+                )?,
+                &[mint.clone(), token_program.clone()],
+            )?,
+            TransferRule::TransferHook { program_id } => invoke(
+                &transfer_hook::instruction::initialize(
+                    &token_program_id,
+                    &mint.key(),
+                    Some(material_authority.key()),
+                    Some(program_id),
+                )?,
+                &[mint.clone(), token_program.clone()],
+            )?,
+        }
+        
+        // Decimals 0 like every other material, minted by the material authority
+        token_2022::initialize_mint2(
+            CpiContext::new(
+                token_program.clone(),
+                token_2022::InitializeMint2 {
+                    mint: mint.clone(),
+                },
+            ),
+            0,
+            &material_authority.key(),
+            None,
+        )?;
         
         invoke_signed(
-            &create_metadata_ix,
-            &metadata_infos,
+            &spl_token_metadata_interface::instruction::initialize(
+                &token_program_id,
+                &mint.key(),
+                &material_authority.key(),
+                &mint.key(),
+                &material_authority.key(),
+                name.clone(),
+                symbol,
+                uri,
+            ),
+            &[mint.clone(), material_authority.clone(), token_program.clone()],
             signer_seeds,
         )?;
         
-        // Create the material account
+        for (key, value) in fields {
+            invoke_signed(
+                &spl_token_metadata_interface::instruction::update_field(
+                    &token_program_id,
+                    &mint.key(),
+                    &material_authority.key(),
+                    Field::Key(key),
+                    value,
+                ),
+                &[mint.clone(), material_authority.clone(), token_program.clone()],
+                signer_seeds,
+            )?;
+        }
+        
+        // Token-2022 materials stack; print editions are a token metadata program feature
         let material = &mut ctx.accounts.material;
-        material.mint = ctx.accounts.mint.key();
+        material.mint = mint.key();
         material.material_type = material_type;
         material.rarity = rarity;
         material.current_supply = 0;
@@ -138,8 +436,74 @@ pub mod material_nft {
         material.updated_at = material.created_at;
         material.authority = ctx.accounts.authority.key();
         material.is_active = true;
+        material.mode = MaterialMode::Fungible;
+        
+        msg!("Token-2022 material created successfully: {}", name);
+        
+        Ok(())
+    }
+    
+    pub fn verify_creator(
+        ctx: Context<VerifyCreator>,
+    ) -> Result<()> {
+        // The material's own authority is the unverified co-creator on its metadata
+        VerifyCreatorV1CpiBuilder::new(&ctx.accounts.token_metadata_program.to_account_info())
+            .authority(&ctx.accounts.creator.to_account_info())
+            .metadata(&ctx.accounts.metadata.to_account_info())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .sysvar_instructions(&ctx.accounts.sysvar_instructions.to_account_info())
+            .invoke()?;
+        
+        msg!("Creator verified: {}", ctx.accounts.creator.key());
+        
+        Ok(())
+    }
+    
+    pub fn update_material_royalties(
+        ctx: Context<UpdateMaterialRoyalties>,
+        seller_fee_basis_points: u16,
+        creators: Vec<CreatorShare>,
+    ) -> Result<()> {
+        let metadata = Metadata::try_from(&ctx.accounts.metadata.to_account_info())
+            .map_err(|_| error!(ErrorCode::InvalidMetadata))?;
+        
+        // Creators who already signed stay verified; the token metadata program rejects unverifying them
+        let previous_creators = metadata.creators.clone().unwrap_or_default();
+        let creators = royalty_creators(
+            seller_fee_basis_points,
+            &creators,
+            ctx.accounts.material_authority.max_royalty_bps,
+            ctx.accounts.material_authority.key(),
+            &previous_creators,
+        )?;
+        
+        // Name, symbol and URI are carried over; only the royalty fields change
+        let data = Data {
+            name: metadata.name.trim_matches('\0').to_string(),
+            symbol: metadata.symbol.trim_matches('\0').to_string(),
+            uri: metadata.uri.trim_matches('\0').to_string(),
+            seller_fee_basis_points,
+            creators: Some(creators),
+        };
+        
+        let authority_bump = ctx.accounts.material_authority.bump;
+        let authority_seeds = &[
+            b"material_authority".as_ref(),
+            &[authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
         
-        msg!("Material created successfully: {}", data.name);
+        UpdateV1CpiBuilder::new(&ctx.accounts.token_metadata_program.to_account_info())
+            .authority(&ctx.accounts.material_authority.to_account_info())
+            .mint(&ctx.accounts.mint.to_account_info())
+            .metadata(&ctx.accounts.metadata.to_account_info())
+            .payer(&ctx.accounts.payer.to_account_info())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .sysvar_instructions(&ctx.accounts.sysvar_instructions.to_account_info())
+            .data(data)
+            .invoke_signed(signer_seeds)?;
+        
+        msg!("Material royalties updated: {} bps", seller_fee_basis_points);
         
         Ok(())
     }
@@ -153,6 +517,14 @@ pub mod material_nft {
         // Validate inputs
         require!(amount > 0, ErrorCode::InvalidMintAmount);
         require!(material.is_active, ErrorCode::MaterialInactive);
+        require!(material.mode == MaterialMode::Fungible, ErrorCode::UniqueMaterial);
+        
+        // Only the material's own authority or a minter permitted for this material may mint
+        require!(
+            ctx.accounts.minter.key() == material.authority ||
+            ctx.accounts.minter_permit.is_some(),
+            ErrorCode::UnauthorizedMinter
+        );
         require!(
             material.current_supply + amount <= material.max_supply,
             ErrorCode::ExceedsMaxSupply
@@ -170,7 +542,8 @@ pub mod material_nft {
         ];
         let signer_seeds = &[&authority_seeds[..]];
         
-        let cpi_accounts = anchor_spl::token::MintTo {
+        // The token program may be either SPL Token or Token-2022, whichever owns the mint
+        let cpi_accounts = token_interface::MintTo {
             mint: ctx.accounts.mint.to_account_info(),
             to: ctx.accounts.token_account.to_account_info(),
             authority: ctx.accounts.material_authority.to_account_info(),
@@ -183,13 +556,286 @@ pub mod material_nft {
             signer_seeds,
         );
         
-        anchor_spl::token::mint_to(cpi_ctx, amount)?;
+        token_interface::mint_to(cpi_ctx, amount)?;
         
         msg!("Minted {} material tokens", amount);
         
         Ok(())
     }
     
+    pub fn print_material(
+        ctx: Context<PrintMaterial>,
+        attributes: Vec<EditionAttribute>,
+    ) -> Result<()> {
+        let material = &mut ctx.accounts.material;
+        
+        // Validate inputs
+        require!(material.is_active, ErrorCode::MaterialInactive);
+        require!(material.mode == MaterialMode::Unique, ErrorCode::FungibleMaterial);
+        require!(
+            attributes.len() <= MaterialEdition::MAX_ATTRIBUTES,
+            ErrorCode::TooManyEditionAttributes
+        );
+        for attribute in &attributes {
+            require!(
+                attribute.trait_type.len() <= EditionAttribute::MAX_TRAIT_TYPE_LEN &&
+                attribute.value.len() <= EditionAttribute::MAX_VALUE_LEN,
+                ErrorCode::EditionAttributeTooLong
+            );
+        }
+        
+        // Only the material's own authority or a minter permitted for this material may print
+        require!(
+            ctx.accounts.minter.key() == material.authority ||
+            ctx.accounts.minter_permit.is_some(),
+            ErrorCode::UnauthorizedMinter
+        );
+        require!(
+            material.current_supply < material.max_supply,
+            ErrorCode::ExceedsMaxSupply
+        );
+        
+        // Every print is one unit of supply, numbered from 1
+        material.current_supply += 1;
+        material.updated_at = Clock::get()?.unix_timestamp;
+        let edition_number = material.current_supply;
+        
+        let authority_bump = ctx.accounts.material_authority.bump;
+        let authority_seeds = &[
+            b"material_authority".as_ref(),
+            &[authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+        
+        // The print copies the master's metadata into a fresh mint owned by the receiver
+        PrintV1CpiBuilder::new(&ctx.accounts.token_metadata_program.to_account_info())
+            .edition_metadata(&ctx.accounts.edition_metadata.to_account_info())
+            .edition(&ctx.accounts.edition.to_account_info())
+            .edition_mint(&ctx.accounts.edition_mint.to_account_info(), true)
+            .edition_token_account_owner(&ctx.accounts.receiver.to_account_info())
+            .edition_token_account(&ctx.accounts.edition_token_account.to_account_info())
+            .edition_mint_authority(&ctx.accounts.material_authority.to_account_info())
+            .master_edition(&ctx.accounts.master_edition.to_account_info())
+            .edition_marker_pda(&ctx.accounts.edition_marker.to_account_info())
+            .payer(&ctx.accounts.payer.to_account_info())
+            .master_token_account_owner(&ctx.accounts.material_authority.to_account_info())
+            .master_token_account(&ctx.accounts.master_token_account.to_account_info())
+            .master_metadata(&ctx.accounts.master_metadata.to_account_info())
+            .update_authority(&ctx.accounts.material_authority.to_account_info())
+            .spl_token_program(&ctx.accounts.token_program.to_account_info())
+            .spl_ata_program(&ctx.accounts.associated_token_program.to_account_info())
+            .sysvar_instructions(&ctx.accounts.sysvar_instructions.to_account_info())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .edition_number(edition_number)
+            .invoke_signed(signer_seeds)?;
+        
+        // Per-instance attributes stay on-chain next to the print
+        let material_edition = &mut ctx.accounts.material_edition;
+        material_edition.master_mint = material.mint;
+        material_edition.mint = ctx.accounts.edition_mint.key();
+        material_edition.edition_number = edition_number;
+        material_edition.attributes = attributes;
+        material_edition.printed_at = material.updated_at;
+        material_edition.bump = ctx.bumps.material_edition;
+        
+        msg!("Printed edition {} of material {}", edition_number, material.mint);
+        
+        Ok(())
+    }
+    
+    pub fn add_minter(
+        ctx: Context<AddMinter>,
+        minter: Pubkey,
+        material_mint: Pubkey,
+    ) -> Result<()> {
+        let minter_permit = &mut ctx.accounts.minter_permit;
+        minter_permit.minter = minter;
+        minter_permit.material_mint = material_mint;
+        minter_permit.granted_by = ctx.accounts.authority.key();
+        minter_permit.granted_at = Clock::get()?.unix_timestamp;
+        minter_permit.bump = ctx.bumps.minter_permit;
+        
+        msg!("Minter {} permitted for material {}", minter, material_mint);
+        
+        Ok(())
+    }
+    
+    pub fn remove_minter(
+        ctx: Context<RemoveMinter>,
+    ) -> Result<()> {
+        msg!("Minter removed: {}", ctx.accounts.minter_permit.minter);
+        
+        Ok(())
+    }
+    
+    pub fn migrate_material_authority(
+        ctx: Context<MigrateMaterialAuthority>,
+    ) -> Result<()> {
+        let authority_info = ctx.accounts.material_authority.to_account_info();
+        
+        // Only the layout from before the royalty cap existed is accepted
+        let legacy = {
+            let data = authority_info.try_borrow_data()?;
+            require!(
+                data.len() == 8 + LegacyMaterialAuthority::LEN &&
+                data[..8] == MaterialAuthority::discriminator(),
+                ErrorCode::InvalidMigrationAccount
+            );
+            LegacyMaterialAuthority::deserialize(&mut &data[8..])
+                .map_err(|_| error!(ErrorCode::InvalidMigrationAccount))?
+        };
+        
+        // Top up rent for the larger layout, then grow the account
+        let new_len = 8 + MaterialAuthority::LEN;
+        let rent_due = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(authority_info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: authority_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        authority_info.realloc(new_len, false)?;
+        
+        let material_authority = MaterialAuthority {
+            authority: legacy.authority,
+            bump: legacy.bump,
+            max_royalty_bps: DEFAULT_MAX_ROYALTY_BPS,
+        };
+        
+        let mut data = authority_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        material_authority.try_serialize(&mut writer)?;
+        
+        let migration_receipt = &mut ctx.accounts.migration_receipt;
+        migration_receipt.account = authority_info.key();
+        migration_receipt.migrated_at = Clock::get()?.unix_timestamp;
+        migration_receipt.bump = ctx.bumps.migration_receipt;
+        
+        msg!("Material authority migrated with a {} bps royalty cap", DEFAULT_MAX_ROYALTY_BPS);
+        
+        Ok(())
+    }
+    
+    pub fn migrate_material(
+        ctx: Context<MigrateMaterial>,
+    ) -> Result<()> {
+        let material_info = ctx.accounts.material.to_account_info();
+        
+        // Read the account in the layout that stored type and rarity as free-form strings
+        let legacy = {
+            let data = material_info.try_borrow_data()?;
+            require!(
+                data.len() > 8 && data[..8] == Material::discriminator(),
+                ErrorCode::InvalidMigrationAccount
+            );
+            LegacyMaterial::deserialize(&mut &data[8..])
+                .map_err(|_| error!(ErrorCode::InvalidMigrationAccount))?
+        };
+        
+        let material = Material {
+            mint: legacy.mint,
+            material_type: MaterialCategory::from_name(&legacy.material_type)
+                .ok_or(ErrorCode::UnrecognizedMaterialType)?,
+            rarity: Rarity::from_name(&legacy.rarity)
+                .ok_or(ErrorCode::UnrecognizedRarity)?,
+            current_supply: legacy.current_supply,
+            max_supply: legacy.max_supply,
+            created_at: legacy.created_at,
+            updated_at: Clock::get()?.unix_timestamp,
+            authority: legacy.authority,
+            is_active: legacy.is_active,
+            mode: MaterialMode::Fungible,
+        };
+        
+        // The typed layout is smaller, so it fits in place; clear what the strings left behind
+        let mut data = material_info.try_borrow_mut_data()?;
+        data.fill(0);
+        let mut writer: &mut [u8] = &mut data[..];
+        material.try_serialize(&mut writer)?;
+        
+        let migration_receipt = &mut ctx.accounts.migration_receipt;
+        migration_receipt.account = material_info.key();
+        migration_receipt.migrated_at = material.updated_at;
+        migration_receipt.bump = ctx.bumps.migration_receipt;
+        
+        msg!("Material migrated: {}", material.mint);
+        
+        Ok(())
+    }
+    
+    pub fn migrate_material_mode(
+        ctx: Context<MigrateMaterialMode>,
+    ) -> Result<()> {
+        let material_info = ctx.accounts.material.to_account_info();
+        
+        // Only the typed layout from before materials had a mode is accepted; accounts converted
+        // from the string layout kept their larger size and already read as fungible
+        let legacy = {
+            let data = material_info.try_borrow_data()?;
+            require!(
+                data.len() == 8 + LegacyTypedMaterial::LEN &&
+                data[..8] == Material::discriminator(),
+                ErrorCode::InvalidMigrationAccount
+            );
+            LegacyTypedMaterial::deserialize(&mut &data[8..])
+                .map_err(|_| error!(ErrorCode::InvalidMigrationAccount))?
+        };
+        
+        // Top up rent for the larger layout, then grow the account
+        let new_len = 8 + Material::LEN;
+        let rent_due = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(material_info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: material_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        material_info.realloc(new_len, false)?;
+        
+        // Every material minted before the mode existed was fungible
+        let material = Material {
+            mint: legacy.mint,
+            material_type: legacy.material_type,
+            rarity: legacy.rarity,
+            current_supply: legacy.current_supply,
+            max_supply: legacy.max_supply,
+            created_at: legacy.created_at,
+            updated_at: Clock::get()?.unix_timestamp,
+            authority: legacy.authority,
+            is_active: legacy.is_active,
+            mode: MaterialMode::Fungible,
+        };
+        
+        let mut data = material_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        material.try_serialize(&mut writer)?;
+        
+        let migration_receipt = &mut ctx.accounts.migration_receipt;
+        migration_receipt.account = material_info.key();
+        migration_receipt.migrated_at = material.updated_at;
+        migration_receipt.bump = ctx.bumps.migration_receipt;
+        
+        msg!("Material migrated to fungible mode: {}", material.mint);
+        
+        Ok(())
+    }
+    
     pub fn set_material_active(
         ctx: Context<UpdateMaterialStatus>,
         is_active: bool,
@@ -211,6 +857,76 @@ pub mod material_nft {
     }
 }
 
+// Helper function to check a signer may create materials in the MagicVial collection: the
+// material authority itself or the crafting program's authority, which creates recipe results
+fn is_collection_creator(authority: &Pubkey, material_authority: &MaterialAuthority) -> bool {
+    let (crafting_authority, _) =
+        Pubkey::find_program_address(&[b"crafting_authority".as_ref()], &CRAFTING_PROGRAM_ID);
+    *authority == material_authority.authority || *authority == crafting_authority
+}
+
+// Helper function to size a Token-2022 mint's metadata extension, which the token program appends after creation
+fn token_metadata_len(name: &str, symbol: &str, uri: &str, fields: &[(String, String)]) -> usize {
+    4 +  // extension type and length
+    32 + // update_authority
+    32 + // mint
+    4 + name.len() +
+    4 + symbol.len() +
+    4 + uri.len() +
+    4 + fields.iter().map(|(key, value)| 4 + key.len() + 4 + value.len()).sum::<usize>()
+}
+
+// Helper function to encode a royalty split as one on-mint metadata field, `address:share:verified` per creator
+fn creators_field(creators: &[Creator]) -> String {
+    creators
+        .iter()
+        .map(|creator| format!("{}:{}:{}", creator.address, creator.share, creator.verified))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Helper function to validate a royalty split and build the metadata creator list
+fn royalty_creators(
+    seller_fee_basis_points: u16,
+    creators: &[CreatorShare],
+    max_royalty_bps: u16,
+    material_authority: Pubkey,
+    previous_creators: &[Creator],
+) -> Result<Vec<Creator>> {
+    require!(
+        seller_fee_basis_points <= max_royalty_bps,
+        ErrorCode::RoyaltyAboveCap
+    );
+    require!(
+        !creators.is_empty() && creators.len() <= MAX_CREATORS,
+        ErrorCode::InvalidCreatorCount
+    );
+    
+    let total_share: u16 = creators.iter().map(|creator| creator.share as u16).sum();
+    require!(total_share == 100, ErrorCode::InvalidCreatorShares);
+    
+    for (i, creator) in creators.iter().enumerate() {
+        require!(
+            !creators[..i].iter().any(|other| other.address == creator.address),
+            ErrorCode::DuplicateCreator
+        );
+    }
+    
+    // The material authority signs every metadata CPI, so it is verified directly;
+    // anyone else signs later through verify_creator
+    Ok(creators
+        .iter()
+        .map(|creator| Creator {
+            address: creator.address,
+            verified: creator.address == material_authority ||
+                previous_creators
+                    .iter()
+                    .any(|previous| previous.address == creator.address && previous.verified),
+            share: creator.share,
+        })
+        .collect())
+}
+
 #[derive(Accounts)]
 #[instruction(authority_bump: u8)]
 pub struct Initialize<'info> {
@@ -231,42 +947,262 @@ pub struct Initialize<'info> {
 }
 
 #[derive(Accounts)]
-pub struct CreateMaterial<'info> {
+pub struct SetMaxRoyalty<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + Material::LEN,
-        seeds = [b"material".as_ref(), mint.key().as_ref()],
-        bump,
+        mut,
+        seeds = [b"material_authority".as_ref()],
+        bump = material_authority.bump,
+        constraint = material_authority.authority == authority.key() @ ErrorCode::Unauthorized,
     )]
-    pub material: Account<'info, Material>,
+    pub material_authority: Account<'info, MaterialAuthority>,
     
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMaterialCollection<'info> {
     #[account(
-        mut,
         seeds = [b"material_authority".as_ref()],
         bump = material_authority.bump,
+        constraint = material_authority.authority == authority.key() @ ErrorCode::Unauthorized,
     )]
     pub material_authority: Account<'info, MaterialAuthority>,
     
-    #[account(mut)]
-    pub mint: Account<'info, Mint>,
-    
+    // One collection per program, so materials can find it without extra state
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"collection_mint".as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = material_authority,
+        mint::freeze_authority = material_authority,
+    )]
+    pub collection_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = collection_mint,
+        associated_token::authority = material_authority,
+    )]
+    pub collection_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Created and checked by the token metadata program
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: Created and checked by the token metadata program
+    #[account(mut)]
+    pub collection_master_edition: UncheckedAccount<'info>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    
+    /// CHECK: This is the token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: Instructions sysvar, read by the token metadata program
+    #[account(address = sysvar::instructions::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMaterial<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Material::LEN,
+        seeds = [b"material".as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub material: Account<'info, Material>,
+    
+    #[account(
+        mut,
+        seeds = [b"material_authority".as_ref()],
+        bump = material_authority.bump,
+    )]
+    pub material_authority: Account<'info, MaterialAuthority>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
     /// CHECK: This is not dangerous because we don't read or write from this account
     #[account(mut)]
     pub metadata: UncheckedAccount<'info>,
     
+    #[account(
+        seeds = [b"collection_mint".as_ref()],
+        bump,
+    )]
+    pub collection_mint: Account<'info, Mint>,
+    
+    /// CHECK: Checked against the collection mint by the token metadata program
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: Checked against the collection mint by the token metadata program
+    pub collection_master_edition: UncheckedAccount<'info>,
+    
+    /// CHECK: Unique materials only; created and checked by the token metadata program
     #[account(mut)]
+    pub master_edition: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Unique materials only; the material authority's token account, created in the instruction
+    #[account(mut)]
+    pub master_token_account: Option<UncheckedAccount<'info>>,
+    
+    // Materials are verified into the official collection, so only approved creators may make them
+    #[account(
+        constraint = is_collection_creator(&authority.key(), &material_authority) @ ErrorCode::Unauthorized,
+    )]
     pub authority: Signer<'info>,
     
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     
-    /// CHECK: This is not dangerous because we don't read or write from this account
+    /// CHECK: This is the token metadata program
+    #[account(address = mpl_token_metadata::ID)]
     pub token_metadata_program: UncheckedAccount<'info>,
     
     pub system_program: Program<'info, System>,
+    
+    /// CHECK: Instructions sysvar, read by the token metadata program
+    #[account(address = sysvar::instructions::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct CreateMaterial2022<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Material::LEN,
+        seeds = [b"material".as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub material: Account<'info, Material>,
+    
+    #[account(
+        seeds = [b"material_authority".as_ref()],
+        bump = material_authority.bump,
+    )]
+    pub material_authority: Account<'info, MaterialAuthority>,
+    
+    // A fresh mint address, created with its extensions in the instruction
+    #[account(mut)]
+    pub mint: Signer<'info>,
+    
+    // Recorded on the mint, as token metadata materials are verified into it
+    #[account(
+        seeds = [b"collection_mint".as_ref()],
+        bump,
+    )]
+    pub collection_mint: Account<'info, Mint>,
+    
+    // The mint names the official collection, so only approved creators may make materials
+    #[account(
+        constraint = is_collection_creator(&authority.key(), &material_authority) @ ErrorCode::Unauthorized,
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyCreator<'info> {
+    #[account(
+        seeds = [b"material".as_ref(), material.mint.as_ref()],
+        bump,
+        constraint = material.authority == creator.key() @ ErrorCode::Unauthorized,
+    )]
+    pub material: Account<'info, Material>,
+    
+    /// CHECK: Derived from the material's mint; the token metadata program checks the creator list
+    #[account(
+        mut,
+        seeds = [b"metadata".as_ref(), token_metadata_program.key().as_ref(), material.mint.as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key(),
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    
+    pub creator: Signer<'info>,
+    
+    /// CHECK: This is the token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: Instructions sysvar, read by the token metadata program
+    #[account(address = sysvar::instructions::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMaterialRoyalties<'info> {
+    #[account(
+        seeds = [b"material".as_ref(), material.mint.as_ref()],
+        bump,
+        constraint = material.authority == authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub material: Account<'info, Material>,
+    
+    #[account(
+        seeds = [b"material_authority".as_ref()],
+        bump = material_authority.bump,
+    )]
+    pub material_authority: Account<'info, MaterialAuthority>,
+    
+    #[account(address = material.mint)]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Derived from the material's mint and parsed as token metadata in the instruction
+    #[account(
+        mut,
+        seeds = [b"metadata".as_ref(), token_metadata_program.key().as_ref(), material.mint.as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key(),
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: This is the token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: Instructions sysvar, read by the token metadata program
+    #[account(address = sysvar::instructions::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+}
+
 #[derive(Accounts)]
 pub struct MintMaterial<'info> {
     #[account(
@@ -284,25 +1220,179 @@ pub struct MintMaterial<'info> {
     pub material_authority: Account<'info, MaterialAuthority>,
     
     #[account(mut)]
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
     
     #[account(
         init_if_needed,
-        payer = receiver,
+        payer = payer,
         associated_token::mint = mint,
         associated_token::authority = receiver,
+        associated_token::token_program = token_program,
+    )]
+    pub token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    /// CHECK: Any wallet may receive minted materials
+    pub receiver: UncheckedAccount<'info>,
+    
+    pub minter: Signer<'info>,
+    
+    // A permit covers a single material, so it is derived from this mint
+    #[account(
+        seeds = [b"minter".as_ref(), minter.key().as_ref(), mint.key().as_ref()],
+        bump = minter_permit.bump,
+    )]
+    pub minter_permit: Option<Account<'info, MinterPermit>>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct PrintMaterial<'info> {
+    #[account(
+        mut,
+        seeds = [b"material".as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub material: Account<'info, Material>,
+    
+    #[account(
+        seeds = [b"material_authority".as_ref()],
+        bump = material_authority.bump,
+    )]
+    pub material_authority: Account<'info, MaterialAuthority>,
+    
+    // The master mint of the unique material
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Derived from the master mint and read by the token metadata program
+    #[account(
+        seeds = [b"metadata".as_ref(), token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key(),
+    )]
+    pub master_metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: Checked against the master mint by the token metadata program
+    #[account(mut)]
+    pub master_edition: UncheckedAccount<'info>,
+    
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = material_authority,
+    )]
+    pub master_token_account: Account<'info, TokenAccount>,
+    
+    // A fresh keypair; the token metadata program creates the mint
+    #[account(mut)]
+    pub edition_mint: Signer<'info>,
+    
+    /// CHECK: Created and checked by the token metadata program
+    #[account(mut)]
+    pub edition_metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: Created and checked by the token metadata program
+    #[account(mut)]
+    pub edition: UncheckedAccount<'info>,
+    
+    /// CHECK: Created and checked by the token metadata program
+    #[account(mut)]
+    pub edition_marker: UncheckedAccount<'info>,
+    
+    /// CHECK: The receiver's token account for the print, created by the token metadata program
+    #[account(mut)]
+    pub edition_token_account: UncheckedAccount<'info>,
+    
+    /// CHECK: Any wallet may receive printed materials
+    pub receiver: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MaterialEdition::LEN,
+        seeds = [b"edition".as_ref(), edition_mint.key().as_ref()],
+        bump,
     )]
-    pub token_account: Account<'info, TokenAccount>,
+    pub material_edition: Account<'info, MaterialEdition>,
+    
+    pub minter: Signer<'info>,
+    
+    // A permit covers a single material, so it is derived from this mint
+    #[account(
+        seeds = [b"minter".as_ref(), minter.key().as_ref(), mint.key().as_ref()],
+        bump = minter_permit.bump,
+    )]
+    pub minter_permit: Option<Account<'info, MinterPermit>>,
     
     #[account(mut)]
-    pub receiver: Signer<'info>,
+    pub payer: Signer<'info>,
     
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+    
+    /// CHECK: This is the token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: Instructions sysvar, read by the token metadata program
+    #[account(address = sysvar::instructions::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(minter: Pubkey, material_mint: Pubkey)]
+pub struct AddMinter<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MinterPermit::LEN,
+        seeds = [b"minter".as_ref(), minter.as_ref(), material_mint.as_ref()],
+        bump,
+    )]
+    pub minter_permit: Account<'info, MinterPermit>,
+    
+    #[account(
+        seeds = [b"material_authority".as_ref()],
+        bump = material_authority.bump,
+        constraint = material_authority.authority == authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub material_authority: Account<'info, MaterialAuthority>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct RemoveMinter<'info> {
+    #[account(
+        mut,
+        seeds = [b"minter".as_ref(), minter_permit.minter.as_ref(), minter_permit.material_mint.as_ref()],
+        bump = minter_permit.bump,
+        close = authority,
+    )]
+    pub minter_permit: Account<'info, MinterPermit>,
+    
+    #[account(
+        seeds = [b"material_authority".as_ref()],
+        bump = material_authority.bump,
+        constraint = material_authority.authority == authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub material_authority: Account<'info, MaterialAuthority>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateMaterialStatus<'info> {
     #[account(
@@ -321,39 +1411,266 @@ pub struct UpdateMaterialStatus<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateMaterialAuthority<'info> {
+    /// CHECK: Still in the legacy layout, so it is parsed and resized in the instruction
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"material_authority".as_ref()],
+        bump,
+    )]
+    pub material_authority: UncheckedAccount<'info>,
+    
+    // Each account can only be migrated once
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MigrationReceipt::LEN,
+        seeds = [b"migration".as_ref(), material_authority.key().as_ref()],
+        bump,
+    )]
+    pub migration_receipt: Account<'info, MigrationReceipt>,
+    
+    // The conversion is deterministic, so anyone may pay to run it
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMaterial<'info> {
+    /// CHECK: Still in the legacy layout, so it is parsed by hand in the instruction
+    #[account(
+        mut,
+        owner = crate::ID,
+    )]
+    pub material: UncheckedAccount<'info>,
+    
+    // Each account can only be migrated once
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MigrationReceipt::LEN,
+        seeds = [b"migration".as_ref(), material.key().as_ref()],
+        bump,
+    )]
+    pub migration_receipt: Account<'info, MigrationReceipt>,
+    
+    // The conversion is deterministic, so anyone may pay to run it
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMaterialMode<'info> {
+    /// CHECK: Still in the layout without a mode, so it is parsed and resized in the instruction
+    #[account(
+        mut,
+        owner = crate::ID,
+    )]
+    pub material: UncheckedAccount<'info>,
+    
+    // Each account can only be migrated to the mode layout once
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MigrationReceipt::LEN,
+        seeds = [b"migration_mode".as_ref(), material.key().as_ref()],
+        bump,
+    )]
+    pub migration_receipt: Account<'info, MigrationReceipt>,
+    
+    // The conversion is deterministic, so anyone may pay to run it
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[account]
 pub struct MaterialAuthority {
     pub authority: Pubkey,
     pub bump: u8,
+    pub max_royalty_bps: u16, // Platform-wide cap on seller fee basis points
 }
 
 impl MaterialAuthority {
-    pub const LEN: usize = 32 + 1; // pubkey + bump
+    pub const LEN: usize = 32 + // authority
+                           1 +  // bump
+                           2;   // max_royalty_bps
+}
+
+// Material authority layout before the royalty cap was added
+#[derive(AnchorDeserialize)]
+struct LegacyMaterialAuthority {
+    authority: Pubkey,
+    bump: u8,
+}
+
+impl LegacyMaterialAuthority {
+    const LEN: usize = 32 + 1; // pubkey + bump
+}
+
+// Transfer behaviour built into a Token-2022 material's mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TransferRule {
+    Transferable,
+    NonTransferable,                     // Soulbound: tokens stay with the wallet they are minted to
+    TransferHook { program_id: Pubkey }, // Every transfer is approved by the hook program
+}
+
+impl TransferRule {
+    // Mint extensions needed for this rule, alongside the metadata pointer every material gets
+    fn extensions(&self) -> Vec<ExtensionType> {
+        match self {
+            TransferRule::Transferable => vec![ExtensionType::MetadataPointer],
+            TransferRule::NonTransferable => vec![
+                ExtensionType::MetadataPointer,
+                ExtensionType::NonTransferable,
+            ],
+            TransferRule::TransferHook { .. } => vec![
+                ExtensionType::MetadataPointer,
+                ExtensionType::TransferHook,
+            ],
+        }
+    }
+}
+
+// One entry of a material's royalty split
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreatorShare {
+    pub address: Pubkey,
+    pub share: u8, // Percentage of royalties, all shares sum to 100
+}
+
+#[account]
+pub struct MinterPermit {
+    pub minter: Pubkey,
+    pub material_mint: Pubkey, // The only material this permit mints
+    pub granted_by: Pubkey,
+    pub granted_at: i64,
+    pub bump: u8,
+}
+
+impl MinterPermit {
+    pub const LEN: usize = 32 + // minter
+                           32 + // material_mint
+                           32 + // granted_by
+                           8 +  // granted_at
+                           1;   // bump
 }
 
 #[account]
 pub struct Material {
     pub mint: Pubkey,
-    pub material_type: String, // Basic, Rare, Seasonal, Mysterious
-    pub rarity: String,        // Common, Rare, Epic, Legendary
+    pub material_type: MaterialCategory,
+    pub rarity: Rarity,
     pub current_supply: u64,
     pub max_supply: u64,
     pub created_at: i64,
     pub updated_at: i64,
     pub authority: Pubkey,
     pub is_active: bool,
+    pub mode: MaterialMode,
 }
 
 impl Material {
     pub const LEN: usize = 32 + // mint
-                           32 + // material_type (max)
-                           16 + // rarity (max)
+                           MaterialCategory::LEN + // material_type
+                           Rarity::LEN + // rarity
                            8 +  // current_supply
                            8 +  // max_supply
                            8 +  // created_at
                            8 +  // updated_at
                            32 + // authority
-                           1;   // is_active
+                           1 +  // is_active
+                           MaterialMode::LEN; // mode
+}
+
+// Material layout before materials had a mode
+#[derive(AnchorDeserialize)]
+struct LegacyTypedMaterial {
+    mint: Pubkey,
+    material_type: MaterialCategory,
+    rarity: Rarity,
+    current_supply: u64,
+    max_supply: u64,
+    created_at: i64,
+    updated_at: i64,
+    authority: Pubkey,
+    is_active: bool,
+}
+
+impl LegacyTypedMaterial {
+    const LEN: usize = Material::LEN - MaterialMode::LEN;
+}
+
+// One trait of a single print, e.g. who crafted it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EditionAttribute {
+    pub trait_type: String,
+    pub value: String,
+}
+
+impl EditionAttribute {
+    pub const MAX_TRAIT_TYPE_LEN: usize = 32;
+    pub const MAX_VALUE_LEN: usize = 64;
+    
+    pub const LEN: usize = 4 + Self::MAX_TRAIT_TYPE_LEN + // trait_type
+                           4 + Self::MAX_VALUE_LEN;       // value
+}
+
+// A numbered print of a unique material
+#[account]
+pub struct MaterialEdition {
+    pub master_mint: Pubkey,
+    pub mint: Pubkey,
+    pub edition_number: u64,
+    pub attributes: Vec<EditionAttribute>,
+    pub printed_at: i64,
+    pub bump: u8,
+}
+
+impl MaterialEdition {
+    pub const MAX_ATTRIBUTES: usize = 8;
+    
+    pub const LEN: usize = 32 + // master_mint
+                           32 + // mint
+                           8 +  // edition_number
+                           4 + Self::MAX_ATTRIBUTES * EditionAttribute::LEN + // attributes
+                           8 +  // printed_at
+                           1;   // bump
+}
+
+// Material layout before type and rarity became shared enums
+#[derive(AnchorDeserialize)]
+struct LegacyMaterial {
+    mint: Pubkey,
+    material_type: String,
+    rarity: String,
+    current_supply: u64,
+    max_supply: u64,
+    created_at: i64,
+    updated_at: i64,
+    authority: Pubkey,
+    is_active: bool,
+}
+
+#[account]
+pub struct MigrationReceipt {
+    pub account: Pubkey,
+    pub migrated_at: i64,
+    pub bump: u8,
+}
+
+impl MigrationReceipt {
+    pub const LEN: usize = 32 + // account
+                           8 +  // migrated_at
+                           1;   // bump
 }
 
 #[error_code]
@@ -367,12 +1684,6 @@ pub enum ErrorCode {
     #[msg("URI cannot be empty")]
     EmptyUri,
     
-    #[msg("Material type cannot be empty")]
-    EmptyType,
-    
-    #[msg("Rarity cannot be empty")]
-    EmptyRarity,
-    
     #[msg("Max supply must be greater than zero")]
     InvalidMaxSupply,
     
@@ -385,6 +1696,51 @@ pub enum ErrorCode {
     #[msg("Mint amount exceeds maximum supply")]
     ExceedsMaxSupply,
     
+    #[msg("Account is not a material in the legacy layout")]
+    InvalidMigrationAccount,
+    
+    #[msg("Legacy material type is not a known category")]
+    UnrecognizedMaterialType,
+    
+    #[msg("Legacy rarity is not a known rarity")]
+    UnrecognizedRarity,
+    
+    #[msg("Signer is not permitted to mint this material")]
+    UnauthorizedMinter,
+    
     #[msg("Unauthorized access")]
     Unauthorized,
+    
+    #[msg("Royalty cap cannot exceed 10000 basis points")]
+    InvalidRoyaltyCap,
+    
+    #[msg("Royalty exceeds the platform cap")]
+    RoyaltyAboveCap,
+    
+    #[msg("A material needs between one and five creators")]
+    InvalidCreatorCount,
+    
+    #[msg("Creator shares must sum to 100")]
+    InvalidCreatorShares,
+    
+    #[msg("Creator is listed more than once")]
+    DuplicateCreator,
+    
+    #[msg("Metadata account could not be parsed")]
+    InvalidMetadata,
+    
+    #[msg("Unique materials need their master edition accounts")]
+    MissingEditionAccounts,
+    
+    #[msg("Unique materials are issued with print_material")]
+    UniqueMaterial,
+    
+    #[msg("Fungible materials are issued with mint_material")]
+    FungibleMaterial,
+    
+    #[msg("Too many edition attributes")]
+    TooManyEditionAttributes,
+    
+    #[msg("Edition attribute is too long")]
+    EditionAttributeTooLong,
 } 
\ No newline at end of file
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use randomness::program::Randomness as RandomnessProgram;
+use randomness::cpi::accounts::FulfillRandomness;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+// Stand-in oracle for local validators and tests. Point the randomness
+// config's oracle at this program's `oracle` PDA, then call `fulfill`
+// with any value to answer a pending request.
+#[program]
+pub mod mock_oracle {
+    use super::*;
+    
+    pub fn fulfill(
+        ctx: Context<Fulfill>,
+        oracle_randomness: [u8; 32],
+    ) -> Result<()> {
+        let oracle_bump = ctx.bumps.oracle;
+        let seeds = &[
+            b"oracle".as_ref(),
+            &[oracle_bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        let cpi_program = ctx.accounts.randomness_program.to_account_info();
+        let cpi_accounts = FulfillRandomness {
+            request: ctx.accounts.request.to_account_info(),
+            oracle: ctx.accounts.oracle.to_account_info(),
+        };
+        
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        randomness::cpi::fulfill_randomness(cpi_ctx, oracle_randomness)?;
+        
+        msg!("Mock oracle fulfilled request: {}", ctx.accounts.request.key());
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Fulfill<'info> {
+    /// CHECK: This account is checked in the CPI call
+    #[account(mut)]
+    pub request: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA that signs as the oracle, holds no data
+    #[account(
+        seeds = [b"oracle".as_ref()],
+        bump,
+    )]
+    pub oracle: UncheckedAccount<'info>,
+    
+    pub randomness_program: Program<'info, RandomnessProgram>,
+}
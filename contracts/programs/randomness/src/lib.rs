@@ -0,0 +1,262 @@
+use anchor_lang::prelude::*;
+use solana_program::keccak;
+use solana_program::sysvar::clock::Clock;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[program]
+pub mod randomness {
+    use super::*;
+    
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        authority_bump: u8,
+        oracle: Pubkey,
+    ) -> Result<()> {
+        let randomness_config = &mut ctx.accounts.randomness_config;
+        randomness_config.authority = ctx.accounts.authority.key();
+        randomness_config.oracle = oracle;
+        randomness_config.bump = authority_bump;
+        
+        msg!("Randomness program initialized with oracle: {}", oracle);
+        
+        Ok(())
+    }
+    
+    pub fn set_oracle(
+        ctx: Context<SetOracle>,
+        oracle: Pubkey,
+    ) -> Result<()> {
+        let randomness_config = &mut ctx.accounts.randomness_config;
+        randomness_config.oracle = oracle;
+        
+        msg!("Randomness oracle updated: {}", oracle);
+        
+        Ok(())
+    }
+    
+    pub fn request_randomness(
+        ctx: Context<RequestRandomness>,
+        consumer: Pubkey,
+    ) -> Result<()> {
+        // The request is bound to its consumer before anyone knows the outcome
+        let request = &mut ctx.accounts.request;
+        request.requester = ctx.accounts.requester.key();
+        request.consumer = consumer;
+        request.oracle = ctx.accounts.randomness_config.oracle;
+        request.request_slot = Clock::get()?.slot;
+        request.fulfilled_slot = None;
+        request.randomness = [0u8; 32];
+        request.status = RequestStatus::Pending;
+        request.bump = ctx.bumps.request;
+        
+        msg!("Randomness requested for consumer: {}", consumer);
+        
+        Ok(())
+    }
+    
+    pub fn fulfill_randomness(
+        ctx: Context<FulfillRandomness>,
+        oracle_randomness: [u8; 32],
+    ) -> Result<()> {
+        let request = &mut ctx.accounts.request;
+        
+        // The oracle must answer in a later slot than the request
+        let current_slot = Clock::get()?.slot;
+        require!(
+            current_slot > request.request_slot,
+            RandomnessError::FulfilledTooEarly
+        );
+        
+        // Mix in the request address so one oracle value cannot serve two requests
+        let request_key = request.key();
+        request.randomness = keccak::hashv(&[
+            oracle_randomness.as_ref(),
+            request_key.as_ref(),
+        ]).to_bytes();
+        request.fulfilled_slot = Some(current_slot);
+        request.status = RequestStatus::Fulfilled;
+        
+        msg!("Randomness fulfilled for consumer: {}", request.consumer);
+        
+        Ok(())
+    }
+}
+
+/// Derives an independent 32-byte value from fulfilled randomness for a named roll.
+pub fn derive(randomness: &[u8; 32], domain: &[u8]) -> [u8; 32] {
+    keccak::hashv(&[randomness.as_ref(), domain]).to_bytes()
+}
+
+/// Rolls a uniform value in `0..bound` for the given domain.
+pub fn roll_below(randomness: &[u8; 32], domain: &[u8], bound: u64) -> u64 {
+    if bound == 0 {
+        return 0;
+    }
+    let bytes = derive(randomness, domain);
+    let value = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+    value % bound
+}
+
+/// Rolls a percentage in `0..100` for the given domain.
+pub fn roll_percent(randomness: &[u8; 32], domain: &[u8]) -> u8 {
+    roll_below(randomness, domain, 100) as u8
+}
+
+/// Picks an index from `weights` with probability proportional to its weight.
+pub fn roll_weighted(randomness: &[u8; 32], domain: &[u8], weights: &[u64]) -> Option<usize> {
+    let total_weight: u64 = weights.iter().sum();
+    if total_weight == 0 {
+        return None;
+    }
+    
+    let mut remaining = roll_below(randomness, domain, total_weight);
+    for (i, &weight) in weights.iter().enumerate() {
+        if remaining < weight {
+            return Some(i);
+        }
+        remaining -= weight;
+    }
+    
+    None
+}
+
+#[derive(Accounts)]
+#[instruction(authority_bump: u8)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RandomnessConfig::LEN,
+        seeds = [b"randomness_config".as_ref()],
+        bump = authority_bump,
+    )]
+    pub randomness_config: Account<'info, RandomnessConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetOracle<'info> {
+    #[account(
+        mut,
+        seeds = [b"randomness_config".as_ref()],
+        bump = randomness_config.bump,
+        constraint = randomness_config.authority == authority.key() @ RandomnessError::Unauthorized,
+    )]
+    pub randomness_config: Account<'info, RandomnessConfig>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(consumer: Pubkey)]
+pub struct RequestRandomness<'info> {
+    #[account(
+        init,
+        payer = requester,
+        space = 8 + RandomnessRequest::LEN,
+        seeds = [b"randomness_request".as_ref(), requester.key().as_ref(), consumer.as_ref()],
+        bump,
+    )]
+    pub request: Account<'info, RandomnessRequest>,
+    
+    #[account(
+        seeds = [b"randomness_config".as_ref()],
+        bump = randomness_config.bump,
+    )]
+    pub randomness_config: Account<'info, RandomnessConfig>,
+    
+    #[account(mut)]
+    pub requester: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FulfillRandomness<'info> {
+    #[account(
+        mut,
+        seeds = [b"randomness_request".as_ref(), request.requester.as_ref(), request.consumer.as_ref()],
+        bump = request.bump,
+        constraint = request.status == RequestStatus::Pending @ RandomnessError::AlreadyFulfilled,
+        constraint = request.oracle == oracle.key() @ RandomnessError::Unauthorized,
+    )]
+    pub request: Account<'info, RandomnessRequest>,
+    
+    pub oracle: Signer<'info>,
+}
+
+#[account]
+pub struct RandomnessConfig {
+    pub authority: Pubkey,
+    pub oracle: Pubkey,
+    pub bump: u8,
+}
+
+impl RandomnessConfig {
+    pub const LEN: usize = 32 + // authority
+                           32 + // oracle
+                           1;   // bump
+}
+
+#[account]
+pub struct RandomnessRequest {
+    pub requester: Pubkey,
+    pub consumer: Pubkey,
+    pub oracle: Pubkey,
+    pub request_slot: u64,
+    pub fulfilled_slot: Option<u64>,
+    pub randomness: [u8; 32],
+    pub status: RequestStatus,
+    pub bump: u8,
+}
+
+impl RandomnessRequest {
+    pub const LEN: usize = 32 + // requester
+                           32 + // consumer
+                           32 + // oracle
+                           8 +  // request_slot
+                           9 +  // fulfilled_slot (Option<u64>)
+                           32 + // randomness
+                           1 +  // status enum
+                           1;   // bump
+    
+    /// Returns the fulfilled randomness, or an error while the oracle has not answered.
+    pub fn value(&self) -> Result<[u8; 32]> {
+        require!(
+            self.status == RequestStatus::Fulfilled,
+            RandomnessError::NotFulfilled
+        );
+        Ok(self.randomness)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RequestStatus {
+    Pending,
+    Fulfilled,
+}
+
+#[error_code]
+pub enum RandomnessError {
+    #[msg("Randomness request has already been fulfilled")]
+    AlreadyFulfilled,
+    
+    #[msg("Randomness request has not been fulfilled yet")]
+    NotFulfilled,
+    
+    #[msg("Randomness must be fulfilled in a later slot than the request")]
+    FulfilledTooEarly,
+    
+    #[msg("Randomness request is not bound to this consumer")]
+    InvalidConsumer,
+    
+    #[msg("Unauthorized access")]
+    Unauthorized,
+}
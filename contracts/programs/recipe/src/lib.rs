@@ -1,6 +1,11 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount};
+use anchor_spl::token_2022;
+use std::collections::BTreeMap;
+use solana_program::pubkey;
 use solana_program::sysvar::clock::Clock;
+use magicvial_types::{MaterialMode, Rarity};
+use randomness::{RandomnessRequest, RequestStatus};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -31,7 +36,12 @@ pub mod recipe {
         ingredients: Vec<Ingredient>,
         result_name: String,
         result_description: String,
-        result_rarity: String,
+        result_uri: String,
+        result_rarity: Rarity,
+        result_mode: MaterialMode,
+        result_token_program: Pubkey,
+        result_quantity: u64,
+        refund_percentage: u8,
     ) -> Result<()> {
         // Validate inputs
         require!(!name.is_empty(), RecipeError::EmptyName);
@@ -41,7 +51,20 @@ pub mod recipe {
         require!(!ingredients.is_empty(), RecipeError::NoIngredients);
         require!(!result_name.is_empty(), RecipeError::EmptyResultName);
         require!(!result_description.is_empty(), RecipeError::EmptyResultDescription);
-        require!(!result_rarity.is_empty(), RecipeError::EmptyResultRarity);
+        require!(!result_uri.is_empty(), RecipeError::EmptyResultUri);
+        require!(result_quantity > 0, RecipeError::InvalidResultQuantity);
+        // Every unique result is its own print, so such recipes yield exactly one
+        require!(
+            result_mode == MaterialMode::Fungible || result_quantity == 1,
+            RecipeError::UniqueResultQuantity
+        );
+        // Print editions are a token metadata program feature, so unique results stay on SPL Token
+        require!(
+            result_token_program == token::ID ||
+            (result_token_program == token_2022::ID && result_mode == MaterialMode::Fungible),
+            RecipeError::InvalidResultTokenProgram
+        );
+        require!(refund_percentage <= 100, RecipeError::InvalidRefundPercentage);
         
         // Further validate ingredients
         let mut total_materials = 0;
@@ -53,6 +76,16 @@ pub mod recipe {
         require!(total_materials > 0, RecipeError::NoMaterials);
         require!(ingredients.len() <= 10, RecipeError::TooManyIngredients);
         
+        // Intermediate ingredients must come from existing recipes without forming a cycle
+        // Remaining accounts: every recipe reachable through intermediate ingredients
+        let stage_recipes = load_stage_recipes(ctx.remaining_accounts)?;
+        let stage = recipe_stage(
+            ctx.accounts.recipe.key(),
+            &ingredients,
+            &stage_recipes,
+            &mut Vec::new(),
+        )?;
+        
         // Create the recipe account
         let recipe = &mut ctx.accounts.recipe;
         recipe.name = name;
@@ -68,7 +101,13 @@ pub mod recipe {
         recipe.ingredients = ingredients;
         recipe.result_name = result_name;
         recipe.result_description = result_description;
+        recipe.result_uri = result_uri;
         recipe.result_rarity = result_rarity;
+        recipe.result_mode = result_mode;
+        recipe.result_token_program = result_token_program;
+        recipe.result_quantity = result_quantity;
+        recipe.refund_percentage = refund_percentage;
+        recipe.stage = stage;
         recipe.times_crafted = 0;
         
         msg!("Recipe created successfully: {}", recipe.name);
@@ -81,8 +120,29 @@ pub mod recipe {
         is_approved: bool,
     ) -> Result<()> {
         // Only the recipe authority can approve recipes
+        if is_approved {
+            // Re-check the recipe graph and require every intermediate recipe to be approved
+            let stage_recipes = load_stage_recipes(ctx.remaining_accounts)?;
+            recipe_stage(
+                ctx.accounts.recipe.key(),
+                &ctx.accounts.recipe.ingredients,
+                &stage_recipes,
+                &mut Vec::new(),
+            )?;
+            require!(
+                stage_recipes.values().all(|stage_recipe| stage_recipe.is_approved),
+                RecipeError::StageRecipeNotApproved
+            );
+        }
+        
         let recipe = &mut ctx.accounts.recipe;
         
+        // The result material is registered from this URI on the first successful craft
+        require!(
+            !is_approved || !recipe.result_uri.is_empty(),
+            RecipeError::EmptyResultUri
+        );
+        
         recipe.is_approved = is_approved;
         recipe.updated_at = Clock::get()?.unix_timestamp;
         
@@ -91,6 +151,21 @@ pub mod recipe {
         Ok(())
     }
     
+    pub fn set_result_uri(
+        ctx: Context<SetResultUri>,
+        result_uri: String,
+    ) -> Result<()> {
+        require!(!result_uri.is_empty(), RecipeError::EmptyResultUri);
+        
+        let recipe = &mut ctx.accounts.recipe;
+        recipe.result_uri = result_uri;
+        recipe.updated_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Recipe result URI updated: {}", recipe.name);
+        
+        Ok(())
+    }
+    
     pub fn set_recipe_enabled(
         ctx: Context<UpdateRecipeStatus>,
         is_enabled: bool,
@@ -112,8 +187,118 @@ pub mod recipe {
         Ok(())
     }
     
+    pub fn migrate_recipe(
+        ctx: Context<MigrateRecipe>,
+    ) -> Result<()> {
+        let recipe_info = ctx.accounts.recipe.to_account_info();
+        
+        // Read the account in the layout that stored the result rarity as a free-form string;
+        // recipes already in the current layout would be misread, so they are refused
+        let legacy = {
+            let data = recipe_info.try_borrow_data()?;
+            require!(
+                data.len() > 8 && data[..8] == Recipe::discriminator(),
+                RecipeError::InvalidMigrationAccount
+            );
+            require!(
+                Recipe::try_deserialize(&mut &data[..]).is_err(),
+                RecipeError::InvalidMigrationAccount
+            );
+            LegacyRecipe::deserialize(&mut &data[8..])
+                .map_err(|_| error!(RecipeError::InvalidMigrationAccount))?
+        };
+        
+        // Baseline recipes issued legendary results as unique materials and the rest as fungible
+        let result_rarity = Rarity::from_name(&legacy.result_rarity)
+            .ok_or(RecipeError::UnrecognizedRarity)?;
+        let result_mode = match result_rarity {
+            Rarity::Legendary => MaterialMode::Unique,
+            _ => MaterialMode::Fungible,
+        };
+        
+        // Baseline recipes used raw materials only, made one unit and refunded nothing
+        let recipe = Recipe {
+            name: legacy.name,
+            description: legacy.description,
+            difficulty: legacy.difficulty,
+            ingredients: legacy
+                .ingredients
+                .into_iter()
+                .map(|ingredient| Ingredient {
+                    material_mint: ingredient.material_mint,
+                    quantity: ingredient.quantity,
+                    source_recipe: None,
+                })
+                .collect(),
+            crafting_time: legacy.crafting_time,
+            success_rate: legacy.success_rate,
+            creator: legacy.creator,
+            is_approved: legacy.is_approved,
+            is_enabled: legacy.is_enabled,
+            created_at: legacy.created_at,
+            updated_at: Clock::get()?.unix_timestamp,
+            result_name: legacy.result_name,
+            result_description: legacy.result_description,
+            // Legacy recipes have no result metadata until the authority sets it
+            result_uri: String::new(),
+            result_rarity,
+            result_mode,
+            result_token_program: token::ID,
+            result_quantity: 1,
+            refund_percentage: 0,
+            stage: 1,
+            times_crafted: legacy.times_crafted,
+        };
+        
+        // The new fields usually fit in the space reserved at creation; grow the account if not
+        let new_len = 8 + recipe.try_to_vec()?.len();
+        if new_len > recipe_info.data_len() {
+            let rent_due = Rent::get()?
+                .minimum_balance(new_len)
+                .saturating_sub(recipe_info.lamports());
+            if rent_due > 0 {
+                anchor_lang::system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.authority.to_account_info(),
+                            to: recipe_info.clone(),
+                        },
+                    ),
+                    rent_due,
+                )?;
+            }
+            recipe_info.realloc(new_len, false)?;
+        }
+        
+        // Clear what the longer legacy fields left behind
+        let mut data = recipe_info.try_borrow_mut_data()?;
+        data.fill(0);
+        let mut writer: &mut [u8] = &mut data[..];
+        recipe.try_serialize(&mut writer)?;
+        
+        let migration_receipt = &mut ctx.accounts.migration_receipt;
+        migration_receipt.account = recipe_info.key();
+        migration_receipt.migrated_at = recipe.updated_at;
+        migration_receipt.bump = ctx.bumps.migration_receipt;
+        
+        msg!("Recipe migrated: {}", recipe.name);
+        
+        Ok(())
+    }
+    
+    pub fn get_raw_material_bill(
+        ctx: Context<GetRawMaterialBill>,
+        crafts: u64,
+    ) -> Result<Vec<Ingredient>> {
+        // Remaining accounts: every recipe reachable through intermediate ingredients
+        let stage_recipes = load_stage_recipes(ctx.remaining_accounts)?;
+        raw_material_bill(&ctx.accounts.recipe.ingredients, crafts, &stage_recipes)
+    }
+    
     pub fn start_crafting(
         ctx: Context<StartCrafting>,
+        nonce: u64,
     ) -> Result<()> {
         let recipe = &ctx.accounts.recipe;
         let crafting = &mut ctx.accounts.crafting;
@@ -128,6 +313,9 @@ pub mod recipe {
         crafting.start_time = Clock::get()?.unix_timestamp;
         crafting.estimated_completion_time = crafting.start_time + recipe.crafting_time as i64;
         crafting.status = CraftingStatus::InProgress;
+        crafting.randomness_request = ctx.accounts.randomness_request.key();
+        crafting.nonce = nonce;
+        crafting.bump = ctx.bumps.crafting;
         
         // Increment recipe usage counter
         // Need to get a mutable reference to recipe
@@ -164,12 +352,10 @@ pub mod recipe {
             RecipeError::CraftingNotReady
         );
         
-        // Determine success or failure based on recipe success rate
-        // In a real implementation, this would use a verifiable random function
-        // For this example, we'll use a simple hash of various timestamps
-        let crafting_seed = (current_time.wrapping_mul(recipe.times_crafted as i64)) as u64;
-        let random_value = crafting_seed % 100;
-        let success = random_value < recipe.success_rate as u64;
+        // Determine success or failure from the randomness bound at start
+        let randomness = ctx.accounts.randomness_request.value()?;
+        let roll = randomness::roll_percent(&randomness, b"recipe_crafting_success");
+        let success = roll < recipe.success_rate;
         
         // Update crafting session
         crafting.completion_time = Some(current_time);
@@ -185,12 +371,152 @@ pub mod recipe {
             recipe.name
         );
         
-        // In a complete implementation, this would mint the result token if successful
+        // On success the crafting program mints the result material after this returns
+        
+        Ok(())
+    }
+    
+    pub fn cancel_crafting(
+        ctx: Context<CancelCrafting>,
+    ) -> Result<()> {
+        let crafting = &mut ctx.accounts.crafting;
+        
+        // A session holds nothing, so it can be abandoned even after the oracle answers;
+        // the crafting program refuses to refund escrowed materials once the outcome is known
+        require!(
+            crafting.status == CraftingStatus::InProgress,
+            RecipeError::InvalidCraftingStatus
+        );
+        
+        crafting.status = CraftingStatus::Cancelled;
+        crafting.completion_time = Some(Clock::get()?.unix_timestamp);
+        
+        msg!("Crafting cancelled for recipe: {}", crafting.recipe);
+        
+        Ok(())
+    }
+    
+    pub fn close_crafting(
+        ctx: Context<CloseCrafting>,
+    ) -> Result<()> {
+        // Finished sessions can be closed to reclaim their rent
+        require!(
+            ctx.accounts.crafting.status != CraftingStatus::InProgress,
+            RecipeError::InvalidCraftingStatus
+        );
+        
+        msg!("Crafting session closed for recipe: {}", ctx.accounts.crafting.recipe);
         
         Ok(())
     }
 }
 
+// Deepest chain of intermediate recipes a recipe may depend on
+pub const MAX_RECIPE_DEPTH: usize = 8;
+
+// Crafting program that mints recipe results; it depends on this program, so its ID is pinned here
+pub const CRAFTING_PROGRAM_ID: Pubkey = pubkey!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+// Helper function to derive the mint a recipe's crafted results are minted from
+pub fn result_mint_address(recipe: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"result_mint".as_ref(), recipe.as_ref()], &CRAFTING_PROGRAM_ID).0
+}
+
+// Helper function to load the recipe accounts supplied for graph checks, keyed by address
+pub fn load_stage_recipes(accounts: &[AccountInfo]) -> Result<BTreeMap<Pubkey, Recipe>> {
+    let mut recipes = BTreeMap::new();
+    for account in accounts {
+        let recipe: Account<Recipe> = Account::try_from(account)?;
+        recipes.insert(account.key(), recipe.into_inner());
+    }
+    Ok(recipes)
+}
+
+// Helper function to compute a recipe's stage: 1 when it uses only raw materials, otherwise
+// one more than its deepest intermediate. Fails on cycles, on intermediates not supplied and
+// on intermediates that name a mint other than their source recipe's result mint.
+pub fn recipe_stage(
+    recipe: Pubkey,
+    ingredients: &[Ingredient],
+    recipes: &BTreeMap<Pubkey, Recipe>,
+    path: &mut Vec<Pubkey>,
+) -> Result<u8> {
+    require!(path.len() < MAX_RECIPE_DEPTH, RecipeError::RecipeTooDeep);
+    path.push(recipe);
+    
+    let mut stage = 1;
+    for ingredient in ingredients {
+        if let Some(source) = ingredient.source_recipe {
+            require!(
+                ingredient.material_mint == result_mint_address(&source),
+                RecipeError::InvalidIntermediateMaterial
+            );
+            require!(!path.contains(&source), RecipeError::RecipeCycle);
+            let source_recipe = recipes.get(&source).ok_or(RecipeError::MissingStageRecipe)?;
+            let source_stage = recipe_stage(source, &source_recipe.ingredients, recipes, path)?;
+            stage = stage.max(source_stage + 1);
+        }
+    }
+    
+    path.pop();
+    Ok(stage)
+}
+
+// Helper function to expand `crafts` successful crafts of a recipe into the raw materials
+// they consume, crafting each intermediate as often as needed to cover its quantity
+pub fn raw_material_bill(
+    ingredients: &[Ingredient],
+    crafts: u64,
+    recipes: &BTreeMap<Pubkey, Recipe>,
+) -> Result<Vec<Ingredient>> {
+    let mut bill = Vec::new();
+    add_to_bill(ingredients, crafts, recipes, &mut bill, 0)?;
+    Ok(bill)
+}
+
+fn add_to_bill(
+    ingredients: &[Ingredient],
+    crafts: u64,
+    recipes: &BTreeMap<Pubkey, Recipe>,
+    bill: &mut Vec<Ingredient>,
+    depth: usize,
+) -> Result<()> {
+    require!(depth < MAX_RECIPE_DEPTH, RecipeError::RecipeTooDeep);
+    
+    for ingredient in ingredients {
+        let needed = ingredient
+            .quantity
+            .checked_mul(crafts)
+            .ok_or(RecipeError::ArithmeticOverflow)?;
+        
+        match ingredient.source_recipe {
+            Some(source) => {
+                let source_recipe = recipes.get(&source).ok_or(RecipeError::MissingStageRecipe)?;
+                let source_crafts = needed
+                    .checked_add(source_recipe.result_quantity - 1)
+                    .ok_or(RecipeError::ArithmeticOverflow)?
+                    / source_recipe.result_quantity;
+                add_to_bill(&source_recipe.ingredients, source_crafts, recipes, bill, depth + 1)?;
+            }
+            None => match bill.iter_mut().find(|entry| entry.material_mint == ingredient.material_mint) {
+                Some(entry) => {
+                    entry.quantity = entry
+                        .quantity
+                        .checked_add(needed)
+                        .ok_or(RecipeError::ArithmeticOverflow)?;
+                }
+                None => bill.push(Ingredient {
+                    material_mint: ingredient.material_mint,
+                    quantity: needed,
+                    source_recipe: None,
+                }),
+            },
+        }
+    }
+    
+    Ok(())
+}
+
 #[derive(Accounts)]
 #[instruction(authority_bump: u8)]
 pub struct Initialize<'info> {
@@ -220,7 +546,12 @@ pub struct Initialize<'info> {
     ingredients: Vec<Ingredient>,
     result_name: String,
     result_description: String,
-    result_rarity: String,
+    result_uri: String,
+    result_rarity: Rarity,
+    result_mode: MaterialMode,
+    result_token_program: Pubkey,
+    result_quantity: u64,
+    refund_percentage: u8,
 )]
 pub struct CreateRecipe<'info> {
     #[account(
@@ -229,7 +560,7 @@ pub struct CreateRecipe<'info> {
         space = 8 + Recipe::LEN + 
                 (ingredients.len() * Ingredient::LEN) +
                 name.len() + description.len() + 
-                result_name.len() + result_description.len() + result_rarity.len() + 
+                result_name.len() + result_description.len() + result_uri.len() + 
                 200, // extra space for future fields
     )]
     pub recipe: Account<'info, Recipe>,
@@ -265,6 +596,69 @@ pub struct ApproveRecipe<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(result_uri: String)]
+pub struct SetResultUri<'info> {
+    // Only the recipe authority sets the metadata crafted results are issued with
+    #[account(
+        mut,
+        realloc = recipe.to_account_info().data_len() + result_uri.len().saturating_sub(recipe.result_uri.len()),
+        realloc::payer = authority,
+        realloc::zero = false,
+    )]
+    pub recipe: Account<'info, Recipe>,
+    
+    #[account(
+        seeds = [b"recipe_authority".as_ref()],
+        bump = recipe_authority.bump,
+        constraint = recipe_authority.authority == authority.key() @ RecipeError::Unauthorized,
+    )]
+    pub recipe_authority: Account<'info, RecipeAuthority>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateRecipe<'info> {
+    /// CHECK: Still in the legacy layout, so it is parsed by hand in the instruction
+    #[account(
+        mut,
+        owner = crate::ID,
+    )]
+    pub recipe: UncheckedAccount<'info>,
+    
+    // Each account can only be migrated once
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MigrationReceipt::LEN,
+        seeds = [b"migration".as_ref(), recipe.key().as_ref()],
+        bump,
+    )]
+    pub migration_receipt: Account<'info, MigrationReceipt>,
+    
+    // Only the recipe authority converts recipes, and pays for any growth
+    #[account(
+        seeds = [b"recipe_authority".as_ref()],
+        bump = recipe_authority.bump,
+        constraint = recipe_authority.authority == authority.key() @ RecipeError::Unauthorized,
+    )]
+    pub recipe_authority: Account<'info, RecipeAuthority>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetRawMaterialBill<'info> {
+    pub recipe: Account<'info, Recipe>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateRecipeStatus<'info> {
     #[account(mut)]
@@ -283,12 +677,14 @@ pub struct UpdateRecipeStatus<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(nonce: u64)]
 pub struct StartCrafting<'info> {
+    // The nonce lets a crafter run the same recipe more than once
     #[account(
         init,
         payer = crafter,
         space = 8 + Crafting::LEN,
-        seeds = [b"crafting".as_ref(), recipe.key().as_ref(), crafter.key().as_ref()],
+        seeds = [b"crafting".as_ref(), recipe.key().as_ref(), crafter.key().as_ref(), nonce.to_le_bytes().as_ref()],
         bump,
     )]
     pub crafting: Account<'info, Crafting>,
@@ -296,6 +692,14 @@ pub struct StartCrafting<'info> {
     #[account(mut)]
     pub recipe: Account<'info, Recipe>,
     
+    // Must be requested for this session before the oracle answers
+    #[account(
+        constraint = randomness_request.requester == crafter.key() @ RecipeError::InvalidRandomnessRequest,
+        constraint = randomness_request.consumer == crafting.key() @ RecipeError::InvalidRandomnessRequest,
+        constraint = randomness_request.status == RequestStatus::Pending @ RecipeError::RandomnessAlreadyFulfilled,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
     #[account(mut)]
     pub crafter: Signer<'info>,
     
@@ -307,8 +711,8 @@ pub struct StartCrafting<'info> {
 pub struct CompleteCrafting<'info> {
     #[account(
         mut,
-        seeds = [b"crafting".as_ref(), recipe.key().as_ref(), crafter.key().as_ref()],
-        bump,
+        seeds = [b"crafting".as_ref(), recipe.key().as_ref(), crafter.key().as_ref(), crafting.nonce.to_le_bytes().as_ref()],
+        bump = crafting.bump,
     )]
     pub crafting: Account<'info, Crafting>,
     
@@ -317,12 +721,48 @@ pub struct CompleteCrafting<'info> {
     )]
     pub recipe: Account<'info, Recipe>,
     
+    #[account(
+        constraint = crafting.randomness_request == randomness_request.key() @ RecipeError::InvalidRandomnessRequest,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
     #[account(mut)]
     pub crafter: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct CancelCrafting<'info> {
+    #[account(
+        mut,
+        seeds = [b"crafting".as_ref(), crafting.recipe.as_ref(), crafter.key().as_ref(), crafting.nonce.to_le_bytes().as_ref()],
+        bump = crafting.bump,
+    )]
+    pub crafting: Account<'info, Crafting>,
+    
+    #[account(
+        constraint = crafting.randomness_request == randomness_request.key() @ RecipeError::InvalidRandomnessRequest,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
+    pub crafter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCrafting<'info> {
+    #[account(
+        mut,
+        seeds = [b"crafting".as_ref(), crafting.recipe.as_ref(), crafter.key().as_ref(), crafting.nonce.to_le_bytes().as_ref()],
+        bump = crafting.bump,
+        close = crafter,
+    )]
+    pub crafting: Account<'info, Crafting>,
+    
+    #[account(mut)]
+    pub crafter: Signer<'info>,
+}
+
 #[account]
 pub struct RecipeAuthority {
     pub authority: Pubkey,
@@ -348,7 +788,13 @@ pub struct Recipe {
     pub updated_at: i64,
     pub result_name: String,
     pub result_description: String,
-    pub result_rarity: String,
+    pub result_uri: String,    // metadata URI the result material is created with
+    pub result_rarity: Rarity,
+    pub result_mode: MaterialMode, // fungible units of one mint, or numbered prints of a master edition
+    pub result_token_program: Pubkey, // SPL Token or Token-2022, fixed for the result mint
+    pub result_quantity: u64,
+    pub refund_percentage: u8, // share of escrowed materials returned on failure, 0-100
+    pub stage: u8,             // 1 for raw materials only, +1 per level of intermediates
     pub times_crafted: u64,
 }
 
@@ -366,10 +812,56 @@ impl Recipe {
                          8 + // updated_at
                          4 + // string prefix for result_name
                          4 + // string prefix for result_description
-                         4 + // string prefix for result_rarity
+                         4 + // string prefix for result_uri
+                         Rarity::LEN + // result_rarity
+                         MaterialMode::LEN + // result_mode
+                         32 + // result_token_program
+                         8 + // result_quantity
+                         1 + // refund_percentage
+                         1 + // stage
                          8;  // times_crafted
 }
 
+// Recipe layout before the result rarity became a shared enum
+#[derive(AnchorDeserialize)]
+struct LegacyRecipe {
+    name: String,
+    description: String,
+    difficulty: RecipeDifficulty,
+    ingredients: Vec<LegacyIngredient>,
+    crafting_time: u64,
+    success_rate: u8,
+    creator: Pubkey,
+    is_approved: bool,
+    is_enabled: bool,
+    created_at: i64,
+    updated_at: i64,
+    result_name: String,
+    result_description: String,
+    result_rarity: String,
+    times_crafted: u64,
+}
+
+// Ingredient layout before intermediate ingredients existed
+#[derive(AnchorDeserialize)]
+struct LegacyIngredient {
+    material_mint: Pubkey,
+    quantity: u64,
+}
+
+#[account]
+pub struct MigrationReceipt {
+    pub account: Pubkey,
+    pub migrated_at: i64,
+    pub bump: u8,
+}
+
+impl MigrationReceipt {
+    pub const LEN: usize = 32 + // account
+                           8 +  // migrated_at
+                           1;   // bump
+}
+
 #[account]
 pub struct Crafting {
     pub recipe: Pubkey,
@@ -378,6 +870,9 @@ pub struct Crafting {
     pub estimated_completion_time: i64,
     pub completion_time: Option<i64>,
     pub status: CraftingStatus,
+    pub randomness_request: Pubkey,
+    pub nonce: u64,
+    pub bump: u8,
 }
 
 impl Crafting {
@@ -386,18 +881,23 @@ impl Crafting {
                           8 +  // start_time
                           8 +  // estimated_completion_time
                           9 +  // completion_time (Option<i64>)
-                          1;   // status enum
+                          1 +  // status enum
+                          32 + // randomness_request
+                          8 +  // nonce
+                          1;   // bump
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub struct Ingredient {
     pub material_mint: Pubkey,
     pub quantity: u64,
+    pub source_recipe: Option<Pubkey>, // recipe producing this intermediate, None for raw materials
 }
 
 impl Ingredient {
     pub const LEN: usize = 32 + // material_mint
-                           8;   // quantity
+                           8 +  // quantity
+                           33;  // source_recipe (Option<Pubkey>)
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
@@ -439,8 +939,20 @@ pub enum RecipeError {
     #[msg("Result description cannot be empty")]
     EmptyResultDescription,
     
-    #[msg("Result rarity cannot be empty")]
-    EmptyResultRarity,
+    #[msg("Result URI cannot be empty")]
+    EmptyResultUri,
+    
+    #[msg("Result quantity must be greater than zero")]
+    InvalidResultQuantity,
+    
+    #[msg("Unique results can only be crafted one at a time")]
+    UniqueResultQuantity,
+    
+    #[msg("Results must use SPL Token, or Token-2022 when fungible")]
+    InvalidResultTokenProgram,
+    
+    #[msg("Refund percentage must be between 0 and 100")]
+    InvalidRefundPercentage,
     
     #[msg("Ingredient quantity must be greater than zero")]
     InvalidIngredientQuantity,
@@ -466,6 +978,36 @@ pub enum RecipeError {
     #[msg("Invalid recipe")]
     InvalidRecipe,
     
+    #[msg("Randomness request is not bound to this crafting session")]
+    InvalidRandomnessRequest,
+    
+    #[msg("Randomness was fulfilled before crafting started")]
+    RandomnessAlreadyFulfilled,
+    
+    #[msg("Account is not a recipe in the legacy layout")]
+    InvalidMigrationAccount,
+    
+    #[msg("Legacy rarity is not a known rarity")]
+    UnrecognizedRarity,
+    
+    #[msg("Recipe graph contains a cycle")]
+    RecipeCycle,
+    
+    #[msg("An intermediate ingredient's recipe was not supplied")]
+    MissingStageRecipe,
+    
+    #[msg("Intermediate recipes must be approved first")]
+    StageRecipeNotApproved,
+    
+    #[msg("Recipe depends on too many levels of intermediates")]
+    RecipeTooDeep,
+    
+    #[msg("Intermediate ingredient is not its source recipe's result")]
+    InvalidIntermediateMaterial,
+    
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    
     #[msg("Unauthorized access")]
     Unauthorized,
 } 
\ No newline at end of file
@@ -21,11 +21,11 @@ pub fn register_developer(
         return Err(MagicVialError::InvalidParameters.into());
     }
     
-    if github_username.len() > 64 {
+    if github_username.len() > DeveloperProfile::MAX_GITHUB_USERNAME_LEN {
         return Err(MagicVialError::DataSizeExceeded.into());
     }
     
-    if display_name.len() > 64 {
+    if display_name.len() > DeveloperProfile::MAX_DISPLAY_NAME_LEN {
         return Err(MagicVialError::DataSizeExceeded.into());
     }
     
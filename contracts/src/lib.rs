@@ -27,6 +27,8 @@ pub mod programs {
     pub mod crafting;
     pub mod guild;
     pub mod token;
+    pub mod achievement;
+    pub mod recipe_market;
 }
 
 // Re-export modules for convenience
@@ -35,6 +37,8 @@ pub use programs::recipe;
 pub use programs::crafting;
 pub use programs::guild;
 pub use programs::token;
+pub use programs::achievement;
+pub use programs::recipe_market;
 
 declare_id!("MVia1LtH6EbzKLmH5BUxY8rNR2UdGoJSBhK5yN124Gi");
 
@@ -138,7 +142,7 @@ pub mod magicvial {
 // Context Structures
 #[derive(Accounts)]
 pub struct CreateToken<'info> {
-    #[account(init, payer = developer, space = TokenConfig::LEN)]
+    #[account(init, payer = developer, space = 8 + TokenConfig::LEN)]
     pub token_config: Account<'info, TokenConfig>,
     
     #[account(mut)]
@@ -160,7 +164,7 @@ pub struct CreateLiquidityPool<'info> {
     #[account(mut)]
     pub token_mint: Account<'info, Mint>,
     
-    #[account(init, payer = developer, space = LiquidityPool::LEN)]
+    #[account(init, payer = developer, space = 8 + LiquidityPool::LEN)]
     pub liquidity_pool: Account<'info, LiquidityPool>,
     
     #[account(mut)]
@@ -172,7 +176,7 @@ pub struct CreateLiquidityPool<'info> {
 
 #[derive(Accounts)]
 pub struct RegisterDeveloper<'info> {
-    #[account(init, payer = user, space = DeveloperProfile::LEN)]
+    #[account(init, payer = user, space = 8 + DeveloperProfile::LEN)]
     pub developer_profile: Account<'info, DeveloperProfile>,
     
     #[account(mut)]
@@ -204,7 +208,7 @@ pub struct SubmitForVerification<'info> {
     #[account(mut)]
     pub developer_profile: Account<'info, DeveloperProfile>,
     
-    #[account(init, payer = developer, space = ProjectVerification::LEN)]
+    #[account(init, payer = developer, space = 8 + ProjectVerification::LEN)]
     pub project_verification: Account<'info, ProjectVerification>,
     
     #[account(mut)]
@@ -259,12 +263,16 @@ pub struct TokenConfig {
 }
 
 impl TokenConfig {
-    pub const LEN: usize = 8 + // discriminator
-        32 + // developer pubkey
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_SYMBOL_LEN: usize = 16;
+    pub const MAX_URI_LEN: usize = 128;
+    
+    // Excludes the 8-byte discriminator, which `space = 8 + LEN` adds
+    pub const LEN: usize = 32 + // developer pubkey
         32 + // mint pubkey
-        64 + // name
-        16 + // symbol
-        128 + // uri
+        4 + Self::MAX_NAME_LEN + // name
+        4 + Self::MAX_SYMBOL_LEN + // symbol
+        4 + Self::MAX_URI_LEN + // uri
         1 + // decimals
         8 + // supply_cap
         8 + // created_at
@@ -288,8 +296,7 @@ pub struct LiquidityPool {
 }
 
 impl LiquidityPool {
-    pub const LEN: usize = 8 + // discriminator
-        32 + // token_mint
+    pub const LEN: usize = 32 + // token_mint
         32 + // pool_authority
         8 + // initial_price
         8 + // current_price
@@ -316,10 +323,12 @@ pub struct DeveloperProfile {
 }
 
 impl DeveloperProfile {
-    pub const LEN: usize = 8 + // discriminator
-        32 + // authority pubkey
-        64 + // github_username
-        64 + // display_name
+    pub const MAX_GITHUB_USERNAME_LEN: usize = 64;
+    pub const MAX_DISPLAY_NAME_LEN: usize = 64;
+    
+    pub const LEN: usize = 32 + // authority pubkey
+        4 + Self::MAX_GITHUB_USERNAME_LEN + // github_username
+        4 + Self::MAX_DISPLAY_NAME_LEN + // display_name
         8 + // reputation_score
         32 + // verification_hash
         1 + // is_verified
@@ -344,12 +353,16 @@ pub struct ProjectVerification {
 }
 
 impl ProjectVerification {
-    pub const LEN: usize = 8 + // discriminator
-        32 + // developer pubkey
-        64 + // project_name
-        256 + // project_description
-        128 + // github_repo
-        128 + // documentation_uri
+    pub const MAX_PROJECT_NAME_LEN: usize = 64;
+    pub const MAX_DESCRIPTION_LEN: usize = 256;
+    pub const MAX_GITHUB_REPO_LEN: usize = 128;
+    pub const MAX_DOCUMENTATION_URI_LEN: usize = 128;
+    
+    pub const LEN: usize = 32 + // developer pubkey
+        4 + Self::MAX_PROJECT_NAME_LEN + // project_name
+        4 + Self::MAX_DESCRIPTION_LEN + // project_description
+        4 + Self::MAX_GITHUB_REPO_LEN + // github_repo
+        4 + Self::MAX_DOCUMENTATION_URI_LEN + // documentation_uri
         8 + // submission_time
         1 + // avg_review_score
         4 + // reviews_count
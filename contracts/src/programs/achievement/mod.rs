@@ -0,0 +1,586 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token_2022::{self, Token2022};
+use solana_program::program::{invoke, invoke_signed};
+use spl_token_2022::extension::{metadata_pointer, ExtensionType};
+use spl_token_2022::instruction::AuthorityType;
+
+// Evidence accounts written by the other MagicVial programs
+use crate::crafting::{CrafterStats, CraftingRecord};
+use crate::recipe::RecipeDiscoveryRecord;
+use magicvial_types::Rarity;
+
+declare_id!("achXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
+
+// Symbol every badge mint carries in its on-mint metadata
+const BADGE_SYMBOL: &str = "MVBADGE";
+
+#[program]
+pub mod magic_vial_achievement {
+    use super::*;
+
+    // Initialize the achievement system
+    pub fn initialize(
+        ctx: Context<Initialize>,
+    ) -> Result<()> {
+        let achievement_config = &mut ctx.accounts.achievement_config;
+        achievement_config.authority = ctx.accounts.authority.key();
+        achievement_config.bump = ctx.bumps.achievement_config;
+        
+        Ok(())
+    }
+
+    // Define a new achievement and who may hand it out
+    pub fn create_achievement(
+        ctx: Context<CreateAchievement>,
+        name: String,
+        description: String,
+        uri: String,
+        trigger: AchievementTrigger,
+        issuer: Pubkey, // Wallet allowed to award this achievement by hand
+    ) -> Result<()> {
+        require!(!name.is_empty(), AchievementError::EmptyName);
+        require!(name.len() <= Achievement::MAX_NAME_LEN, AchievementError::NameTooLong);
+        require!(
+            description.len() <= Achievement::MAX_DESCRIPTION_LEN,
+            AchievementError::DescriptionTooLong
+        );
+        require!(uri.len() <= Achievement::MAX_URI_LEN, AchievementError::UriTooLong);
+        
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.name = name;
+        achievement.description = description;
+        achievement.uri = uri;
+        achievement.trigger = trigger;
+        achievement.issuer = issuer;
+        achievement.active = true;
+        achievement.awarded_count = 0;
+        achievement.created_at = Clock::get()?.unix_timestamp;
+        achievement.bump = ctx.bumps.achievement;
+        
+        Ok(())
+    }
+
+    // Stop or resume handing out an achievement
+    pub fn set_achievement_active(
+        ctx: Context<UpdateAchievement>,
+        active: bool,
+    ) -> Result<()> {
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.active = active;
+        
+        Ok(())
+    }
+
+    // Award an achievement by hand; used for triggers with no on-chain evidence such as guild quests
+    pub fn award_achievement(
+        ctx: Context<AwardAchievement>,
+    ) -> Result<()> {
+        require!(ctx.accounts.achievement.active, AchievementError::AchievementInactive);
+        
+        issue_badge(
+            &ctx.accounts.issuer.to_account_info(),
+            &ctx.accounts.wallet.to_account_info(),
+            &ctx.accounts.achievement,
+            &ctx.accounts.badge_mint.to_account_info(),
+            ctx.bumps.badge_mint,
+            &ctx.accounts.wallet_token_account.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        
+        let badge = &mut ctx.accounts.badge;
+        badge.achievement = ctx.accounts.achievement.key();
+        badge.wallet = ctx.accounts.wallet.key();
+        badge.mint = ctx.accounts.badge_mint.key();
+        badge.awarded_by = ctx.accounts.issuer.key();
+        badge.awarded_at = Clock::get()?.unix_timestamp;
+        badge.bump = ctx.bumps.badge;
+        
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.awarded_count = achievement
+            .awarded_count
+            .checked_add(1)
+            .ok_or(AchievementError::ArithmeticOverflow)?;
+        
+        Ok(())
+    }
+
+    // Claim an achievement by presenting the record that proves its trigger
+    pub fn claim_achievement(
+        ctx: Context<ClaimAchievement>,
+    ) -> Result<()> {
+        let achievement = &ctx.accounts.achievement;
+        require!(achievement.active, AchievementError::AchievementInactive);
+        
+        verify_trigger(
+            &achievement.trigger,
+            &ctx.accounts.evidence.to_account_info(),
+            &ctx.accounts.wallet.key(),
+        )?;
+        
+        issue_badge(
+            &ctx.accounts.wallet.to_account_info(),
+            &ctx.accounts.wallet.to_account_info(),
+            &ctx.accounts.achievement,
+            &ctx.accounts.badge_mint.to_account_info(),
+            ctx.bumps.badge_mint,
+            &ctx.accounts.wallet_token_account.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        
+        let badge = &mut ctx.accounts.badge;
+        badge.achievement = ctx.accounts.achievement.key();
+        badge.wallet = ctx.accounts.wallet.key();
+        badge.mint = ctx.accounts.badge_mint.key();
+        badge.awarded_by = ctx.accounts.wallet.key();
+        badge.awarded_at = Clock::get()?.unix_timestamp;
+        badge.bump = ctx.bumps.badge;
+        
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.awarded_count = achievement
+            .awarded_count
+            .checked_add(1)
+            .ok_or(AchievementError::ArithmeticOverflow)?;
+        
+        Ok(())
+    }
+}
+
+// Helper function to check that an evidence account satisfies an achievement's trigger for a wallet
+fn verify_trigger(
+    trigger: &AchievementTrigger,
+    evidence: &AccountInfo,
+    wallet: &Pubkey,
+) -> Result<()> {
+    match trigger {
+        AchievementTrigger::FirstDiscovery { recipe } => {
+            let record = Account::<RecipeDiscoveryRecord>::try_from(evidence)?;
+            require!(record.explorer == *wallet, AchievementError::EvidenceNotOwned);
+            require!(record.is_first, AchievementError::TriggerNotMet);
+            if let Some(recipe) = recipe {
+                require!(record.recipe == *recipe, AchievementError::TriggerNotMet);
+            }
+        }
+        AchievementTrigger::CrafterLevel { level } => {
+            let stats = Account::<CrafterStats>::try_from(evidence)?;
+            require!(stats.crafter == *wallet, AchievementError::EvidenceNotOwned);
+            require!(stats.experience_level >= *level, AchievementError::TriggerNotMet);
+        }
+        AchievementTrigger::FirstLegendaryCraft => {
+            // Badges are one per wallet, so the first legendary craft presented is the one that counts
+            let record = Account::<CraftingRecord>::try_from(evidence)?;
+            require!(record.crafter == *wallet, AchievementError::EvidenceNotOwned);
+            require!(
+                record.resolved && record.success && record.result_rarity == Rarity::Legendary,
+                AchievementError::TriggerNotMet
+            );
+        }
+        // Guild quests are tracked by the guild program, so their issuer awards these by hand
+        AchievementTrigger::GuildQuests { .. } | AchievementTrigger::Manual => {
+            return err!(AchievementError::AwardedByIssuer);
+        }
+    }
+    
+    Ok(())
+}
+
+// Helper function to size the badge metadata the token program appends to the mint
+fn badge_metadata_len(name: &str, uri: &str) -> usize {
+    4 +  // extension type and length
+    32 + // update_authority
+    32 + // mint
+    4 + name.len() +
+    4 + BADGE_SYMBOL.len() +
+    4 + uri.len() +
+    4    // additional_metadata, empty
+}
+
+// Helper function to mint a one-of-one, non-transferable badge into the wallet's Token-2022 account
+fn issue_badge<'info>(
+    payer: &AccountInfo<'info>,
+    wallet: &AccountInfo<'info>,
+    achievement: &Account<'info, Achievement>,
+    badge_mint: &AccountInfo<'info>,
+    badge_mint_bump: u8,
+    wallet_token_account: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let wallet_key = wallet.key();
+    let achievement_key = achievement.key();
+    let seeds = &[
+        b"badge_mint".as_ref(),
+        achievement_key.as_ref(),
+        wallet_key.as_ref(),
+        &[badge_mint_bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    // Extensions have to be in place before the mint is initialized. Rent also covers the
+    // on-mint metadata the token program appends afterwards
+    let mint_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::NonTransferable,
+        ExtensionType::MetadataPointer,
+    ])?;
+    let metadata_len = badge_metadata_len(&achievement.name, &achievement.uri);
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::CreateAccount {
+                from: payer.clone(),
+                to: badge_mint.clone(),
+            },
+            signer,
+        ),
+        Rent::get()?.minimum_balance(mint_len + metadata_len),
+        mint_len as u64,
+        &token_2022::ID,
+    )?;
+    
+    invoke(
+        &spl_token_2022::instruction::initialize_non_transferable_mint(
+            &token_2022::ID,
+            &badge_mint.key(),
+        )?,
+        &[badge_mint.clone(), token_program.clone()],
+    )?;
+    
+    // The mint is its own metadata account, so wallets show the achievement's name and image
+    invoke(
+        &metadata_pointer::instruction::initialize(
+            &token_2022::ID,
+            &badge_mint.key(),
+            Some(badge_mint.key()),
+            Some(badge_mint.key()),
+        )?,
+        &[badge_mint.clone(), token_program.clone()],
+    )?;
+    
+    // The mint is its own authority, as for material instances
+    token_2022::initialize_mint2(
+        CpiContext::new(
+            token_program.clone(),
+            token_2022::InitializeMint2 {
+                mint: badge_mint.clone(),
+            },
+        ),
+        0,
+        &badge_mint.key(),
+        None,
+    )?;
+    
+    // Written while the mint can still sign as its own mint authority
+    invoke_signed(
+        &spl_token_metadata_interface::instruction::initialize(
+            &token_2022::ID,
+            &badge_mint.key(),
+            &badge_mint.key(),
+            &badge_mint.key(),
+            &badge_mint.key(),
+            achievement.name.clone(),
+            BADGE_SYMBOL.to_string(),
+            achievement.uri.clone(),
+        ),
+        &[badge_mint.clone(), token_program.clone()],
+        signer,
+    )?;
+    
+    associated_token::create(CpiContext::new(
+        associated_token_program.clone(),
+        associated_token::Create {
+            payer: payer.clone(),
+            associated_token: wallet_token_account.clone(),
+            authority: wallet.clone(),
+            mint: badge_mint.clone(),
+            system_program: system_program.clone(),
+            token_program: token_program.clone(),
+        },
+    ))?;
+    
+    token_2022::mint_to(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            token_2022::MintTo {
+                mint: badge_mint.clone(),
+                to: wallet_token_account.clone(),
+                authority: badge_mint.clone(),
+            },
+            signer,
+        ),
+        1,
+    )?;
+    
+    // Drop the mint authority so the badge stays a one-of-one
+    token_2022::set_authority(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            token_2022::SetAuthority {
+                current_authority: badge_mint.clone(),
+                account_or_mint: badge_mint.clone(),
+            },
+            signer,
+        ),
+        AuthorityType::MintTokens,
+        None,
+    )?;
+    
+    Ok(())
+}
+
+// What earns an achievement
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AchievementTrigger {
+    FirstDiscovery { recipe: Option<Pubkey> }, // First to discover a recipe, or any recipe if None
+    CrafterLevel { level: u8 },                // Reached a crafter level
+    GuildQuests { count: u32 },                // Completed this many guild quests
+    FirstLegendaryCraft,                       // Crafted a legendary item
+    Manual,                                    // Handed out by the issuer at its discretion
+}
+
+impl AchievementTrigger {
+    pub const LEN: usize = 1 + 33; // variant + largest payload (Option<Pubkey>)
+}
+
+#[account]
+pub struct AchievementConfig {
+    pub authority: Pubkey,        // Admin authority that defines achievements
+    pub bump: u8,
+}
+
+impl AchievementConfig {
+    pub const LEN: usize = 32 + // authority
+                           1;   // bump
+}
+
+#[account]
+pub struct Achievement {
+    pub name: String,             // Achievement name, also its address seed
+    pub description: String,      // Achievement description
+    pub uri: String,              // Badge metadata URI
+    pub trigger: AchievementTrigger, // What earns this achievement
+    pub issuer: Pubkey,           // Wallet that may award it by hand
+    pub active: bool,             // Whether it can still be earned
+    pub awarded_count: u64,       // Badges issued so far
+    pub created_at: i64,          // Creation timestamp
+    pub bump: u8,
+}
+
+impl Achievement {
+    pub const MAX_NAME_LEN: usize = 32; // Bounded by the seed length limit
+    pub const MAX_DESCRIPTION_LEN: usize = 256;
+    pub const MAX_URI_LEN: usize = 200;
+    
+    pub const LEN: usize = 4 + Self::MAX_NAME_LEN + // name
+                           4 + Self::MAX_DESCRIPTION_LEN + // description
+                           4 + Self::MAX_URI_LEN + // uri
+                           AchievementTrigger::LEN + // trigger
+                           32 + // issuer
+                           1 +  // active
+                           8 +  // awarded_count
+                           8 +  // created_at
+                           1;   // bump
+}
+
+#[account]
+pub struct Badge {
+    pub achievement: Pubkey,      // Achievement this badge was issued for
+    pub wallet: Pubkey,           // Wallet holding the badge
+    pub mint: Pubkey,             // Non-transferable badge mint
+    pub awarded_by: Pubkey,       // Issuer, or the wallet itself when claimed
+    pub awarded_at: i64,          // Award timestamp
+    pub bump: u8,
+}
+
+impl Badge {
+    pub const LEN: usize = 32 + // achievement
+                           32 + // wallet
+                           32 + // mint
+                           32 + // awarded_by
+                           8 +  // awarded_at
+                           1;   // bump
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AchievementConfig::LEN,
+        seeds = [b"achievement_config".as_ref()],
+        bump
+    )]
+    pub achievement_config: Account<'info, AchievementConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateAchievement<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Achievement::LEN,
+        seeds = [b"achievement".as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub achievement: Account<'info, Achievement>,
+    
+    #[account(
+        seeds = [b"achievement_config".as_ref()],
+        bump = achievement_config.bump,
+        has_one = authority @ AchievementError::UnauthorizedAccess,
+    )]
+    pub achievement_config: Account<'info, AchievementConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAchievement<'info> {
+    #[account(
+        mut,
+        seeds = [b"achievement".as_ref(), achievement.name.as_bytes()],
+        bump = achievement.bump
+    )]
+    pub achievement: Account<'info, Achievement>,
+    
+    #[account(
+        seeds = [b"achievement_config".as_ref()],
+        bump = achievement_config.bump,
+        has_one = authority @ AchievementError::UnauthorizedAccess,
+    )]
+    pub achievement_config: Account<'info, AchievementConfig>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AwardAchievement<'info> {
+    #[account(
+        mut,
+        seeds = [b"achievement".as_ref(), achievement.name.as_bytes()],
+        bump = achievement.bump,
+        has_one = issuer @ AchievementError::UnauthorizedAccess,
+    )]
+    pub achievement: Account<'info, Achievement>,
+    
+    // One badge per wallet and achievement; a second award fails here
+    #[account(
+        init,
+        payer = issuer,
+        space = 8 + Badge::LEN,
+        seeds = [b"badge".as_ref(), achievement.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub badge: Account<'info, Badge>,
+    
+    /// CHECK: Created and initialized as a non-transferable mint in the instruction
+    #[account(
+        mut,
+        seeds = [b"badge_mint".as_ref(), achievement.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub badge_mint: UncheckedAccount<'info>,
+    
+    /// CHECK: The wallet's associated account for the badge mint, created in the instruction
+    #[account(mut)]
+    pub wallet_token_account: UncheckedAccount<'info>,
+    
+    /// CHECK: Any wallet may receive a badge
+    pub wallet: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAchievement<'info> {
+    #[account(
+        mut,
+        seeds = [b"achievement".as_ref(), achievement.name.as_bytes()],
+        bump = achievement.bump
+    )]
+    pub achievement: Account<'info, Achievement>,
+    
+    // One badge per wallet and achievement; a second claim fails here
+    #[account(
+        init,
+        payer = wallet,
+        space = 8 + Badge::LEN,
+        seeds = [b"badge".as_ref(), achievement.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub badge: Account<'info, Badge>,
+    
+    /// CHECK: Created and initialized as a non-transferable mint in the instruction
+    #[account(
+        mut,
+        seeds = [b"badge_mint".as_ref(), achievement.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub badge_mint: UncheckedAccount<'info>,
+    
+    /// CHECK: The wallet's associated account for the badge mint, created in the instruction
+    #[account(mut)]
+    pub wallet_token_account: UncheckedAccount<'info>,
+    
+    /// CHECK: Discovery record, crafter stats or crafting record, parsed against the trigger
+    pub evidence: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[error_code]
+pub enum AchievementError {
+    #[msg("Unauthorized access")]
+    UnauthorizedAccess,
+    
+    #[msg("Achievement name cannot be empty")]
+    EmptyName,
+    
+    #[msg("Name exceeds the maximum length")]
+    NameTooLong,
+    
+    #[msg("Description exceeds the maximum length")]
+    DescriptionTooLong,
+    
+    #[msg("URI exceeds the maximum length")]
+    UriTooLong,
+    
+    #[msg("Achievement is not active")]
+    AchievementInactive,
+    
+    #[msg("Evidence account does not belong to this wallet")]
+    EvidenceNotOwned,
+    
+    #[msg("Evidence does not meet the achievement's trigger")]
+    TriggerNotMet,
+    
+    #[msg("This achievement is awarded by its issuer and cannot be claimed")]
+    AwardedByIssuer,
+    
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+}
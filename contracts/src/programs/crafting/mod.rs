@@ -1,12 +1,22 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::system_program;
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
 
 // Import our other program interfaces
-use crate::material::{MaterialInstance, MaterialTypeInfo, ConsumeMaterial, AttributeEffect};
-use crate::recipe::{Recipe, RecordCrafting};
+use crate::material::{
+    MaterialInstance, MaterialTypeInfo, ConsumeMaterial, AttributeEffect, MigrationReceipt,
+};
+use crate::recipe::{hash_ingredients, sort_ingredients, Recipe, RecipeDiscoveryRecord, RecordCrafting};
+use crate::recipe_market::RecipeLicense;
+use magicvial_types::Rarity;
+use randomness::{RandomnessRequest, RequestStatus};
 
 declare_id!("craXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
 
+// Most success rate the materials of one craft can add together
+const MAX_MATERIAL_BONUS: u8 = 30;
+
 #[program]
 pub mod magic_vial_crafting {
     use super::*;
@@ -21,8 +31,11 @@ pub mod magic_vial_crafting {
         let crafting_config = &mut ctx.accounts.crafting_config;
         crafting_config.authority = ctx.accounts.authority.key();
         crafting_config.fee_destination = ctx.accounts.fee_destination.key();
+        crafting_config.fee_mint = ctx.accounts.fee_mint.key();
         crafting_config.fee_percentage = fee_percentage;
         crafting_config.paused = false;
+        crafting_config.time_of_day_windows = Vec::new();
+        crafting_config.bump = ctx.bumps.crafting_config;
         
         Ok(())
     }
@@ -31,6 +44,8 @@ pub mod magic_vial_crafting {
     pub fn craft(
         ctx: Context<Craft>,
         material_instances: Vec<Pubkey>,
+        ingredient_types: Vec<Pubkey>,  // Secret recipes only: the ingredients the crafter knows; empty otherwise
+        ingredient_amounts: Vec<u64>,   // Secret recipes only: amount of each ingredient
         guild_boost: Option<u8>, // Optional boost from guild membership
     ) -> Result<()> {
         // Check if crafting system is paused
@@ -40,20 +55,33 @@ pub mod magic_vial_crafting {
         require!(ctx.accounts.recipe.approved, CraftingError::RecipeNotApproved);
         require!(!ctx.accounts.recipe.disabled, CraftingError::RecipeDisabled);
         
+        // Secret recipes need the crafter's discovery record or license, and the ingredients
+        // they name must match the recipe's commitment
+        let material_types = recipe_ingredients(
+            &ctx.accounts.recipe,
+            ctx.accounts.recipe_discovery.as_deref(),
+            ctx.accounts.recipe_license.as_deref(),
+            ingredient_types,
+            ingredient_amounts,
+            Clock::get()?.unix_timestamp,
+        )?;
+        
         // Verify materials match recipe requirements
         require!(
-            material_instances.len() == ctx.accounts.recipe.material_types.len(),
+            material_instances.len() == material_types.len(),
             CraftingError::MaterialMismatch
         );
         
-        // Verify the crafting fee is sufficient
+        // The crafter pays exactly the fee, so check up front that they hold it
         let fee_amount = calculate_fee(
             ctx.accounts.recipe.difficulty,
             ctx.accounts.crafting_config.fee_percentage,
         );
-        require!(ctx.accounts.payment_amount >= fee_amount, CraftingError::InsufficientFee);
+        require!(ctx.accounts.crafter_token.amount >= fee_amount, CraftingError::InsufficientFee);
         
-        // Process the crafting fee payment
+        // Process the crafting fee payment; the recipe creator's royalty accrues
+        // in their earnings vault until they claim it
+        let (platform_fee, creator_royalty) = split_fee(fee_amount, ctx.accounts.recipe.creator_royalty_bps);
         token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
@@ -63,12 +91,54 @@ pub mod magic_vial_crafting {
                     authority: ctx.accounts.crafter.to_account_info(),
                 },
             ),
-            fee_amount,
+            platform_fee,
         )?;
         
-        // Load material data for success calculation (simplified for now)
-        // In reality, would use CPI to verify materials and their attributes
-        let material_bonus = get_material_bonus(&material_instances);
+        let creator_earnings = &mut ctx.accounts.creator_earnings;
+        if creator_earnings.creator == Pubkey::default() {
+            creator_earnings.creator = ctx.accounts.recipe.creator;
+            creator_earnings.bump = ctx.bumps.creator_earnings;
+        }
+        if creator_royalty > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.crafter_token.to_account_info(),
+                        to: ctx.accounts.creator_vault.to_account_info(),
+                        authority: ctx.accounts.crafter.to_account_info(),
+                    },
+                ),
+                creator_royalty,
+            )?;
+            creator_earnings.accrued = creator_earnings
+                .accrued
+                .checked_add(creator_royalty)
+                .ok_or(CraftingError::ArithmeticOverflow)?;
+            creator_earnings.total_earned = creator_earnings
+                .total_earned
+                .checked_add(creator_royalty)
+                .ok_or(CraftingError::ArithmeticOverflow)?;
+        }
+        
+        // Load each material and its type to total the real success bonus; every instance
+        // spends one unit of its amount, so the same material cannot back crafts forever
+        let material_bonus = get_material_bonus(
+            &material_instances,
+            &material_types,
+            &ctx.accounts.crafter.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        
+        // Live-ops bonuses depend on when the craft happens
+        let now = Clock::get()?.unix_timestamp;
+        let seasonal_bonus = get_seasonal_event_bonus(
+            ctx.accounts.seasonal_event.as_deref(),
+            &ctx.accounts.recipe.key(),
+            &material_types,
+            now,
+        );
         
         // Calculate crafting success based on recipe success rate and multiple factors
         let success_factors = SuccessFactors {
@@ -78,63 +148,79 @@ pub mod magic_vial_crafting {
             material_bonus, 
             guild_boost,
             recipe_attempts: ctx.accounts.recipe.times_crafted, // Familiarity bonus
-            time_of_day_bonus: get_time_of_day_bonus(),
-            is_seasonal_event: is_seasonal_event_active(),
+            time_of_day_bonus: get_time_of_day_bonus(&ctx.accounts.crafting_config.time_of_day_windows, now),
+            seasonal_bonus,
         };
         
         let success_rate = calculate_success_rate(success_factors);
         
-        // Generate randomness for success determination
-        let success = determine_crafting_success(
-            success_rate,
-            ctx.accounts.recent_blockhash.key(),
-            ctx.accounts.crafter.key(),
-            Clock::get()?.unix_timestamp,
-        );
-        
-        // Create the crafting record
+        // Create the crafting record; the outcome is rolled in resolve_craft
+        // once the bound randomness request has been fulfilled
         let crafting_record = &mut ctx.accounts.crafting_record;
         crafting_record.crafter = ctx.accounts.crafter.key();
         crafting_record.recipe = ctx.accounts.recipe.key();
-        crafting_record.timestamp = Clock::get()?.unix_timestamp;
+        crafting_record.timestamp = now;
         crafting_record.materials_used = material_instances;
-        crafting_record.success = success;
         crafting_record.calculated_success_rate = success_rate;
         
-        // Update recipe statistics (cross-program invocation)
-        // In reality, would use CPI to call record_crafting_attempt on the recipe program
+        // Pin the recipe version, so a balance patch cannot change an attempt already started
+        crafting_record.recipe_version = ctx.accounts.recipe.current_version;
+        crafting_record.base_success_rate = ctx.accounts.recipe.success_rate;
+        crafting_record.recipe_difficulty = ctx.accounts.recipe.difficulty;
+        crafting_record.randomness_request = ctx.accounts.randomness_request.key();
+        crafting_record.resolved = false;
+        
+        // Record crafting attempt for guild achievements if in a guild
+        if let Some(guild_id) = ctx.accounts.crafter_stats.guild {
+            // In reality, would use CPI to update guild stats
+            crafting_record.guild = Some(guild_id);
+        }
         
-        // Update crafter stats 
+        Ok(())
+    }
+    
+    // Roll the outcome of a crafting attempt from its fulfilled randomness; permissionless
+    pub fn resolve_craft(
+        ctx: Context<ResolveCraft>,
+    ) -> Result<()> {
+        let randomness = ctx.accounts.randomness_request.value()?;
+        let recipe = &ctx.accounts.recipe;
+        let crafting_record = &mut ctx.accounts.crafting_record;
         let crafter_stats = &mut ctx.accounts.crafter_stats;
         
+        let success = determine_crafting_success(
+            crafting_record.calculated_success_rate,
+            &randomness,
+        );
+        crafting_record.success = success;
+        crafting_record.resolved = true;
+        
+        // Update recipe statistics (cross-program invocation)
+        // In reality, would use CPI to call record_crafting_attempt on the recipe program
+        
         if success {
             // If successful, determine the result type based on weighted probabilities
-            // This is simplified and would need cross-program invocation to get result type data
             crafting_record.result_type = select_result_type(
-                &ctx.accounts.recipe.result_types,
-                &ctx.accounts.recipe.result_weights,
-                ctx.accounts.recent_blockhash.key(),
-                crafting_record.timestamp,
-                crafter_stats.experience_level,
+                &recipe.result_types,
+                &recipe.result_weights,
+                &randomness,
             );
             
             // Determine rarity roll with potential critical success
-            let (rarity_level, is_critical) = determine_result_rarity(
-                ctx.accounts.recent_blockhash.key(),
-                ctx.accounts.crafter.key(),
-                crafting_record.timestamp + 1, // Different seed than success determination
+            let (rarity, is_critical) = determine_result_rarity(
+                &randomness,
                 crafter_stats.experience_level,
-                ctx.accounts.recipe.difficulty,
+                crafting_record.recipe_difficulty,
             );
             
-            crafting_record.result_rarity = rarity_level;
+            crafting_record.result_rarity = rarity;
             crafting_record.is_critical_success = is_critical;
             
             // Update crafter stats
             crafter_stats.successful_crafts += 1;
             crafter_stats.success_streak += 1;
             crafter_stats.experience_points += get_experience_points(
-                ctx.accounts.recipe.difficulty, 
+                crafting_record.recipe_difficulty, 
                 true, 
                 is_critical,
             );
@@ -149,10 +235,8 @@ pub mod magic_vial_crafting {
             // Could still mint a "failed experiment" token
             // Determine if this is an "interesting failure" worth keeping
             let interesting_failure = determine_interesting_failure(
-                ctx.accounts.recent_blockhash.key(),
-                ctx.accounts.crafter.key(),
-                crafting_record.timestamp + 2, // Different seed
-                ctx.accounts.recipe.difficulty,
+                &randomness,
+                crafting_record.recipe_difficulty,
             );
             
             crafting_record.is_interesting_failure = interesting_failure;
@@ -161,18 +245,12 @@ pub mod magic_vial_crafting {
             crafter_stats.failed_crafts += 1;
             crafter_stats.success_streak = 0; // Reset streak on failure
             crafter_stats.experience_points += get_experience_points(
-                ctx.accounts.recipe.difficulty, 
+                crafting_record.recipe_difficulty, 
                 false, 
                 false,
             );
         }
         
-        // Record crafting attempt for guild achievements if in a guild
-        if let Some(guild_id) = crafter_stats.guild {
-            // In reality, would use CPI to update guild stats
-            crafting_record.guild = Some(guild_id);
-        }
-        
         Ok(())
     }
 
@@ -189,6 +267,32 @@ pub mod magic_vial_crafting {
         Ok(())
     }
 
+    // Replace the daily windows that grant a time-of-day success bonus
+    pub fn set_time_of_day_windows(
+        ctx: Context<UpdateConfig>,
+        windows: Vec<TimeOfDayWindow>,
+    ) -> Result<()> {
+        require!(
+            windows.len() <= CraftingConfig::MAX_TIME_OF_DAY_WINDOWS,
+            CraftingError::TooManyTimeWindows
+        );
+        for window in &windows {
+            require!(
+                window.start_hour < 24 && window.end_hour < 24 && window.start_hour != window.end_hour,
+                CraftingError::InvalidTimeWindow
+            );
+            require!(
+                window.bonus <= TimeOfDayWindow::MAX_BONUS,
+                CraftingError::InvalidTimeOfDayBonus
+            );
+        }
+        
+        let crafting_config = &mut ctx.accounts.crafting_config;
+        crafting_config.time_of_day_windows = windows;
+        
+        Ok(())
+    }
+
     // Pause or unpause crafting system
     pub fn set_pause_state(
         ctx: Context<UpdateConfig>,
@@ -225,28 +329,43 @@ pub mod magic_vial_crafting {
     
     // Start a seasonal event
     pub fn start_seasonal_event(
-        ctx: Context<ManageSeasonalEvent>,
+        ctx: Context<StartSeasonalEvent>,
         event_name: String,
         description: String,
         duration_hours: u16,
         success_rate_bonus: u8,
+        recipes: Vec<Pubkey>,        // Recipes the bonus applies to; empty for all
+        material_types: Vec<Pubkey>, // Material types that trigger the bonus; empty for all
     ) -> Result<()> {
         require!(success_rate_bonus <= 25, CraftingError::InvalidBonus);
+        require!(event_name.len() <= SeasonalEvent::MAX_NAME_LEN, CraftingError::NameTooLong);
+        require!(
+            description.len() <= SeasonalEvent::MAX_DESCRIPTION_LEN,
+            CraftingError::DescriptionTooLong
+        );
+        require!(
+            recipes.len() <= SeasonalEvent::MAX_SCOPE_ENTRIES &&
+            material_types.len() <= SeasonalEvent::MAX_SCOPE_ENTRIES,
+            CraftingError::TooManyScopeEntries
+        );
         
         let seasonal_event = &mut ctx.accounts.seasonal_event;
+        seasonal_event.crafting_config = ctx.accounts.crafting_config.key();
         seasonal_event.name = event_name;
         seasonal_event.description = description;
         seasonal_event.start_time = Clock::get()?.unix_timestamp;
         seasonal_event.end_time = Clock::get()?.unix_timestamp + (duration_hours as i64 * 3600);
         seasonal_event.success_rate_bonus = success_rate_bonus;
         seasonal_event.active = true;
+        seasonal_event.recipes = recipes;
+        seasonal_event.material_types = material_types;
         
         Ok(())
     }
     
     // End a seasonal event early
     pub fn end_seasonal_event(
-        ctx: Context<ManageSeasonalEvent>,
+        ctx: Context<EndSeasonalEvent>,
     ) -> Result<()> {
         let seasonal_event = &mut ctx.accounts.seasonal_event;
         seasonal_event.active = false;
@@ -254,6 +373,130 @@ pub mod magic_vial_crafting {
         
         Ok(())
     }
+    
+    // Pay out a recipe creator's accrued royalties
+    pub fn claim_creator_earnings(
+        ctx: Context<ClaimCreatorEarnings>,
+    ) -> Result<()> {
+        let amount = ctx.accounts.creator_earnings.accrued;
+        require!(amount > 0, CraftingError::NothingToClaim);
+        
+        let creator_key = ctx.accounts.creator.key();
+        let seeds = &[
+            b"creator_earnings".as_ref(),
+            creator_key.as_ref(),
+            &[ctx.accounts.creator_earnings.bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.creator_vault.to_account_info(),
+                    to: ctx.accounts.creator_token.to_account_info(),
+                    authority: ctx.accounts.creator_earnings.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        let creator_earnings = &mut ctx.accounts.creator_earnings;
+        creator_earnings.accrued = 0;
+        creator_earnings.last_claimed_at = Clock::get()?.unix_timestamp;
+        
+        Ok(())
+    }
+    
+    // Move a crafting config created before time-of-day windows were configurable to the canonical config address
+    pub fn migrate_crafting_config(
+        ctx: Context<MigrateCraftingConfig>,
+    ) -> Result<()> {
+        let account_info = ctx.accounts.legacy_config.to_account_info();
+        
+        // Baseline configs were allocated with spare room, so only the prefix is the legacy layout
+        let legacy = {
+            let data = account_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + LegacyCraftingConfig::LEN &&
+                data[..8] == CraftingConfig::discriminator(),
+                CraftingError::InvalidMigrationAccount
+            );
+            LegacyCraftingConfig::deserialize(&mut &data[8..])
+                .map_err(|_| error!(CraftingError::InvalidMigrationAccount))?
+        };
+        require!(
+            ctx.accounts.fee_destination.key() == legacy.fee_destination,
+            CraftingError::InvalidFeeDestination
+        );
+        
+        // No windows until the authority sets them
+        let crafting_config = &mut ctx.accounts.crafting_config;
+        crafting_config.authority = legacy.authority;
+        crafting_config.fee_destination = legacy.fee_destination;
+        crafting_config.fee_mint = ctx.accounts.fee_destination.mint;
+        crafting_config.fee_percentage = legacy.fee_percentage;
+        crafting_config.paused = legacy.paused;
+        crafting_config.time_of_day_windows = Vec::new();
+        crafting_config.bump = ctx.bumps.crafting_config;
+        
+        let migration_receipt = &mut ctx.accounts.migration_receipt;
+        migration_receipt.account = account_info.key();
+        migration_receipt.migrated_at = Clock::get()?.unix_timestamp;
+        migration_receipt.bump = ctx.bumps.migration_receipt;
+        
+        Ok(())
+    }
+    
+    // Convert a seasonal event created before events could be scoped
+    pub fn migrate_seasonal_event(
+        ctx: Context<MigrateSeasonalEvent>,
+    ) -> Result<()> {
+        let account_info = ctx.accounts.seasonal_event.to_account_info();
+        
+        let legacy = {
+            let data = account_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + LegacySeasonalEvent::LEN &&
+                data[..8] == SeasonalEvent::discriminator(),
+                CraftingError::InvalidMigrationAccount
+            );
+            LegacySeasonalEvent::deserialize(&mut &data[8..])
+                .map_err(|_| error!(CraftingError::InvalidMigrationAccount))?
+        };
+        
+        grow_account(
+            &account_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + SeasonalEvent::LEN,
+        )?;
+        
+        // Events from before scoping applied to every recipe and material
+        let seasonal_event = SeasonalEvent {
+            crafting_config: ctx.accounts.crafting_config.key(),
+            name: legacy.name,
+            description: legacy.description,
+            start_time: legacy.start_time,
+            end_time: legacy.end_time,
+            success_rate_bonus: legacy.success_rate_bonus,
+            active: legacy.active,
+            recipes: Vec::new(),
+            material_types: Vec::new(),
+        };
+        
+        let mut data = account_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        seasonal_event.try_serialize(&mut writer)?;
+        
+        let migration_receipt = &mut ctx.accounts.migration_receipt;
+        migration_receipt.account = account_info.key();
+        migration_receipt.migrated_at = Clock::get()?.unix_timestamp;
+        migration_receipt.bump = ctx.bumps.migration_receipt;
+        
+        Ok(())
+    }
 }
 
 // Success factors for crafting
@@ -265,7 +508,7 @@ struct SuccessFactors {
     guild_boost: Option<u8>, // Optional boost from guild membership
     recipe_attempts: u64,    // How many times this recipe has been crafted globally
     time_of_day_bonus: u8,   // Bonus based on time of day
-    is_seasonal_event: bool, // Whether a seasonal event is active
+    seasonal_bonus: u8,      // Bonus from a running seasonal event
 }
 
 // Helper functions (would be implementation details)
@@ -277,22 +520,238 @@ fn calculate_fee(difficulty: u8, fee_percentage: u8) -> u64 {
     base_fee.saturating_mul(difficulty_multiplier).saturating_mul(fee_multiplier) / 100
 }
 
-fn get_material_bonus(material_instances: &Vec<Pubkey>) -> u8 {
-    // In a real implementation, would use CPI to query material attributes
-    // For now, just return a placeholder value
-    5 // 5% bonus
+// Helper function to split a crafting fee into the platform's part and the recipe creator's royalty
+fn split_fee(fee_amount: u64, creator_royalty_bps: u16) -> (u64, u64) {
+    let creator_royalty = (fee_amount as u128 * creator_royalty_bps as u128 / 10_000) as u64;
+    
+    (fee_amount - creator_royalty, creator_royalty)
 }
 
-fn get_time_of_day_bonus() -> u8 {
-    // In a real implementation, would calculate based on current time
-    // For now, just return a placeholder value
-    2 // 2% bonus
+// Helper function to get the material types a recipe asks for
+fn recipe_ingredients(
+    recipe: &Recipe,
+    recipe_discovery: Option<&RecipeDiscoveryRecord>,
+    recipe_license: Option<&RecipeLicense>,
+    ingredient_types: Vec<Pubkey>,
+    ingredient_amounts: Vec<u64>,
+    now: i64,
+) -> Result<Vec<Pubkey>> {
+    if !recipe.is_secret {
+        return Ok(recipe.material_types.clone());
+    }
+    
+    // Licenses bought on the recipe market work until they expire
+    if recipe_discovery.is_none() {
+        let recipe_license = recipe_license.ok_or(CraftingError::RecipeNotDiscovered)?;
+        require!(recipe_license.is_valid(now), CraftingError::LicenseExpired);
+    }
+    
+    // Only the commitment is on chain, so the crafter names the ingredients and they are hashed
+    // the same way a discovery is; materials then follow this sorted order
+    require!(ingredient_types.len() == ingredient_amounts.len(), CraftingError::InvalidIngredients);
+    let commitment = recipe
+        .ingredient_commitment
+        .as_ref()
+        .ok_or(CraftingError::InvalidIngredients)?;
+    let (material_types, material_amounts) = sort_ingredients(ingredient_types, ingredient_amounts);
+    require!(
+        hash_ingredients(&commitment.salt, &material_types, &material_amounts) == commitment.hash,
+        CraftingError::InvalidIngredients
+    );
+    
+    Ok(material_types)
 }
 
-fn is_seasonal_event_active() -> bool {
-    // In a real implementation, would query for active seasonal events
-    // For now, just return a placeholder value
-    false
+// Helper function to total the success bonus of the materials used in a craft
+// Remaining accounts: (MaterialInstance, MaterialTypeInfo, MaterialUsage) per material instance, in ingredient order
+fn get_material_bonus<'info>(
+    material_instances: &[Pubkey],
+    required_types: &[Pubkey],
+    crafter: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    material_accounts: &[AccountInfo<'info>],
+) -> Result<u8> {
+    require!(
+        material_accounts.len() == material_instances.len() * 3,
+        CraftingError::MaterialMismatch
+    );
+    
+    let mut counted_types: Vec<Pubkey> = Vec::new();
+    let mut total_bonus: u8 = 0;
+    
+    for ((instance_key, required_type), accounts) in material_instances
+        .iter()
+        .zip(required_types.iter())
+        .zip(material_accounts.chunks(3))
+    {
+        let material_instance = Account::<MaterialInstance>::try_from(&accounts[0])?;
+        let material_type_info = Account::<MaterialTypeInfo>::try_from(&accounts[1])?;
+        
+        // Each instance must be the crafter's, hold material and be of the type the recipe asks for
+        require!(accounts[0].key() == *instance_key, CraftingError::MaterialMismatch);
+        require!(material_instance.owner == crafter.key(), CraftingError::MaterialNotOwned);
+        require!(material_instance.amount > 0, CraftingError::MaterialUsedUp);
+        require!(
+            material_instance.material_type == accounts[1].key(),
+            CraftingError::MaterialMismatch
+        );
+        require!(
+            material_instance.material_type == *required_type,
+            CraftingError::MaterialMismatch
+        );
+        
+        use_material_instance(
+            &accounts[2],
+            instance_key,
+            material_instance.amount,
+            crafter,
+            system_program,
+        )?;
+        
+        // Bonuses do not stack across several instances of the same material type
+        if counted_types.contains(&material_instance.material_type) {
+            continue;
+        }
+        counted_types.push(material_instance.material_type);
+        
+        total_bonus = total_bonus.saturating_add(material_type_bonus(&material_type_info));
+    }
+    
+    Ok(total_bonus.min(MAX_MATERIAL_BONUS))
+}
+
+// Helper function to spend one unit of a material instance on a craft, creating its usage record on first use
+fn use_material_instance<'info>(
+    usage_info: &AccountInfo<'info>,
+    material_instance: &Pubkey,
+    amount: u64,
+    crafter: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let (usage_address, bump) = Pubkey::find_program_address(
+        &[b"material_usage".as_ref(), material_instance.as_ref()],
+        &crate::ID,
+    );
+    require!(usage_info.key() == usage_address, CraftingError::MaterialMismatch);
+    
+    let mut material_usage = if usage_info.data_is_empty() {
+        let space = 8 + MaterialUsage::LEN;
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: crafter.clone(),
+                    to: usage_info.clone(),
+                },
+                &[&[b"material_usage".as_ref(), material_instance.as_ref(), &[bump]]],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &crate::ID,
+        )?;
+        MaterialUsage {
+            material_instance: *material_instance,
+            used: 0,
+            bump,
+        }
+    } else {
+        require!(usage_info.owner == &crate::ID, CraftingError::MaterialMismatch);
+        let data = usage_info.try_borrow_data()?;
+        MaterialUsage::try_deserialize(&mut &data[..])?
+    };
+    
+    // Crafting cannot use more of a material than the instance holds
+    require!(material_usage.used < amount, CraftingError::MaterialUsedUp);
+    material_usage.used += 1;
+    
+    let mut data = usage_info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    material_usage.try_serialize(&mut writer)?;
+    
+    Ok(())
+}
+
+// Helper function to get one material type's success bonus
+fn material_type_bonus(material_type_info: &MaterialTypeInfo) -> u8 {
+    // Only SuccessBonus attributes raise the success rate; the other effects apply elsewhere
+    let attribute_bonus = material_type_info
+        .attributes
+        .iter()
+        .filter(|attribute| matches!(attribute.effect, AttributeEffect::SuccessBonus))
+        .fold(0u8, |sum, attribute| sum.saturating_add(attribute.value));
+    
+    // Attributes cannot lift a type past the limit on its own bonus
+    material_type_info
+        .success_rate_bonus
+        .saturating_add(attribute_bonus)
+        .min(MaterialTypeInfo::MAX_SUCCESS_RATE_BONUS)
+}
+
+// Helper function to get the time-of-day bonus for the current UTC hour
+fn get_time_of_day_bonus(windows: &[TimeOfDayWindow], now: i64) -> u8 {
+    let hour = (now.rem_euclid(86_400) / 3600) as u8;
+    
+    // Overlapping windows do not stack; the best one applies
+    windows
+        .iter()
+        .filter(|window| window.contains(hour))
+        .map(|window| window.bonus)
+        .max()
+        .unwrap_or(0)
+}
+
+// Helper function to get a seasonal event's bonus if it is running and covers this craft
+fn get_seasonal_event_bonus(
+    seasonal_event: Option<&SeasonalEvent>,
+    recipe: &Pubkey,
+    material_types: &[Pubkey],
+    now: i64,
+) -> u8 {
+    let seasonal_event = match seasonal_event {
+        Some(seasonal_event) => seasonal_event,
+        None => return 0,
+    };
+    
+    let running = seasonal_event.active &&
+        seasonal_event.start_time <= now &&
+        now < seasonal_event.end_time;
+    let covers_recipe = seasonal_event.recipes.is_empty() ||
+        seasonal_event.recipes.contains(recipe);
+    let covers_materials = seasonal_event.material_types.is_empty() ||
+        material_types.iter().any(|material_type| seasonal_event.material_types.contains(material_type));
+    
+    if running && covers_recipe && covers_materials {
+        seasonal_event.success_rate_bonus
+    } else {
+        0
+    }
+}
+
+// Helper function to top up rent and grow an account to a larger layout
+pub(crate) fn grow_account<'info>(
+    account_info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account_info.lamports());
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account_info.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    account_info.realloc(new_len, false)?;
+    
+    Ok(())
 }
 
 fn calculate_success_rate(factors: SuccessFactors) -> u8 {
@@ -324,83 +783,36 @@ fn calculate_success_rate(factors: SuccessFactors) -> u8 {
     success_rate = success_rate.saturating_add(factors.time_of_day_bonus);
     
     // Add seasonal event bonus
-    if factors.is_seasonal_event {
-        success_rate = success_rate.saturating_add(10); // 10% bonus during events
-    }
+    success_rate = success_rate.saturating_add(factors.seasonal_bonus);
     
     // Cap at 95% to always leave some chance of failure
     std::cmp::min(success_rate, 95)
 }
 
-fn determine_crafting_success(success_rate: u8, recent_blockhash: Pubkey, crafter: Pubkey, timestamp: i64) -> bool {
-    // Generate a pseudo-random number using blockhash, crafter address, and timestamp
-    let mut data = [0u8; 32];
-    let blockhash_bytes = recent_blockhash.to_bytes();
-    let crafter_bytes = crafter.to_bytes();
-    let timestamp_bytes = timestamp.to_le_bytes();
-    
-    for i in 0..32 {
-        data[i] = blockhash_bytes[i] ^ 
-                 crafter_bytes[i % 32] ^ 
-                 (if i < 8 { timestamp_bytes[i] } else { 0 });
-    }
-    
-    // Use first byte as percentage (0-255, scale to 0-100)
-    let roll = data[0] as u16 * 100 / 255;
+fn determine_crafting_success(success_rate: u8, randomness: &[u8; 32]) -> bool {
+    // Roll 0-99 against the final success rate
+    let roll = randomness::roll_percent(randomness, b"craft_success");
     
-    roll < success_rate as u16
+    roll < success_rate
 }
 
-fn select_result_type(result_types: &Vec<Pubkey>, result_weights: &Vec<u8>, recent_blockhash: Pubkey, timestamp: i64, crafter_level: u8) -> Pubkey {
-    // Sum weights
-    let total_weight: u16 = result_weights.iter().map(|&w| w as u16).sum();
+fn select_result_type(result_types: &Vec<Pubkey>, result_weights: &Vec<u8>, randomness: &[u8; 32]) -> Pubkey {
+    let weights: Vec<u64> = result_weights.iter().map(|&w| w as u64).collect();
     
-    // Generate a pseudo-random number
-    let mut data = [0u8; 32];
-    let blockhash_bytes = recent_blockhash.to_bytes();
-    let timestamp_bytes = timestamp.to_le_bytes();
-    let level_bytes = [crafter_level, 0, 0, 0, 0, 0, 0, 0];
-    
-    for i in 0..32 {
-        data[i] = blockhash_bytes[i] ^ 
-                 (if i < 8 { timestamp_bytes[i] } else { 0 }) ^
-                 (if i < 8 { level_bytes[i] } else { 0 });
-    }
-    
-    // Use first two bytes as random value
-    let roll = ((data[0] as u16) << 8 | data[1] as u16) % total_weight;
-    
-    // Select result based on weights
-    let mut current_weight = 0;
-    for i in 0..result_types.len() {
-        current_weight += result_weights[i] as u16;
-        if roll < current_weight {
-            return result_types[i];
-        }
+    // Select result based on weights, falling back to the first result (should never happen)
+    match randomness::roll_weighted(randomness, b"craft_result_type", &weights) {
+        Some(i) => result_types[i],
+        None => result_types[0],
     }
-    
-    // Fallback (should never happen)
-    result_types[0]
 }
 
 fn determine_result_rarity(
-    recent_blockhash: Pubkey, 
-    crafter: Pubkey, 
-    timestamp: i64, 
+    randomness: &[u8; 32],
     crafter_level: u8,
     recipe_difficulty: u8,
-) -> (u8, bool) {
-    // Generate pseudo-random bytes
-    let mut data = [0u8; 32];
-    let blockhash_bytes = recent_blockhash.to_bytes();
-    let crafter_bytes = crafter.to_bytes();
-    let timestamp_bytes = timestamp.to_le_bytes();
-    
-    for i in 0..32 {
-        data[i] = blockhash_bytes[i] ^ 
-                 crafter_bytes[i % 32] ^ 
-                 (if i < 8 { timestamp_bytes[i] } else { 0 });
-    }
+) -> (Rarity, bool) {
+    // Derive independent bytes for the rarity rolls
+    let data = randomness::derive(randomness, b"craft_rarity");
     
     // Use different parts of the random data for different calculations
     
@@ -409,9 +821,9 @@ fn determine_result_rarity(
     let critical_roll = data[2]; // 0-255
     let is_critical = critical_roll < critical_threshold;
     
-    // Determine rarity (1-5)
+    // Determine rarity
     // Base chance for each rarity:
-    // 1: 40%, 2: 30%, 3: 20%, 4: 9%, 5: 1%
+    // Common: 40%, Uncommon: 30%, Rare: 20%, Epic: 9%, Legendary: 1%
     
     // Higher crafter level and recipe difficulty increase chances of higher rarity
     // Level gives up to +10% chance of better rarity
@@ -420,15 +832,15 @@ fn determine_result_rarity(
     let level_bonus = crafter_level.saturating_div(10); // 0-10% bonus
     let rarity_roll = data[3] as u16; // 0-255
     
-    // Critical success always gives at least rarity 3
+    // Critical success always gives at least Rare
     if is_critical {
-        // For criticals: rarity 3: 50%, rarity 4: 40%, rarity 5: 10%
+        // For criticals: Rare: 50%, Epic: 40%, Legendary: 10%
         if rarity_roll < 128 {
-            return (3, true);
+            return (Rarity::Rare, true);
         } else if rarity_roll < 230 {
-            return (4, true);
+            return (Rarity::Epic, true);
         } else {
-            return (5, true);
+            return (Rarity::Legendary, true);
         }
     }
     
@@ -439,35 +851,24 @@ fn determine_result_rarity(
     let difficulty_factor = recipe_difficulty.saturating_div(20); // 0-5 adjustment
     
     if adjusted_roll < (102 - difficulty_factor as u16) { // ~40% - difficulty adjustment
-        return (1, false);
+        return (Rarity::Common, false);
     } else if adjusted_roll < (179 - difficulty_factor as u16) { // ~30% - difficulty adjustment
-        return (2, false);
+        return (Rarity::Uncommon, false);
     } else if adjusted_roll < (230 - difficulty_factor as u16) { // ~20% - difficulty adjustment
-        return (3, false);
+        return (Rarity::Rare, false);
     } else if adjusted_roll < (253 - difficulty_factor as u16) { // ~9% - difficulty adjustment
-        return (4, false);
+        return (Rarity::Epic, false);
     } else {
-        return (5, false); // ~1% + difficulty adjustment
+        return (Rarity::Legendary, false); // ~1% + difficulty adjustment
     }
 }
 
 fn determine_interesting_failure(
-    recent_blockhash: Pubkey, 
-    crafter: Pubkey, 
-    timestamp: i64, 
+    randomness: &[u8; 32],
     recipe_difficulty: u8,
 ) -> bool {
-    // Generate pseudo-random bytes
-    let mut data = [0u8; 32];
-    let blockhash_bytes = recent_blockhash.to_bytes();
-    let crafter_bytes = crafter.to_bytes();
-    let timestamp_bytes = timestamp.to_le_bytes();
-    
-    for i in 0..32 {
-        data[i] = blockhash_bytes[i] ^ 
-                 crafter_bytes[i % 32] ^ 
-                 (if i < 8 { timestamp_bytes[i] } else { 0 });
-    }
+    // Derive independent bytes for the failure roll
+    let data = randomness::derive(randomness, b"craft_interesting_failure");
     
     // Higher difficulty increases chance of interesting failure
     // Base 5% chance, +0.5% per difficulty point
@@ -501,8 +902,91 @@ fn get_level_threshold(current_level: u8) -> u64 {
 pub struct CraftingConfig {
     pub authority: Pubkey,        // Admin authority
     pub fee_destination: Pubkey,  // Where crafting fees go
+    pub fee_mint: Pubkey,         // Token crafting fees are paid in
     pub fee_percentage: u8,       // Fee percentage (0-100)
     pub paused: bool,             // Whether crafting is paused
+    pub time_of_day_windows: Vec<TimeOfDayWindow>, // Daily windows with a success bonus
+    pub bump: u8,
+}
+
+impl CraftingConfig {
+    pub const MAX_TIME_OF_DAY_WINDOWS: usize = 4;
+    
+    pub const LEN: usize = 32 + // authority
+                           32 + // fee_destination
+                           32 + // fee_mint
+                           1 +  // fee_percentage
+                           1 +  // paused
+                           4 + Self::MAX_TIME_OF_DAY_WINDOWS * TimeOfDayWindow::LEN + // time_of_day_windows
+                           1;   // bump
+}
+
+#[account]
+pub struct CreatorEarnings {
+    pub creator: Pubkey,          // Recipe creator the royalties belong to
+    pub accrued: u64,             // Royalties waiting in the creator's vault
+    pub total_earned: u64,        // Royalties earned over all time
+    pub last_claimed_at: i64,     // Last claim timestamp
+    pub bump: u8,
+}
+
+impl CreatorEarnings {
+    pub const LEN: usize = 32 + // creator
+                           8 +  // accrued
+                           8 +  // total_earned
+                           8 +  // last_claimed_at
+                           1;   // bump
+}
+
+// How much of a material instance crafting has used; the material program keeps the instance itself
+#[account]
+pub struct MaterialUsage {
+    pub material_instance: Pubkey, // Instance this record counts uses of
+    pub used: u64,                 // Units spent on crafts so far
+    pub bump: u8,
+}
+
+impl MaterialUsage {
+    pub const LEN: usize = 32 + // material_instance
+                           8 +  // used
+                           1;   // bump
+}
+
+// Daily UTC hours during which crafting gets a success bonus; wraps past midnight when start > end
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct TimeOfDayWindow {
+    pub start_hour: u8,           // First hour of the window (0-23)
+    pub end_hour: u8,             // Hour the window closes (0-23)
+    pub bonus: u8,                // Success rate bonus inside the window
+}
+
+impl TimeOfDayWindow {
+    pub const MAX_BONUS: u8 = 10;
+    
+    pub const LEN: usize = 1 +  // start_hour
+                           1 +  // end_hour
+                           1;   // bonus
+    
+    pub fn contains(&self, hour: u8) -> bool {
+        if self.start_hour < self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+// Crafting config layout before time-of-day windows were configurable
+#[derive(AnchorDeserialize)]
+struct LegacyCraftingConfig {
+    authority: Pubkey,
+    fee_destination: Pubkey,
+    fee_percentage: u8,
+    paused: bool,
+}
+
+impl LegacyCraftingConfig {
+    const LEN: usize = 32 + 32 + 1 + 1;
 }
 
 #[account]
@@ -517,6 +1001,17 @@ pub struct CrafterStats {
     pub success_streak: u16,       // Current streak of successful crafts
 }
 
+impl CrafterStats {
+    pub const LEN: usize = 32 + // crafter
+                           8 +  // experience_points
+                           1 +  // experience_level
+                           8 +  // successful_crafts
+                           8 +  // failed_crafts
+                           8 +  // created_at
+                           33 + // guild (Option<Pubkey>)
+                           2;   // success_streak
+}
+
 #[account]
 pub struct CraftingRecord {
     pub crafter: Pubkey,           // Who performed the crafting
@@ -525,32 +1020,111 @@ pub struct CraftingRecord {
     pub materials_used: Vec<Pubkey>, // Material instances consumed
     pub success: bool,             // Whether crafting was successful
     pub result_type: Pubkey,       // Resulting item type (if successful)
-    pub result_rarity: u8,         // Rarity of result (1-5)
+    pub result_rarity: Rarity,     // Rarity of result
     pub is_critical_success: bool, // Whether this was a critical success
     pub is_interesting_failure: bool, // Whether a failed craft produced something
     pub calculated_success_rate: u8, // What the final success rate was
     pub guild: Option<Pubkey>,     // Guild affiliation during crafting
+    pub randomness_request: Pubkey, // Randomness request bound to this attempt
+    pub resolved: bool,            // Whether the outcome has been rolled
+    pub recipe_version: u32,       // Recipe version the attempt started on
+    pub base_success_rate: u8,     // Recipe success rate in that version
+    pub recipe_difficulty: u8,     // Recipe difficulty in that version
+}
+
+impl CraftingRecord {
+    // One material instance per recipe material, so bounded by the recipe limit
+    pub const LEN: usize = 32 + // crafter
+                           32 + // recipe
+                           8 +  // timestamp
+                           4 + Recipe::MAX_MATERIALS * 32 + // materials_used
+                           1 +  // success
+                           32 + // result_type
+                           Rarity::LEN + // result_rarity
+                           1 +  // is_critical_success
+                           1 +  // is_interesting_failure
+                           1 +  // calculated_success_rate
+                           33 + // guild (Option<Pubkey>)
+                           32 + // randomness_request
+                           1 +  // resolved
+                           4 +  // recipe_version
+                           1 +  // base_success_rate
+                           1;   // recipe_difficulty
 }
 
 #[account]
 pub struct SeasonalEvent {
+    pub crafting_config: Pubkey,  // Config whose authority runs the event
     pub name: String,             // Event name
     pub description: String,      // Event description 
     pub start_time: i64,          // When event started
     pub end_time: i64,            // When event ends
     pub success_rate_bonus: u8,   // Success rate bonus during event
     pub active: bool,             // Whether event is active
+    pub recipes: Vec<Pubkey>,     // Recipes the event covers; empty for all
+    pub material_types: Vec<Pubkey>, // Material types that trigger the event; empty for all
+}
+
+impl SeasonalEvent {
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_DESCRIPTION_LEN: usize = 256;
+    pub const MAX_SCOPE_ENTRIES: usize = 8;
+    
+    pub const LEN: usize = 32 + // crafting_config
+                           4 + Self::MAX_NAME_LEN + // name
+                           4 + Self::MAX_DESCRIPTION_LEN + // description
+                           8 +  // start_time
+                           8 +  // end_time
+                           1 +  // success_rate_bonus
+                           1 +  // active
+                           4 + Self::MAX_SCOPE_ENTRIES * 32 + // recipes
+                           4 + Self::MAX_SCOPE_ENTRIES * 32;  // material_types
+}
+
+// Seasonal event layout before events could be scoped
+#[derive(AnchorDeserialize)]
+struct LegacySeasonalEvent {
+    name: String,
+    description: String,
+    start_time: i64,
+    end_time: i64,
+    success_rate_bonus: u8,
+    active: bool,
+}
+
+impl LegacySeasonalEvent {
+    const LEN: usize = 4 + SeasonalEvent::MAX_NAME_LEN +
+                       4 + SeasonalEvent::MAX_DESCRIPTION_LEN +
+                       8 + 8 + 1 + 1;
 }
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
-    #[account(init, payer = authority, space = 8 + 128)]
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CraftingConfig::LEN,
+        seeds = [b"crafting_config".as_ref()],
+        bump
+    )]
     pub crafting_config: Account<'info, CraftingConfig>,
     
     #[account(mut)]
     pub authority: Signer<'info>,
     
-    pub fee_destination: AccountInfo<'info>,
+    // Every admin check trusts the canonical config, so only the upgrade authority may create it
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ CraftingError::UnauthorizedAccess
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(token::mint = fee_mint)]
+    pub fee_destination: Account<'info, TokenAccount>,
+    
+    pub fee_mint: Account<'info, Mint>,
     
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
@@ -558,17 +1132,34 @@ pub struct Initialize<'info> {
 
 #[derive(Accounts)]
 pub struct Craft<'info> {
-    #[account(init, payer = crafter, space = 8 + 512)]
+    #[account(init, payer = crafter, space = 8 + CraftingRecord::LEN)]
     pub crafting_record: Account<'info, CraftingRecord>,
     
+    #[account(seeds = [b"crafting_config".as_ref()], bump = crafting_config.bump)]
     pub crafting_config: Account<'info, CraftingConfig>,
     
     pub recipe: Account<'info, Recipe>,
     
+    // Required for secret recipes
+    #[account(
+        seeds = [b"recipe_discovery".as_ref(), recipe.key().as_ref(), crafter.key().as_ref()],
+        bump = recipe_discovery.bump,
+        seeds::program = crate::recipe::ID
+    )]
+    pub recipe_discovery: Option<Account<'info, RecipeDiscoveryRecord>>,
+    
+    // Accepted for secret recipes in place of a discovery record
+    #[account(
+        seeds = [b"license".as_ref(), recipe.key().as_ref(), crafter.key().as_ref()],
+        bump = recipe_license.bump,
+        seeds::program = crate::recipe_market::ID
+    )]
+    pub recipe_license: Option<Account<'info, RecipeLicense>>,
+    
     #[account(
         init_if_needed,
         payer = crafter,
-        space = 8 + 128,
+        space = 8 + CrafterStats::LEN,
         seeds = [b"crafter-stats", crafter.key().as_ref()],
         bump
     )]
@@ -577,22 +1168,91 @@ pub struct Craft<'info> {
     #[account(mut)]
     pub crafter: Signer<'info>,
     
-    #[account(mut)]
+    #[account(mut, token::mint = fee_mint)]
     pub crafter_token: Account<'info, TokenAccount>,
     
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = fee_destination_token.key() == crafting_config.fee_destination @ CraftingError::InvalidFeeDestination
+    )]
     pub fee_destination_token: Account<'info, TokenAccount>,
     
-    pub recent_blockhash: AccountInfo<'info>,
+    #[account(constraint = fee_mint.key() == crafting_config.fee_mint @ CraftingError::InvalidFeeMint)]
+    pub fee_mint: Account<'info, Mint>,
+    
+    // Royalties owed to the recipe's creator
+    #[account(
+        init_if_needed,
+        payer = crafter,
+        space = 8 + CreatorEarnings::LEN,
+        seeds = [b"creator_earnings".as_ref(), recipe.creator.as_ref()],
+        bump
+    )]
+    pub creator_earnings: Account<'info, CreatorEarnings>,
+    
+    #[account(
+        init_if_needed,
+        payer = crafter,
+        seeds = [b"creator_vault".as_ref(), recipe.creator.as_ref()],
+        bump,
+        token::mint = fee_mint,
+        token::authority = creator_earnings,
+    )]
+    pub creator_vault: Account<'info, TokenAccount>,
+    
+    // Must be requested for this record before the oracle answers
+    #[account(
+        constraint = randomness_request.requester == crafter.key() @ CraftingError::InvalidRandomnessRequest,
+        constraint = randomness_request.consumer == crafting_record.key() @ CraftingError::InvalidRandomnessRequest,
+        constraint = randomness_request.status == RequestStatus::Pending @ CraftingError::RandomnessAlreadyFulfilled,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
+    // Live-ops event to apply, if one is running for this craft
+    #[account(
+        constraint = seasonal_event.crafting_config == crafting_config.key() @ CraftingError::InvalidSeasonalEvent
+    )]
+    pub seasonal_event: Option<Account<'info, SeasonalEvent>>,
     
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct ResolveCraft<'info> {
+    #[account(
+        mut,
+        has_one = crafter @ CraftingError::UnauthorizedAccess,
+        has_one = recipe @ CraftingError::InvalidRecipe,
+        has_one = randomness_request @ CraftingError::InvalidRandomnessRequest,
+        constraint = !crafting_record.resolved @ CraftingError::AlreadyResolved,
+    )]
+    pub crafting_record: Account<'info, CraftingRecord>,
+    
+    pub recipe: Account<'info, Recipe>,
+    
+    #[account(
+        mut,
+        seeds = [b"crafter-stats", crafter.key().as_ref()],
+        bump
+    )]
+    pub crafter_stats: Account<'info, CrafterStats>,
+    
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
+    /// CHECK: Anyone may resolve once the randomness is fulfilled, so a crafter cannot sit on a failure
+    pub crafter: UncheckedAccount<'info>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateConfig<'info> {
-    #[account(mut, has_one = authority @ CraftingError::UnauthorizedAccess)]
+    #[account(
+        mut,
+        seeds = [b"crafting_config".as_ref()],
+        bump = crafting_config.bump,
+        has_one = authority @ CraftingError::UnauthorizedAccess
+    )]
     pub crafting_config: Account<'info, CraftingConfig>,
     
     #[account(mut)]
@@ -615,10 +1275,11 @@ pub struct GuildMembership<'info> {
 }
 
 #[derive(Accounts)]
-pub struct ManageSeasonalEvent<'info> {
-    #[account(init_if_needed, payer = authority, space = 8 + 512)]
+pub struct StartSeasonalEvent<'info> {
+    #[account(init_if_needed, payer = authority, space = 8 + SeasonalEvent::LEN)]
     pub seasonal_event: Account<'info, SeasonalEvent>,
     
+    #[account(seeds = [b"crafting_config".as_ref()], bump = crafting_config.bump)]
     pub crafting_config: Account<'info, CraftingConfig>,
     
     #[account(mut, constraint = authority.key() == crafting_config.authority @ CraftingError::UnauthorizedAccess)]
@@ -628,6 +1289,122 @@ pub struct ManageSeasonalEvent<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct EndSeasonalEvent<'info> {
+    // Any size is fine here; the event is only updated, never created
+    #[account(
+        mut,
+        constraint = seasonal_event.crafting_config == crafting_config.key() @ CraftingError::InvalidSeasonalEvent
+    )]
+    pub seasonal_event: Account<'info, SeasonalEvent>,
+    
+    #[account(seeds = [b"crafting_config".as_ref()], bump = crafting_config.bump)]
+    pub crafting_config: Account<'info, CraftingConfig>,
+    
+    #[account(constraint = authority.key() == crafting_config.authority @ CraftingError::UnauthorizedAccess)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCreatorEarnings<'info> {
+    #[account(
+        mut,
+        seeds = [b"creator_earnings".as_ref(), creator.key().as_ref()],
+        bump = creator_earnings.bump,
+        has_one = creator @ CraftingError::UnauthorizedAccess,
+    )]
+    pub creator_earnings: Account<'info, CreatorEarnings>,
+    
+    #[account(
+        mut,
+        seeds = [b"creator_vault".as_ref(), creator.key().as_ref()],
+        bump,
+    )]
+    pub creator_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = creator_vault.mint)]
+    pub creator_token: Account<'info, TokenAccount>,
+    
+    pub creator: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCraftingConfig<'info> {
+    /// CHECK: Still in the legacy layout, so it is parsed by hand in the instruction
+    #[account(owner = crate::ID)]
+    pub legacy_config: UncheckedAccount<'info>,
+    
+    // Legacy configs lived at keypair addresses; the migrated one becomes the canonical config
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CraftingConfig::LEN,
+        seeds = [b"crafting_config".as_ref()],
+        bump
+    )]
+    pub crafting_config: Account<'info, CraftingConfig>,
+    
+    // Each account can only be migrated once
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MigrationReceipt::LEN,
+        seeds = [b"migration", legacy_config.key().as_ref()],
+        bump
+    )]
+    pub migration_receipt: Account<'info, MigrationReceipt>,
+    
+    // Legacy config's fee destination, which the fee mint is read from
+    pub fee_destination: Account<'info, TokenAccount>,
+    
+    // Baseline configs could be created by anyone, so the upgrade authority picks which one becomes canonical
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ CraftingError::UnauthorizedAccess
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateSeasonalEvent<'info> {
+    /// CHECK: Still in the legacy layout, so it is parsed by hand in the instruction
+    #[account(mut, owner = crate::ID)]
+    pub seasonal_event: UncheckedAccount<'info>,
+    
+    // Each account can only be migrated once
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MigrationReceipt::LEN,
+        seeds = [b"migration", seasonal_event.key().as_ref()],
+        bump
+    )]
+    pub migration_receipt: Account<'info, MigrationReceipt>,
+    
+    // Legacy events could hang off any config, so the canonical config's authority adopts them
+    #[account(seeds = [b"crafting_config".as_ref()], bump = crafting_config.bump)]
+    pub crafting_config: Account<'info, CraftingConfig>,
+    
+    #[account(constraint = authority.key() == crafting_config.authority @ CraftingError::UnauthorizedAccess)]
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[error_code]
 pub enum CraftingError {
     #[msg("Crafting system is currently paused")]
@@ -639,9 +1416,21 @@ pub enum CraftingError {
     #[msg("Recipe is disabled")]
     RecipeDisabled,
     
+    #[msg("Secret recipes can only be crafted by their discoverers or license holders")]
+    RecipeNotDiscovered,
+    
+    #[msg("Recipe license has expired")]
+    LicenseExpired,
+    
+    #[msg("No creator earnings to claim")]
+    NothingToClaim,
+    
     #[msg("Materials do not match recipe requirements")]
     MaterialMismatch,
     
+    #[msg("Material is not owned by the crafter")]
+    MaterialNotOwned,
+    
     #[msg("Insufficient fee provided")]
     InsufficientFee,
     
@@ -656,4 +1445,55 @@ pub enum CraftingError {
     
     #[msg("Success rate bonus cannot exceed 25")]
     InvalidBonus,
+    
+    #[msg("Recipe does not match the crafting record")]
+    InvalidRecipe,
+    
+    #[msg("Randomness request is not bound to this crafting record")]
+    InvalidRandomnessRequest,
+    
+    #[msg("Randomness was fulfilled before crafting started")]
+    RandomnessAlreadyFulfilled,
+    
+    #[msg("Crafting attempt has already been resolved")]
+    AlreadyResolved,
+    
+    #[msg("Event name is too long")]
+    NameTooLong,
+    
+    #[msg("Event description is too long")]
+    DescriptionTooLong,
+    
+    #[msg("Too many time-of-day windows")]
+    TooManyTimeWindows,
+    
+    #[msg("Time-of-day window hours must be distinct and between 0 and 23")]
+    InvalidTimeWindow,
+    
+    #[msg("Time-of-day bonus cannot exceed 10")]
+    InvalidTimeOfDayBonus,
+    
+    #[msg("Seasonal event lists too many recipes or material types")]
+    TooManyScopeEntries,
+    
+    #[msg("Account is not in the legacy layout")]
+    InvalidMigrationAccount,
+    
+    #[msg("Material instance has no amount left to craft with")]
+    MaterialUsedUp,
+    
+    #[msg("Seasonal event does not belong to the crafting config")]
+    InvalidSeasonalEvent,
+    
+    #[msg("Ingredients do not match the secret recipe")]
+    InvalidIngredients,
+    
+    #[msg("Fee destination is not the crafting config's")]
+    InvalidFeeDestination,
+    
+    #[msg("Fee mint is not the crafting config's")]
+    InvalidFeeMint,
+    
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
 }
\ No newline at end of file
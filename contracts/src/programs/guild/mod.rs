@@ -17,8 +17,9 @@ pub mod magic_vial_guild {
         min_vial_deposit: u64,
     ) -> Result<()> {
         // Validate input parameters
-        require!(name.len() <= 50, GuildError::NameTooLong);
-        require!(description.len() <= 200, GuildError::DescriptionTooLong);
+        require!(name.len() <= Guild::MAX_NAME_LEN, GuildError::NameTooLong);
+        require!(description.len() <= Guild::MAX_DESCRIPTION_LEN, GuildError::DescriptionTooLong);
+        require!(image_uri.len() <= Guild::MAX_IMAGE_URI_LEN, GuildError::UriTooLong);
         require!(min_members >= 3, GuildError::MinMembersTooLow);
         require!(min_vial_deposit >= 1000, GuildError::MinDepositTooLow);
         
@@ -138,8 +139,8 @@ pub mod magic_vial_guild {
         deadline: i64,
     ) -> Result<()> {
         // Validate inputs
-        require!(name.len() <= 50, GuildError::NameTooLong);
-        require!(description.len() <= 200, GuildError::DescriptionTooLow);
+        require!(name.len() <= GuildProject::MAX_NAME_LEN, GuildError::NameTooLong);
+        require!(description.len() <= GuildProject::MAX_DESCRIPTION_LEN, GuildError::DescriptionTooLong);
         require!(vial_contribution > 0, GuildError::NoContribution);
         require!(deadline > Clock::get()?.unix_timestamp, GuildError::InvalidDeadline);
         
@@ -282,6 +283,23 @@ pub struct Guild {
     pub active: bool,             // Whether guild is active
 }
 
+impl Guild {
+    pub const MAX_NAME_LEN: usize = 50;
+    pub const MAX_DESCRIPTION_LEN: usize = 200;
+    pub const MAX_IMAGE_URI_LEN: usize = 200;
+    
+    pub const LEN: usize = 4 + Self::MAX_NAME_LEN + // name
+                           4 + Self::MAX_DESCRIPTION_LEN + // description
+                           4 + Self::MAX_IMAGE_URI_LEN + // image_uri
+                           32 + // founder
+                           32 + // treasury
+                           1 +  // min_members
+                           8 +  // min_vial_deposit
+                           1 +  // member_count
+                           8 +  // created_at
+                           1;   // active
+}
+
 #[account]
 pub struct GuildMember {
     pub guild: Pubkey,            // Guild this membership belongs to
@@ -292,6 +310,15 @@ pub struct GuildMember {
     pub active: bool,             // Whether membership is active
 }
 
+impl GuildMember {
+    pub const LEN: usize = 32 + // guild
+                           32 + // member
+                           1 +  // role
+                           8 +  // joined_at
+                           8 +  // contribution
+                           1;   // active
+}
+
 #[account]
 pub struct GuildProject {
     pub guild: Pubkey,            // Guild this project belongs to
@@ -305,6 +332,21 @@ pub struct GuildProject {
     pub status: u8,               // Project status (see ProjectStatus enum)
 }
 
+impl GuildProject {
+    pub const MAX_NAME_LEN: usize = 50;
+    pub const MAX_DESCRIPTION_LEN: usize = 200;
+    
+    pub const LEN: usize = 32 + // guild
+                           4 + Self::MAX_NAME_LEN + // name
+                           4 + Self::MAX_DESCRIPTION_LEN + // description
+                           32 + // creator
+                           32 + // target_recipe
+                           8 +  // vial_contribution
+                           8 +  // created_at
+                           8 +  // deadline
+                           1;   // status
+}
+
 #[account]
 pub struct ProjectContribution {
     pub project: Pubkey,          // Project contributed to
@@ -313,12 +355,19 @@ pub struct ProjectContribution {
     pub contributed_at: i64,      // Contribution timestamp
 }
 
+impl ProjectContribution {
+    pub const LEN: usize = 32 + // project
+                           32 + // contributor
+                           8 +  // amount
+                           8;   // contributed_at
+}
+
 #[derive(Accounts)]
 pub struct CreateGuild<'info> {
-    #[account(init, payer = founder, space = 8 + 512)]
+    #[account(init, payer = founder, space = 8 + Guild::LEN)]
     pub guild: Account<'info, Guild>,
     
-    #[account(init, payer = founder, space = 8 + 128)]
+    #[account(init, payer = founder, space = 8 + GuildMember::LEN)]
     pub founder_member: Account<'info, GuildMember>,
     
     #[account(mut)]
@@ -343,7 +392,7 @@ pub struct JoinGuild<'info> {
     #[account(mut)]
     pub guild: Account<'info, Guild>,
     
-    #[account(init, payer = joiner, space = 8 + 128)]
+    #[account(init, payer = joiner, space = 8 + GuildMember::LEN)]
     pub new_member: Account<'info, GuildMember>,
     
     #[account(mut)]
@@ -379,10 +428,10 @@ pub struct CreateProject<'info> {
     #[account(has_one = member @ GuildError::NotMember)]
     pub member: Account<'info, GuildMember>,
     
-    #[account(init, payer = creator, space = 8 + 512)]
+    #[account(init, payer = creator, space = 8 + GuildProject::LEN)]
     pub project: Account<'info, GuildProject>,
     
-    #[account(init, payer = creator, space = 8 + 128)]
+    #[account(init, payer = creator, space = 8 + ProjectContribution::LEN)]
     pub creator_contribution: Account<'info, ProjectContribution>,
     
     #[account(mut)]
@@ -408,7 +457,7 @@ pub struct ContributeToProject<'info> {
     
     pub project: Account<'info, GuildProject>,
     
-    #[account(init, payer = contributor, space = 8 + 128)]
+    #[account(init, payer = contributor, space = 8 + ProjectContribution::LEN)]
     pub contribution: Account<'info, ProjectContribution>,
     
     #[account(mut)]
@@ -491,4 +540,7 @@ pub enum GuildError {
     
     #[msg("Cannot assign Founder role to another member")]
     CannotAssignFounder,
+    
+    #[msg("Image URI too long (max 200 characters)")]
+    UriTooLong,
 } 
\ No newline at end of file
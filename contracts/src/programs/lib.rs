@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token};
 use solana_program::sysvar::{clock};
+use magicvial_types::{MaterialCategory, Rarity};
+use randomness::{RandomnessRequest, RequestStatus};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -104,32 +106,46 @@ pub mod magic_vial {
         // Use recipe materials - in a real implementation we would check user has materials
         // and burn them as part of the crafting process
         
-        // Determine if craft successful using slot as randomness source
-        // In production, you'd want a more sophisticated randomness source
+        // Set up craft result; the outcome is rolled in resolve_craft_item
+        // once the bound randomness request has been fulfilled
         let clock = clock::Clock::get()?;
-        let random_value: u64 = (clock.slot as u64).wrapping_add(clock.unix_timestamp as u64);
-        let is_successful = random_value % 10000 < base_success_rate as u64;
-        
-        // Set up craft result
         craft_result.crafter = ctx.accounts.authority.key();
         craft_result.recipe_id = recipe.recipe_id;
         craft_result.craft_id = magic_vial.total_crafts;
-        craft_result.success = is_successful;
+        craft_result.success = false;
         craft_result.creation_time = clock.unix_timestamp;
+        craft_result.success_rate_bps = base_success_rate;
+        craft_result.randomness_request = ctx.accounts.randomness_request.key();
+        craft_result.resolved = false;
+        
+        magic_vial.total_crafts = magic_vial.total_crafts.checked_add(1).unwrap();
+        
+        Ok(())
+    }
+
+    // Roll the outcome of a crafted item from its fulfilled randomness
+    pub fn resolve_craft_item(ctx: Context<ResolveCraftItem>) -> Result<()> {
+        let recipe = &ctx.accounts.recipe;
+        let craft_result = &mut ctx.accounts.craft_result;
+        let randomness = ctx.accounts.randomness_request.value()?;
+        
+        let success_roll = randomness::roll_below(&randomness, b"craft_item_success", 10000);
+        let is_successful = success_roll < craft_result.success_rate_bps as u64;
+        
+        craft_result.success = is_successful;
+        craft_result.resolved = true;
         
         if is_successful {
             // Select output type from weighted possibilities
-            let output_index = select_weighted_output(&recipe.output_weights, random_value)?;
+            let output_index = select_weighted_output(&recipe.output_weights, &randomness)?;
             craft_result.result_type = recipe.output_types[output_index];
-            craft_result.rarity = determine_rarity(recipe.difficulty, random_value)?;
+            craft_result.rarity = determine_rarity(recipe.difficulty, &randomness)?;
         } else {
             // Failed experiment
             craft_result.result_type = 0; // 0 = failed experiment
-            craft_result.rarity = 1; // Common rarity for failures
+            craft_result.rarity = Rarity::Common; // Common rarity for failures
         }
         
-        magic_vial.total_crafts = magic_vial.total_crafts.checked_add(1).unwrap();
-        
         emit!(ItemCrafted {
             craft_id: craft_result.craft_id,
             crafter: craft_result.crafter,
@@ -167,33 +183,25 @@ pub mod magic_vial {
 }
 
 // Helper functions
-fn select_weighted_output(weights: &[u16], random_value: u64) -> Result<usize> {
-    let total_weight: u16 = weights.iter().sum();
-    let mut remaining = (random_value % total_weight as u64) as u16;
-    
-    for (i, &weight) in weights.iter().enumerate() {
-        if remaining < weight {
-            return Ok(i);
-        }
-        remaining = remaining.saturating_sub(weight);
-    }
+fn select_weighted_output(weights: &[u16], randomness: &[u8; 32]) -> Result<usize> {
+    let weights: Vec<u64> = weights.iter().map(|&w| w as u64).collect();
     
     // Default to first output if something went wrong
-    Ok(0)
+    Ok(randomness::roll_weighted(randomness, b"craft_item_output", &weights).unwrap_or(0))
 }
 
-fn determine_rarity(difficulty: u8, random_value: u64) -> Result<u8> {
+fn determine_rarity(difficulty: u8, randomness: &[u8; 32]) -> Result<Rarity> {
     // Simple rarity determination algorithm
     // Higher difficulty means higher chance of rare results
-    let rarity_roll = random_value % 100;
+    let rarity_roll = randomness::roll_percent(randomness, b"craft_item_rarity");
     
     let rarity = match (rarity_roll, difficulty) {
-        (0..=59, _) => 1,     // Common (60%)
-        (60..=84, _) => 2,    // Uncommon (25%)
-        (85..=94, _) => 3,    // Rare (10%)
-        (95..=98, _) => 4,    // Epic (4%)
-        (99, _) => 5,         // Legendary (1%)
-        _ => 1,               // Default to common
+        (0..=59, _) => Rarity::Common,     // 60%
+        (60..=84, _) => Rarity::Uncommon,  // 25%
+        (85..=94, _) => Rarity::Rare,      // 10%
+        (95..=98, _) => Rarity::Epic,      // 4%
+        (99, _) => Rarity::Legendary,      // 1%
+        _ => Rarity::Common,               // Default to common
     };
     
     Ok(rarity)
@@ -300,12 +308,43 @@ pub struct CraftItem<'info> {
     )]
     pub craft_result: Account<'info, CraftResult>,
     
+    // Must be requested for this craft before the oracle answers
+    #[account(
+        constraint = randomness_request.requester == authority.key() @ CustomError::InvalidRandomnessRequest,
+        constraint = randomness_request.consumer == craft_result.key() @ CustomError::InvalidRandomnessRequest,
+        constraint = randomness_request.status == RequestStatus::Pending @ CustomError::RandomnessAlreadyFulfilled,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ResolveCraftItem<'info> {
+    #[account(
+        seeds = [
+            b"recipe",
+            recipe.recipe_id.to_le_bytes().as_ref(),
+        ],
+        bump = recipe.bump,
+        constraint = recipe.recipe_id == craft_result.recipe_id @ CustomError::InvalidRecipeParameters,
+    )]
+    pub recipe: Account<'info, Recipe>,
+    
+    #[account(
+        mut,
+        constraint = craft_result.randomness_request == randomness_request.key() @ CustomError::InvalidRandomnessRequest,
+        constraint = !craft_result.resolved @ CustomError::AlreadyResolved,
+    )]
+    pub craft_result: Account<'info, CraftResult>,
+    
+    // Anyone may resolve once the randomness is fulfilled, so a crafter cannot sit on a failure
+    pub randomness_request: Account<'info, RandomnessRequest>,
+}
+
 #[derive(Accounts)]
 #[instruction(params: GuildParams)]
 pub struct CreateGuild<'info> {
@@ -348,8 +387,8 @@ impl MagicVialPlatform {
 pub struct Material {
     pub material_id: u64,
     pub name: String,
-    pub material_type: u8, // 1 = basic, 2 = rare, 3 = seasonal, 4 = mysterious
-    pub rarity: u8,        // 1-5 scale
+    pub material_type: MaterialCategory,
+    pub rarity: Rarity,
     pub seasonal: bool,
     pub season_id: Option<u8>,
     pub max_supply: Option<u64>,
@@ -407,12 +446,15 @@ pub struct CraftResult {
     pub recipe_id: u64,
     pub success: bool,
     pub result_type: u16,
-    pub rarity: u8,
+    pub rarity: Rarity,
     pub creation_time: i64,
+    pub success_rate_bps: u16,
+    pub randomness_request: Pubkey,
+    pub resolved: bool,
 }
 
 impl CraftResult {
-    pub const SPACE: usize = 8 + 32 + 8 + 1 + 2 + 1 + 8;
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 2 + 1 + 8 + 2 + 32 + 1;
 }
 
 #[account]
@@ -442,8 +484,8 @@ pub struct InitializeParams {
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct MaterialParams {
     pub name: String,
-    pub material_type: u8,
-    pub rarity: u8,
+    pub material_type: MaterialCategory,
+    pub rarity: Rarity,
     pub seasonal: bool,
     pub season_id: Option<u8>,
     pub max_supply: Option<u64>,
@@ -486,8 +528,8 @@ pub struct PlatformInitialized {
 pub struct MaterialAdded {
     pub material_id: u64,
     pub name: String,
-    pub material_type: u8,
-    pub rarity: u8,
+    pub material_type: MaterialCategory,
+    pub rarity: Rarity,
 }
 
 #[event]
@@ -505,7 +547,7 @@ pub struct ItemCrafted {
     pub recipe_id: u64,
     pub success: bool,
     pub result_type: u16,
-    pub rarity: u8,
+    pub rarity: Rarity,
 }
 
 #[event]
@@ -526,4 +568,12 @@ pub enum CustomError {
     GuildLevelRequirementNotMet,
     #[msg("Invalid recipe parameters")]
     InvalidRecipeParameters,
+    #[msg("Unauthorized access")]
+    Unauthorized,
+    #[msg("Randomness request is not bound to this craft")]
+    InvalidRandomnessRequest,
+    #[msg("Randomness was fulfilled before crafting started")]
+    RandomnessAlreadyFulfilled,
+    #[msg("Craft has already been resolved")]
+    AlreadyResolved,
 } 
\ No newline at end of file
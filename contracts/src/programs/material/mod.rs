@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use magicvial_types::{MaterialCategory, Rarity};
+use randomness::{RandomnessRequest, RequestStatus};
+use crate::crafting::{CrafterStats, CraftingConfig};
 
 declare_id!("matXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
 
@@ -12,17 +15,35 @@ pub mod magic_vial_material {
         ctx: Context<InitializeMaterialType>,
         name: String,
         description: String,
-        material_type: u8, // 1: Basic, 2: Rare, 3: Seasonal, 4: Mysterious
-        rarity: u8,        // 1-5, higher is rarer
+        material_type: MaterialCategory,
+        rarity: Rarity,
         uri: String,
         season_id: Option<u8>, // Season identifier for seasonal materials
         attributes: Option<Vec<MaterialAttribute>>, // Optional special attributes
         success_rate_bonus: u8, // Bonus to crafting success rate (0-50)
         discovery_difficulty: u8, // How hard to discover (1-100)
     ) -> Result<()> {
-        require!(rarity >= 1 && rarity <= 5, MaterialError::InvalidRarity);
-        require!(success_rate_bonus <= 50, MaterialError::InvalidBonus);
+        require!(
+            success_rate_bonus <= MaterialTypeInfo::MAX_SUCCESS_RATE_BONUS,
+            MaterialError::InvalidBonus
+        );
         require!(discovery_difficulty <= 100, MaterialError::InvalidDiscoveryDifficulty);
+        require!(name.len() <= MaterialTypeInfo::MAX_NAME_LEN, MaterialError::NameTooLong);
+        require!(
+            description.len() <= MaterialTypeInfo::MAX_DESCRIPTION_LEN,
+            MaterialError::DescriptionTooLong
+        );
+        require!(uri.len() <= MaterialTypeInfo::MAX_URI_LEN, MaterialError::UriTooLong);
+        if let Some(attrs) = &attributes {
+            require!(
+                attrs.len() <= MaterialTypeInfo::MAX_ATTRIBUTES,
+                MaterialError::TooManyAttributes
+            );
+            require!(
+                attrs.iter().all(|attr| attr.name.len() <= MaterialAttribute::MAX_NAME_LEN),
+                MaterialError::NameTooLong
+            );
+        }
         
         let material_type_info = &mut ctx.accounts.material_type_info;
         material_type_info.name = name;
@@ -43,11 +64,15 @@ pub mod magic_vial_material {
         material_type_info.success_rate_bonus = success_rate_bonus;
         material_type_info.discovery_difficulty = discovery_difficulty;
         
-        // Set expiry timestamp for seasonal materials
-        if let Some(season) = season_id {
-            // Season materials expire after approximately 3 months
-            // This is simplified and would be managed by governance in reality
-            material_type_info.expires_at = Some(Clock::get()?.unix_timestamp + 7_776_000); // 90 days
+        // Seasonal materials take their window from the season they belong to
+        if let Some(season_id) = season_id {
+            let season = ctx.accounts.season.as_ref().ok_or(MaterialError::MissingSeason)?;
+            require!(season.season_id == season_id, MaterialError::SeasonMismatch);
+            require!(
+                !season.is_over(Clock::get()?.unix_timestamp),
+                MaterialError::SeasonOver
+            );
+            material_type_info.expires_at = Some(season.end_time);
         } else {
             material_type_info.expires_at = None;
         }
@@ -61,10 +86,11 @@ pub mod magic_vial_material {
         amount: u64,
     ) -> Result<()> {
         // Check if material type is still valid
-        if let Some(expires_at) = ctx.accounts.material_type_info.expires_at {
-            let current_time = Clock::get()?.unix_timestamp;
-            require!(current_time < expires_at, MaterialError::MaterialExpired);
-        }
+        check_material_window(
+            &ctx.accounts.material_type_info,
+            ctx.accounts.season.as_deref(),
+            Clock::get()?.unix_timestamp,
+        )?;
         
         require!(ctx.accounts.material_type_info.enabled, MaterialError::MaterialDisabled);
         
@@ -92,54 +118,215 @@ pub mod magic_vial_material {
         Ok(())
     }
 
-    // Discover a material (for exploration gameplay)
-    pub fn discover_material(
-        ctx: Context<DiscoverMaterial>,
+    // Create an exploration area that materials can be discovered in
+    pub fn create_exploration_area(
+        ctx: Context<CreateExplorationArea>,
         area_id: u8,
-        explorer_level: u8,
+        name: String,
+        min_level: u8,
+        cooldown_seconds: i64,
+        stamina_cost: u8,
+    ) -> Result<()> {
+        require!(name.len() <= ExplorationArea::MAX_NAME_LEN, MaterialError::NameTooLong);
+        require!(cooldown_seconds >= 0, MaterialError::InvalidCooldown);
+        require!(stamina_cost <= ExplorerState::MAX_STAMINA, MaterialError::InvalidStaminaCost);
+        
+        let exploration_area = &mut ctx.accounts.exploration_area;
+        exploration_area.area_id = area_id;
+        exploration_area.name = name;
+        exploration_area.authority = ctx.accounts.authority.key();
+        exploration_area.drops = Vec::new();
+        exploration_area.min_level = min_level;
+        exploration_area.cooldown_seconds = cooldown_seconds;
+        exploration_area.stamina_cost = stamina_cost;
+        exploration_area.enabled = true;
+        exploration_area.bump = ctx.bumps.exploration_area;
+        
+        Ok(())
+    }
+    
+    // Change an exploration area's level requirement, cooldown, stamina cost or availability
+    pub fn update_exploration_area(
+        ctx: Context<UpdateExplorationArea>,
+        min_level: u8,
+        cooldown_seconds: i64,
+        stamina_cost: u8,
+        enabled: bool,
     ) -> Result<()> {
-        // Verify the material can be discovered in this area
+        require!(cooldown_seconds >= 0, MaterialError::InvalidCooldown);
+        require!(stamina_cost <= ExplorerState::MAX_STAMINA, MaterialError::InvalidStaminaCost);
+        
+        let exploration_area = &mut ctx.accounts.exploration_area;
+        exploration_area.min_level = min_level;
+        exploration_area.cooldown_seconds = cooldown_seconds;
+        exploration_area.stamina_cost = stamina_cost;
+        exploration_area.enabled = enabled;
+        
+        Ok(())
+    }
+    
+    // Add a material type to an area's drop table, or change its weight and amount
+    pub fn set_area_drop(
+        ctx: Context<SetAreaDrop>,
+        weight: u16,
+        amount: u64,
+    ) -> Result<()> {
+        require!(weight > 0 && amount > 0, MaterialError::InvalidAreaDrop);
+        
         let material_type_info = &ctx.accounts.material_type_info;
+        let drop = AreaDrop {
+            material_type: material_type_info.key(),
+            weight,
+            amount,
+            discovery_difficulty: material_type_info.discovery_difficulty,
+            rarity: material_type_info.rarity,
+        };
         
-        // Check if material is discoverable
-        require!(material_type_info.enabled, MaterialError::MaterialDisabled);
+        let drops = &mut ctx.accounts.exploration_area.drops;
+        if let Some(existing) = drops.iter_mut().find(|d| d.material_type == drop.material_type) {
+            *existing = drop;
+        } else {
+            require!(drops.len() < ExplorationArea::MAX_DROPS, MaterialError::TooManyAreaDrops);
+            drops.push(drop);
+        }
         
-        // Check for seasonal expiry
-        if let Some(expires_at) = material_type_info.expires_at {
-            let current_time = Clock::get()?.unix_timestamp;
-            require!(current_time < expires_at, MaterialError::MaterialExpired);
+        Ok(())
+    }
+    
+    // Remove a material type from an area's drop table
+    pub fn remove_area_drop(
+        ctx: Context<UpdateExplorationArea>,
+        material_type: Pubkey,
+    ) -> Result<()> {
+        let drops = &mut ctx.accounts.exploration_area.drops;
+        let index = drops
+            .iter()
+            .position(|d| d.material_type == material_type)
+            .ok_or(MaterialError::AreaDropNotFound)?;
+        drops.remove(index);
+        
+        Ok(())
+    }
+
+    // Explore an area (for exploration gameplay)
+    pub fn discover_material(
+        ctx: Context<DiscoverMaterial>,
+    ) -> Result<()> {
+        let exploration_area = &ctx.accounts.exploration_area;
+        require!(exploration_area.enabled, MaterialError::AreaDisabled);
+        require!(!exploration_area.drops.is_empty(), MaterialError::AreaDropNotFound);
+        
+        // Explorer level comes from the on-chain crafting profile
+        let explorer_level = ctx.accounts.crafter_stats.experience_level;
+        require!(explorer_level >= exploration_area.min_level, MaterialError::ExplorerLevelTooLow);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        let explorer_state = &mut ctx.accounts.explorer_state;
+        if explorer_state.explorer == Pubkey::default() {
+            explorer_state.explorer = ctx.accounts.explorer.key();
+            explorer_state.stamina = ExplorerState::MAX_STAMINA;
+            explorer_state.stamina_updated_at = current_time;
+            explorer_state.bump = ctx.bumps.explorer_state;
+        } else {
+            require!(
+                current_time >= explorer_state.last_explored_at + exploration_area.cooldown_seconds,
+                MaterialError::ExplorationCooldown
+            );
         }
         
+        explorer_state.regenerate_stamina(current_time);
+        require!(
+            explorer_state.stamina >= exploration_area.stamina_cost,
+            MaterialError::InsufficientStamina
+        );
+        explorer_state.stamina -= exploration_area.stamina_cost;
+        explorer_state.last_explored_at = current_time;
+        explorer_state.discovery_attempts += 1;
+        
+        // Record the attempt; the drop and outcome are rolled in resolve_discovery
+        // once the bound randomness request has been fulfilled
+        let discovery_record = &mut ctx.accounts.discovery_record;
+        discovery_record.explorer = ctx.accounts.explorer.key();
+        discovery_record.material_type = Pubkey::default();
+        discovery_record.timestamp = current_time;
+        discovery_record.area = exploration_area.key();
+        discovery_record.explorer_level = explorer_level;
+        discovery_record.discovery_chance = 0;
+        discovery_record.amount = 0;
+        discovery_record.randomness_request = ctx.accounts.randomness_request.key();
+        discovery_record.resolved = false;
+        discovery_record.success = false;
+        discovery_record.claimed = false;
+        
+        Ok(())
+    }
+    
+    // Roll which material an exploration turns up and whether it is found
+    pub fn resolve_discovery(
+        ctx: Context<ResolveDiscovery>,
+    ) -> Result<()> {
+        let randomness = ctx.accounts.randomness_request.value()?;
+        let exploration_area = &ctx.accounts.exploration_area;
+        let discovery_record = &mut ctx.accounts.discovery_record;
+        
+        let weights: Vec<u64> = exploration_area.drops.iter().map(|d| d.weight as u64).collect();
+        let drop = randomness::roll_weighted(&randomness, b"area_drop", &weights)
+            .map(|i| &exploration_area.drops[i])
+            .ok_or(MaterialError::AreaDropNotFound)?;
+        
         // Calculate discovery chance based on explorer level and material difficulty
         let discovery_chance = calculate_discovery_chance(
-            explorer_level,
-            material_type_info.discovery_difficulty,
-            material_type_info.rarity,
+            discovery_record.explorer_level,
+            drop.discovery_difficulty,
+            drop.rarity,
         );
         
-        // Use recent blockhash for randomness (in production would use a VRF)
-        let recent_blockhash = ctx.accounts.recent_blockhash.key();
-        let explorer = ctx.accounts.explorer.key();
-        let current_time = Clock::get()?.unix_timestamp;
+        discovery_record.material_type = drop.material_type;
+        discovery_record.amount = drop.amount;
+        discovery_record.discovery_chance = discovery_chance;
+        discovery_record.success = determine_discovery_success(discovery_chance, &randomness);
+        discovery_record.resolved = true;
         
-        let successful_discovery = determine_discovery_success(
-            discovery_chance,
-            recent_blockhash,
-            explorer,
-            current_time,
-        );
+        Ok(())
+    }
+    
+    // Mint the material found by a successful discovery to the explorer
+    pub fn claim_discovery(
+        ctx: Context<ClaimDiscovery>,
+    ) -> Result<()> {
+        let material_type_info = &ctx.accounts.material_type_info;
+        require!(material_type_info.enabled, MaterialError::MaterialDisabled);
         
-        require!(successful_discovery, MaterialError::DiscoveryFailed);
+        // Check for seasonal expiry
+        check_material_window(
+            material_type_info,
+            ctx.accounts.season.as_deref(),
+            Clock::get()?.unix_timestamp,
+        )?;
         
-        // If discovery successful, mint material to the explorer
         let discovery_record = &mut ctx.accounts.discovery_record;
-        discovery_record.explorer = explorer;
-        discovery_record.material_type = material_type_info.key();
-        discovery_record.timestamp = current_time;
-        discovery_record.area_id = area_id;
+        discovery_record.claimed = true;
+        
+        let material_instance = &mut ctx.accounts.material_instance;
+        material_instance.material_type = material_type_info.key();
+        material_instance.owner = ctx.accounts.explorer.key();
+        material_instance.amount = discovery_record.amount;
+        material_instance.created_at = Clock::get()?.unix_timestamp;
+        material_instance.rarity = material_type_info.rarity;
+        material_instance.material_type_enum = material_type_info.material_type;
         
-        // Mint a small amount of the discovered material
-        // In production implementation, would use CPI to the mint_material instruction
+        // Same NFT representation as mint_material
+        token::mint_to(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.explorer_token.to_account_info(),
+                    authority: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            1,
+        )?;
         
         Ok(())
     }
@@ -195,29 +382,76 @@ pub mod magic_vial_material {
         Ok(())
     }
     
+    // Add an entry to the synthesis table: what a material of a given rarity upgrades into
+    pub fn create_synthesis_recipe(
+        ctx: Context<CreateSynthesisRecipe>,
+        input_count: u64,
+        output_amount: u64,
+        success_chance: u8,
+    ) -> Result<()> {
+        validate_synthesis_terms(input_count, output_amount, success_chance)?;
+        
+        let input_type = &ctx.accounts.input_material_type;
+        let output_type = &ctx.accounts.output_material_type;
+        require!(output_type.rarity > input_type.rarity, MaterialError::InvalidSynthesisOutput);
+        
+        let synthesis_recipe = &mut ctx.accounts.synthesis_recipe;
+        synthesis_recipe.input_material_type = input_type.key();
+        synthesis_recipe.input_rarity = input_type.rarity;
+        synthesis_recipe.output_material_type = output_type.key();
+        synthesis_recipe.input_count = input_count;
+        synthesis_recipe.output_amount = output_amount;
+        synthesis_recipe.success_chance = success_chance;
+        synthesis_recipe.authority = ctx.accounts.authority.key();
+        synthesis_recipe.enabled = true;
+        synthesis_recipe.bump = ctx.bumps.synthesis_recipe;
+        
+        Ok(())
+    }
+    
+    // Change the input counts, output amount, success chance or availability of a synthesis entry
+    pub fn update_synthesis_recipe(
+        ctx: Context<UpdateSynthesisRecipe>,
+        input_count: u64,
+        output_amount: u64,
+        success_chance: u8,
+        enabled: bool,
+    ) -> Result<()> {
+        validate_synthesis_terms(input_count, output_amount, success_chance)?;
+        
+        let synthesis_recipe = &mut ctx.accounts.synthesis_recipe;
+        synthesis_recipe.input_count = input_count;
+        synthesis_recipe.output_amount = output_amount;
+        synthesis_recipe.success_chance = success_chance;
+        synthesis_recipe.enabled = enabled;
+        
+        Ok(())
+    }
+    
     // Combine two materials to upgrade (material synthesis)
     pub fn synthesize_materials(
         ctx: Context<SynthesizeMaterials>,
     ) -> Result<()> {
-        // Check that both input materials are the same type
-        require!(
-            ctx.accounts.material_instance_1.material_type == ctx.accounts.material_instance_2.material_type,
-            MaterialError::IncompatibleMaterials
-        );
+        let synthesis_recipe = &ctx.accounts.synthesis_recipe;
+        require!(synthesis_recipe.enabled, MaterialError::SynthesisDisabled);
         
-        // Check that both materials have sufficient quantity
-        require!(ctx.accounts.material_instance_1.amount >= 5, MaterialError::InsufficientAmount);
-        require!(ctx.accounts.material_instance_2.amount >= 5, MaterialError::InsufficientAmount);
-        
-        // Get material type info
-        let material_type = &ctx.accounts.material_type_info;
+        // Both inputs must be of the entry's material type and rarity
+        for material_instance in [&ctx.accounts.material_instance_1, &ctx.accounts.material_instance_2] {
+            require!(
+                material_instance.material_type == synthesis_recipe.input_material_type
+                    && material_instance.rarity == synthesis_recipe.input_rarity,
+                MaterialError::IncompatibleMaterials
+            );
+        }
         
-        // Check if this material type can be synthesized to a higher level
-        require!(material_type.rarity < 5, MaterialError::MaxRarityReached);
+        // Check that both materials have sufficient quantity
+        let input_count = synthesis_recipe.input_count;
+        require!(ctx.accounts.material_instance_1.amount >= input_count, MaterialError::InsufficientAmount);
+        require!(ctx.accounts.material_instance_2.amount >= input_count, MaterialError::InsufficientAmount);
         
-        // Consume 5 from each input material
-        ctx.accounts.material_instance_1.amount -= 5;
-        ctx.accounts.material_instance_2.amount -= 5;
+        // Consume the input count from each input material
+        ctx.accounts.material_instance_1.amount -= input_count;
+        ctx.accounts.material_instance_2.amount -= input_count;
         
         // If either material is fully consumed, burn its NFT
         if ctx.accounts.material_instance_1.amount == 0 {
@@ -248,12 +482,185 @@ pub mod magic_vial_material {
             )?;
         }
         
-        // In a real implementation, would mint a new material of higher rarity
-        // using cross-program invocation to mint_material
+        // Record the attempt; the roll happens in resolve_synthesis once
+        // the bound randomness request has been fulfilled
+        let synthesis_attempt = &mut ctx.accounts.synthesis_attempt;
+        synthesis_attempt.crafter = ctx.accounts.crafter.key();
+        synthesis_attempt.synthesis_recipe = synthesis_recipe.key();
+        synthesis_attempt.output_material_type = synthesis_recipe.output_material_type;
+        synthesis_attempt.output_amount = synthesis_recipe.output_amount;
+        synthesis_attempt.success_chance = synthesis_recipe.success_chance;
+        synthesis_attempt.randomness_request = ctx.accounts.randomness_request.key();
+        synthesis_attempt.timestamp = Clock::get()?.unix_timestamp;
+        synthesis_attempt.resolved = false;
+        synthesis_attempt.success = false;
+        synthesis_attempt.claimed = false;
 
         Ok(())
     }
     
+    // Roll the outcome of a synthesis attempt from its fulfilled randomness
+    pub fn resolve_synthesis(
+        ctx: Context<ResolveSynthesis>,
+    ) -> Result<()> {
+        let randomness = ctx.accounts.randomness_request.value()?;
+        let synthesis_attempt = &mut ctx.accounts.synthesis_attempt;
+        
+        let roll = randomness::roll_percent(&randomness, b"material_synthesis");
+        synthesis_attempt.success = roll < synthesis_attempt.success_chance;
+        synthesis_attempt.resolved = true;
+        
+        Ok(())
+    }
+    
+    // Mint the upgraded material of a successful synthesis to the crafter
+    pub fn claim_synthesis_output(
+        ctx: Context<ClaimSynthesisOutput>,
+    ) -> Result<()> {
+        let output_type = &ctx.accounts.output_material_type;
+        require!(output_type.enabled, MaterialError::MaterialDisabled);
+        
+        let synthesis_attempt = &mut ctx.accounts.synthesis_attempt;
+        synthesis_attempt.claimed = true;
+        
+        let material_instance = &mut ctx.accounts.material_instance;
+        material_instance.material_type = output_type.key();
+        material_instance.owner = ctx.accounts.crafter.key();
+        material_instance.amount = synthesis_attempt.output_amount;
+        material_instance.created_at = Clock::get()?.unix_timestamp;
+        material_instance.rarity = output_type.rarity;
+        material_instance.material_type_enum = output_type.material_type;
+        
+        // Same NFT representation as mint_material
+        token::mint_to(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.crafter_token.to_account_info(),
+                    authority: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        
+        Ok(())
+    }
+    
+    // Convert a material type stored with 1-based u8 category and rarity codes
+    pub fn migrate_material_type(
+        ctx: Context<MigrateMaterialType>,
+    ) -> Result<()> {
+        let account_info = ctx.accounts.material_type_info.to_account_info();
+        
+        let legacy = {
+            let data = account_info.try_borrow_data()?;
+            require!(
+                data.len() > 8 && data[..8] == MaterialTypeInfo::discriminator(),
+                MaterialError::InvalidMigrationAccount
+            );
+            LegacyMaterialTypeInfo::deserialize(&mut &data[8..])
+                .map_err(|_| error!(MaterialError::InvalidMigrationAccount))?
+        };
+        
+        // Both layouts have the same size, so only the type's authority may say which one it is in
+        require!(
+            legacy.authority == ctx.accounts.authority.key(),
+            MaterialError::InvalidOwner
+        );
+        
+        let material_type_info = MaterialTypeInfo {
+            name: legacy.name,
+            description: legacy.description,
+            material_type: MaterialCategory::from_code(legacy.material_type)
+                .ok_or(MaterialError::UnrecognizedMaterialType)?,
+            rarity: Rarity::from_level(legacy.rarity).ok_or(MaterialError::InvalidRarity)?,
+            uri: legacy.uri,
+            authority: legacy.authority,
+            enabled: legacy.enabled,
+            created_at: legacy.created_at,
+            attributes: legacy.attributes,
+            success_rate_bonus: legacy.success_rate_bonus,
+            discovery_difficulty: legacy.discovery_difficulty,
+            season_id: legacy.season_id,
+            expires_at: legacy.expires_at,
+        };
+        
+        // Same size, so the typed layout is written in place
+        let mut data = account_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        material_type_info.try_serialize(&mut writer)?;
+        
+        let migration_receipt = &mut ctx.accounts.migration_receipt;
+        migration_receipt.account = account_info.key();
+        migration_receipt.migrated_at = Clock::get()?.unix_timestamp;
+        migration_receipt.bump = ctx.bumps.migration_receipt;
+        
+        Ok(())
+    }
+    
+    // Convert a material instance stored with 1-based u8 category and rarity codes
+    pub fn migrate_material_instance(
+        ctx: Context<MigrateMaterialInstance>,
+    ) -> Result<()> {
+        let account_info = ctx.accounts.material_instance.to_account_info();
+        
+        let legacy = {
+            let data = account_info.try_borrow_data()?;
+            require!(
+                data.len() > 8 && data[..8] == MaterialInstance::discriminator(),
+                MaterialError::InvalidMigrationAccount
+            );
+            LegacyMaterialInstance::deserialize(&mut &data[8..])
+                .map_err(|_| error!(MaterialError::InvalidMigrationAccount))?
+        };
+        
+        // Both layouts have the same size, so only the material type's authority may say which
+        // one an instance is in. Category and rarity are single bytes in either type layout, so
+        // the legacy reader finds the type's authority whether or not the type was migrated
+        let type_info = ctx.accounts.material_type_info.to_account_info();
+        require!(
+            type_info.key() == legacy.material_type,
+            MaterialError::IncompatibleMaterials
+        );
+        let type_authority = {
+            let data = type_info.try_borrow_data()?;
+            require!(
+                data.len() > 8 && data[..8] == MaterialTypeInfo::discriminator(),
+                MaterialError::InvalidMigrationAccount
+            );
+            LegacyMaterialTypeInfo::deserialize(&mut &data[8..])
+                .map_err(|_| error!(MaterialError::InvalidMigrationAccount))?
+                .authority
+        };
+        require!(
+            type_authority == ctx.accounts.authority.key(),
+            MaterialError::InvalidOwner
+        );
+        
+        let material_instance = MaterialInstance {
+            material_type: legacy.material_type,
+            owner: legacy.owner,
+            amount: legacy.amount,
+            created_at: legacy.created_at,
+            rarity: Rarity::from_level(legacy.rarity).ok_or(MaterialError::InvalidRarity)?,
+            material_type_enum: MaterialCategory::from_code(legacy.material_type_enum)
+                .ok_or(MaterialError::UnrecognizedMaterialType)?,
+        };
+        
+        // Same size, so the typed layout is written in place
+        let mut data = account_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        material_instance.try_serialize(&mut writer)?;
+        
+        let migration_receipt = &mut ctx.accounts.migration_receipt;
+        migration_receipt.account = account_info.key();
+        migration_receipt.migrated_at = Clock::get()?.unix_timestamp;
+        migration_receipt.bump = ctx.bumps.migration_receipt;
+        
+        Ok(())
+    }
+    
     // Initialize a new season (admin only)
     pub fn initialize_season(
         ctx: Context<InitializeSeason>,
@@ -262,6 +669,12 @@ pub mod magic_vial_material {
         description: String,
         duration_days: u16,
     ) -> Result<()> {
+        require!(name.len() <= Season::MAX_NAME_LEN, MaterialError::NameTooLong);
+        require!(
+            description.len() <= Season::MAX_DESCRIPTION_LEN,
+            MaterialError::DescriptionTooLong
+        );
+        
         let season = &mut ctx.accounts.season;
         season.season_id = season_id;
         season.name = name;
@@ -269,17 +682,152 @@ pub mod magic_vial_material {
         season.start_time = Clock::get()?.unix_timestamp;
         season.end_time = Clock::get()?.unix_timestamp + (duration_days as i64 * 86400);
         season.authority = ctx.accounts.authority.key();
+        season.closed = false;
+        season.essence_material_type = None;
+        season.essence_per_unit = 0;
+        season.bump = ctx.bumps.season;
+        
+        Ok(())
+    }
+    
+    // Push back the end of a season that has not been closed
+    pub fn extend_season(
+        ctx: Context<ManageSeason>,
+        additional_days: u16,
+    ) -> Result<()> {
+        let season = &mut ctx.accounts.season;
+        require!(!season.closed, MaterialError::SeasonOver);
+        
+        season.end_time = season.end_time
+            .checked_add(additional_days as i64 * 86400)
+            .ok_or(MaterialError::ArithmeticOverflow)?;
+        
+        Ok(())
+    }
+    
+    // End a season now; its materials expire and can be sunset into essence
+    pub fn close_season(
+        ctx: Context<ManageSeason>,
+    ) -> Result<()> {
+        let season = &mut ctx.accounts.season;
+        require!(!season.closed, MaterialError::SeasonOver);
+        
+        season.end_time = season.end_time.min(Clock::get()?.unix_timestamp);
+        season.closed = true;
+        
+        Ok(())
+    }
+    
+    // Choose the material expired seasonal materials turn into, and how much of it per unit
+    pub fn set_season_essence(
+        ctx: Context<SetSeasonEssence>,
+        essence_per_unit: u64,
+    ) -> Result<()> {
+        let essence_type = &ctx.accounts.essence_material_type;
+        require!(essence_type.season_id.is_none(), MaterialError::SeasonalEssence);
+        require!(essence_per_unit > 0, MaterialError::InvalidEssenceRate);
+        
+        let season = &mut ctx.accounts.season;
+        season.essence_material_type = Some(essence_type.key());
+        season.essence_per_unit = essence_per_unit;
+        
+        Ok(())
+    }
+    
+    // Convert an expired seasonal material into the season's essence material
+    pub fn sunset_material(
+        ctx: Context<SunsetMaterial>,
+    ) -> Result<()> {
+        let season = &ctx.accounts.season;
+        let material_instance = &ctx.accounts.material_instance;
+        
+        // Only materials whose season has ended can be sunset
+        require!(
+            ctx.accounts.material_type_info.season_id == Some(season.season_id),
+            MaterialError::SeasonMismatch
+        );
+        require!(
+            season.is_over(Clock::get()?.unix_timestamp),
+            MaterialError::SeasonNotOver
+        );
+        require!(
+            season.essence_material_type == Some(ctx.accounts.essence_material_type.key()),
+            MaterialError::EssenceNotConfigured
+        );
+        require!(ctx.accounts.essence_material_type.enabled, MaterialError::MaterialDisabled);
+        
+        let essence_amount = material_instance.amount
+            .checked_mul(season.essence_per_unit)
+            .ok_or(MaterialError::ArithmeticOverflow)?;
+        
+        // Burn the expired material's token; its instance account is closed to the owner
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.owner_token.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        
+        let essence_type = &ctx.accounts.essence_material_type;
+        let essence_instance = &mut ctx.accounts.essence_instance;
+        essence_instance.material_type = essence_type.key();
+        essence_instance.owner = ctx.accounts.owner.key();
+        essence_instance.amount = essence_amount;
+        essence_instance.created_at = Clock::get()?.unix_timestamp;
+        essence_instance.rarity = essence_type.rarity;
+        essence_instance.material_type_enum = essence_type.material_type;
+        
+        // Same NFT representation as mint_material
+        token::mint_to(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.essence_mint.to_account_info(),
+                    to: ctx.accounts.essence_token.to_account_info(),
+                    authority: ctx.accounts.essence_mint.to_account_info(),
+                },
+            ),
+            1,
+        )?;
         
         Ok(())
     }
 }
 
+// Helper function to check a material type can still be minted or discovered
+fn check_material_window(
+    material_type_info: &MaterialTypeInfo,
+    season: Option<&Season>,
+    now: i64,
+) -> Result<()> {
+    match material_type_info.season_id {
+        // The season account is authoritative, since it can be extended or closed
+        Some(season_id) => {
+            let season = season.ok_or(MaterialError::MissingSeason)?;
+            require!(season.season_id == season_id, MaterialError::SeasonMismatch);
+            require!(season.is_open(now), MaterialError::MaterialExpired);
+        }
+        None => {
+            if let Some(expires_at) = material_type_info.expires_at {
+                require!(now < expires_at, MaterialError::MaterialExpired);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
 // Helper function for discovery chance
-fn calculate_discovery_chance(explorer_level: u8, difficulty: u8, rarity: u8) -> u8 {
+fn calculate_discovery_chance(explorer_level: u8, difficulty: u8, rarity: Rarity) -> u8 {
     let base_chance = 50; // 50% base chance
     let level_bonus = explorer_level.saturating_mul(2); // 2% per level
     let difficulty_penalty = difficulty.saturating_div(4); // 0.25% penalty per difficulty point
-    let rarity_penalty = rarity.saturating_mul(10); // 10% penalty per rarity level
+    let rarity_penalty = rarity.level().saturating_mul(10); // 10% penalty per rarity level
     
     base_chance.saturating_add(level_bonus)
                .saturating_sub(difficulty_penalty)
@@ -287,25 +835,23 @@ fn calculate_discovery_chance(explorer_level: u8, difficulty: u8, rarity: u8) ->
                .min(95) // Cap at 95% chance
 }
 
-// Helper function for discovery success determination
-fn determine_discovery_success(chance: u8, blockhash: Pubkey, explorer: Pubkey, timestamp: i64) -> bool {
-    // Use blockhash, explorer, and timestamp to generate pseudo-randomness
-    // In production, would use a verifiable random function
-    let mut data = [0u8; 32];
-    let blockhash_bytes = blockhash.to_bytes();
-    let explorer_bytes = explorer.to_bytes();
-    let timestamp_bytes = timestamp.to_le_bytes();
-    
-    for i in 0..32 {
-        data[i] = blockhash_bytes[i] ^ 
-                 explorer_bytes[i % 32] ^ 
-                 (if i < 8 { timestamp_bytes[i] } else { 0 });
-    }
+// Helper function to validate the terms of a synthesis table entry
+fn validate_synthesis_terms(input_count: u64, output_amount: u64, success_chance: u8) -> Result<()> {
+    require!(input_count > 0 && output_amount > 0, MaterialError::InvalidSynthesisAmount);
+    require!(
+        success_chance > 0 && success_chance <= 100,
+        MaterialError::InvalidSynthesisChance
+    );
     
-    // Use first byte as percentage (0-255)
-    let roll = data[0] as u16 * 100 / 255;
+    Ok(())
+}
+
+// Helper function for discovery success determination
+fn determine_discovery_success(chance: u8, randomness: &[u8; 32]) -> bool {
+    // Roll 0-99 against the discovery chance
+    let roll = randomness::roll_percent(randomness, b"material_discovery");
     
-    roll < chance as u16
+    roll < chance
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
@@ -324,12 +870,20 @@ pub struct MaterialAttribute {
     pub value: u8,
 }
 
+impl MaterialAttribute {
+    pub const MAX_NAME_LEN: usize = 32;
+    
+    pub const LEN: usize = 4 + Self::MAX_NAME_LEN + // name
+                           1 +  // effect enum
+                           1;   // value
+}
+
 #[account]
 pub struct MaterialTypeInfo {
     pub name: String,           // Material name
     pub description: String,    // Material description
-    pub material_type: u8,      // Material type category
-    pub rarity: u8,             // Rarity score (1-5)
+    pub material_type: MaterialCategory, // Material type category
+    pub rarity: Rarity,         // Rarity tier
     pub uri: String,            // Metadata URI
     pub authority: Pubkey,      // Authority that can modify this type
     pub enabled: bool,          // Whether this material is still available
@@ -338,7 +892,29 @@ pub struct MaterialTypeInfo {
     pub success_rate_bonus: u8, // Bonus to crafting success (0-50%)
     pub discovery_difficulty: u8, // How hard to discover (1-100)
     pub season_id: Option<u8>,  // Season this material belongs to (if seasonal)
-    pub expires_at: Option<i64>, // When this material expires (for seasonal)
+    pub expires_at: Option<i64>, // Season end when created; the Season account is authoritative
+}
+
+impl MaterialTypeInfo {
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_DESCRIPTION_LEN: usize = 256;
+    pub const MAX_URI_LEN: usize = 200;
+    pub const MAX_ATTRIBUTES: usize = 8;
+    pub const MAX_SUCCESS_RATE_BONUS: u8 = 50;
+    
+    pub const LEN: usize = 4 + Self::MAX_NAME_LEN + // name
+                           4 + Self::MAX_DESCRIPTION_LEN + // description
+                           MaterialCategory::LEN + // material_type
+                           Rarity::LEN + // rarity
+                           4 + Self::MAX_URI_LEN + // uri
+                           32 + // authority
+                           1 +  // enabled
+                           8 +  // created_at
+                           4 + Self::MAX_ATTRIBUTES * MaterialAttribute::LEN + // attributes
+                           1 +  // success_rate_bonus
+                           1 +  // discovery_difficulty
+                           2 +  // season_id (Option<u8>)
+                           9;   // expires_at (Option<i64>)
 }
 
 #[account]
@@ -347,8 +923,17 @@ pub struct MaterialInstance {
     pub owner: Pubkey,          // Current owner
     pub amount: u64,            // Quantity of this material
     pub created_at: i64,        // Creation timestamp
-    pub rarity: u8,             // Cached rarity from material type
-    pub material_type_enum: u8, // Cached material type from material type
+    pub rarity: Rarity,         // Cached rarity from material type
+    pub material_type_enum: MaterialCategory, // Cached material type from material type
+}
+
+impl MaterialInstance {
+    pub const LEN: usize = 32 + // material_type
+                           32 + // owner
+                           8 +  // amount
+                           8 +  // created_at
+                           Rarity::LEN + // rarity
+                           MaterialCategory::LEN; // material_type_enum
 }
 
 #[account]
@@ -359,35 +944,269 @@ pub struct Season {
     pub start_time: i64,        // Season start time
     pub end_time: i64,          // Season end time
     pub authority: Pubkey,      // Admin authority
+    pub closed: bool,           // Whether the season was ended by its authority
+    pub essence_material_type: Option<Pubkey>, // What expired materials convert into
+    pub essence_per_unit: u64,  // Essence granted per unit of expired material
+    pub bump: u8,
+}
+
+impl Season {
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_DESCRIPTION_LEN: usize = 256;
+    
+    pub const LEN: usize = 1 +  // season_id
+                           4 + Self::MAX_NAME_LEN + // name
+                           4 + Self::MAX_DESCRIPTION_LEN + // description
+                           8 +  // start_time
+                           8 +  // end_time
+                           32 + // authority
+                           1 +  // closed
+                           33 + // essence_material_type (Option<Pubkey>)
+                           8 +  // essence_per_unit
+                           1;   // bump
+    
+    pub fn is_open(&self, now: i64) -> bool {
+        !self.closed && self.start_time <= now && now < self.end_time
+    }
+    
+    pub fn is_over(&self, now: i64) -> bool {
+        self.closed || now >= self.end_time
+    }
+}
+
+#[account]
+pub struct MigrationReceipt {
+    pub account: Pubkey,        // Account that was migrated
+    pub migrated_at: i64,       // Migration timestamp
+    pub bump: u8,
+}
+
+impl MigrationReceipt {
+    pub const LEN: usize = 32 + // account
+                           8 +  // migrated_at
+                           1;   // bump
+}
+
+// Material type layout before category and rarity became shared enums
+#[derive(AnchorDeserialize)]
+struct LegacyMaterialTypeInfo {
+    name: String,
+    description: String,
+    material_type: u8,
+    rarity: u8,
+    uri: String,
+    authority: Pubkey,
+    enabled: bool,
+    created_at: i64,
+    attributes: Vec<MaterialAttribute>,
+    success_rate_bonus: u8,
+    discovery_difficulty: u8,
+    season_id: Option<u8>,
+    expires_at: Option<i64>,
+}
+
+// Material instance layout before category and rarity became shared enums
+#[derive(AnchorDeserialize)]
+struct LegacyMaterialInstance {
+    material_type: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+    created_at: i64,
+    rarity: u8,
+    material_type_enum: u8,
 }
 
 #[account]
 pub struct DiscoveryRecord {
     pub explorer: Pubkey,       // Who discovered the material
-    pub material_type: Pubkey,  // What was discovered
+    pub material_type: Pubkey,  // What was discovered (set when resolved)
     pub timestamp: i64,         // When it was discovered
-    pub area_id: u8,            // Where it was discovered
+    pub area: Pubkey,           // Exploration area it was discovered in
+    pub explorer_level: u8,     // Explorer's crafting level at the time
+    pub discovery_chance: u8,   // Chance rolled against (0-95)
+    pub amount: u64,            // Units minted on success
+    pub randomness_request: Pubkey, // Randomness request bound to this attempt
+    pub resolved: bool,         // Whether the outcome has been rolled
+    pub success: bool,          // Whether the discovery succeeded
+    pub claimed: bool,          // Whether the material has been minted
 }
 
-#[derive(Accounts)]
-pub struct InitializeMaterialType<'info> {
-    #[account(init, payer = authority, space = 8 + 1024)]
-    pub material_type_info: Account<'info, MaterialTypeInfo>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+impl DiscoveryRecord {
+    pub const LEN: usize = 32 + // explorer
+                           32 + // material_type
+                           8 +  // timestamp
+                           32 + // area
+                           1 +  // explorer_level
+                           1 +  // discovery_chance
+                           8 +  // amount
+                           32 + // randomness_request
+                           1 +  // resolved
+                           1 +  // success
+                           1;   // claimed
 }
 
-#[derive(Accounts)]
-pub struct MintMaterial<'info> {
-    #[account(init, payer = authority, space = 8 + 128)]
-    pub material_instance: Account<'info, MaterialInstance>,
-    
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AreaDrop {
+    pub material_type: Pubkey,  // Material type that can drop
+    pub weight: u16,            // Relative chance of being rolled
+    pub amount: u64,            // Units found on success
+    pub discovery_difficulty: u8, // Copied from the material type
+    pub rarity: Rarity,         // Copied from the material type
+}
+
+impl AreaDrop {
+    pub const LEN: usize = 32 + // material_type
+                           2 +  // weight
+                           8 +  // amount
+                           1 +  // discovery_difficulty
+                           Rarity::LEN; // rarity
+}
+
+#[account]
+pub struct ExplorationArea {
+    pub area_id: u8,            // Unique area identifier
+    pub name: String,           // Area name
+    pub authority: Pubkey,      // Authority that manages this area
+    pub drops: Vec<AreaDrop>,   // Materials that can be found here
+    pub min_level: u8,          // Crafting level needed to explore
+    pub cooldown_seconds: i64,  // Wait after an explorer's last exploration
+    pub stamina_cost: u8,       // Stamina spent per exploration
+    pub enabled: bool,          // Whether the area can be explored
+    pub bump: u8,
+}
+
+impl ExplorationArea {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const MAX_DROPS: usize = 8;
+    
+    pub const LEN: usize = 1 +  // area_id
+                           4 + Self::MAX_NAME_LEN + // name
+                           32 + // authority
+                           4 + Self::MAX_DROPS * AreaDrop::LEN + // drops
+                           1 +  // min_level
+                           8 +  // cooldown_seconds
+                           1 +  // stamina_cost
+                           1 +  // enabled
+                           1;   // bump
+}
+
+#[account]
+pub struct ExplorerState {
+    pub explorer: Pubkey,       // Explorer's address
+    pub stamina: u8,            // Stamina as of stamina_updated_at
+    pub stamina_updated_at: i64, // Last time stamina was regenerated
+    pub last_explored_at: i64,  // Last exploration, for area cooldowns
+    pub discovery_attempts: u64, // Total explorations
+    pub bump: u8,
+}
+
+impl ExplorerState {
+    pub const MAX_STAMINA: u8 = 100;
+    pub const STAMINA_REGEN_SECONDS: i64 = 360; // 1 stamina every 6 minutes
+    
+    pub const LEN: usize = 32 + // explorer
+                           1 +  // stamina
+                           8 +  // stamina_updated_at
+                           8 +  // last_explored_at
+                           8 +  // discovery_attempts
+                           1;   // bump
+    
+    pub fn regenerate_stamina(&mut self, now: i64) {
+        let regenerated = (now - self.stamina_updated_at).max(0) / Self::STAMINA_REGEN_SECONDS;
+        let stamina = (self.stamina as i64 + regenerated).min(Self::MAX_STAMINA as i64);
+        self.stamina = stamina as u8;
+        self.stamina_updated_at += regenerated * Self::STAMINA_REGEN_SECONDS;
+        if self.stamina == Self::MAX_STAMINA {
+            self.stamina_updated_at = now;
+        }
+    }
+}
+
+#[account]
+pub struct SynthesisRecipe {
+    pub input_material_type: Pubkey, // Material type consumed
+    pub input_rarity: Rarity,   // Rarity the inputs must have
+    pub output_material_type: Pubkey, // Material type produced
+    pub input_count: u64,       // Units consumed from each of the two inputs
+    pub output_amount: u64,     // Units of the output minted on success
+    pub success_chance: u8,     // Chance of success (1-100)
+    pub authority: Pubkey,      // Authority that can update this entry
+    pub enabled: bool,          // Whether this entry can be used
+    pub bump: u8,
+}
+
+impl SynthesisRecipe {
+    pub const LEN: usize = 32 + // input_material_type
+                           Rarity::LEN + // input_rarity
+                           32 + // output_material_type
+                           8 +  // input_count
+                           8 +  // output_amount
+                           1 +  // success_chance
+                           32 + // authority
+                           1 +  // enabled
+                           1;   // bump
+}
+
+#[account]
+pub struct SynthesisAttempt {
+    pub crafter: Pubkey,        // Who attempted the synthesis
+    pub synthesis_recipe: Pubkey, // Synthesis table entry used
+    pub output_material_type: Pubkey, // Material type minted on success
+    pub output_amount: u64,     // Units minted on success
+    pub success_chance: u8,     // Chance rolled against (1-100)
+    pub randomness_request: Pubkey, // Randomness request bound to this attempt
+    pub timestamp: i64,         // When the inputs were consumed
+    pub resolved: bool,         // Whether the outcome has been rolled
+    pub success: bool,          // Whether the synthesis succeeded
+    pub claimed: bool,          // Whether the output has been minted
+}
+
+impl SynthesisAttempt {
+    pub const LEN: usize = 32 + // crafter
+                           32 + // synthesis_recipe
+                           32 + // output_material_type
+                           8 +  // output_amount
+                           1 +  // success_chance
+                           32 + // randomness_request
+                           8 +  // timestamp
+                           1 +  // resolved
+                           1 +  // success
+                           1;   // claimed
+}
+
+#[derive(Accounts)]
+pub struct InitializeMaterialType<'info> {
+    #[account(init, payer = authority, space = 8 + MaterialTypeInfo::LEN)]
+    pub material_type_info: Account<'info, MaterialTypeInfo>,
+    
+    // Required for seasonal materials
+    #[account(
+        seeds = [b"season".as_ref(), &[season.season_id]],
+        bump = season.bump
+    )]
+    pub season: Option<Account<'info, Season>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MintMaterial<'info> {
+    #[account(init, payer = authority, space = 8 + MaterialInstance::LEN)]
+    pub material_instance: Account<'info, MaterialInstance>,
+    
     pub material_type_info: Account<'info, MaterialTypeInfo>,
     
+    // Required for seasonal materials
+    #[account(
+        seeds = [b"season".as_ref(), &[season.season_id]],
+        bump = season.bump
+    )]
+    pub season: Option<Account<'info, Season>>,
+    
     #[account(
         init,
         payer = authority,
@@ -414,22 +1233,170 @@ pub struct MintMaterial<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+#[instruction(area_id: u8)]
+pub struct CreateExplorationArea<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ExplorationArea::LEN,
+        seeds = [b"area".as_ref(), &[area_id]],
+        bump
+    )]
+    pub exploration_area: Account<'info, ExplorationArea>,
+    
+    // Area ids are global, so only the platform authority may claim one
+    #[account(
+        seeds = [b"crafting_config".as_ref()],
+        bump = crafting_config.bump,
+        seeds::program = crate::crafting::ID
+    )]
+    pub crafting_config: Account<'info, CraftingConfig>,
+    
+    #[account(mut, constraint = authority.key() == crafting_config.authority @ MaterialError::InvalidOwner)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateExplorationArea<'info> {
+    #[account(
+        mut,
+        seeds = [b"area".as_ref(), &[exploration_area.area_id]],
+        bump = exploration_area.bump,
+        has_one = authority @ MaterialError::InvalidOwner,
+    )]
+    pub exploration_area: Account<'info, ExplorationArea>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAreaDrop<'info> {
+    #[account(
+        mut,
+        seeds = [b"area".as_ref(), &[exploration_area.area_id]],
+        bump = exploration_area.bump,
+        has_one = authority @ MaterialError::InvalidOwner,
+    )]
+    pub exploration_area: Account<'info, ExplorationArea>,
+    
+    // Only the material's own authority can place it in an area
+    #[account(has_one = authority @ MaterialError::InvalidOwner)]
+    pub material_type_info: Account<'info, MaterialTypeInfo>,
+    
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct DiscoverMaterial<'info> {
-    #[account(init, payer = explorer, space = 8 + 128)]
+    #[account(init, payer = explorer, space = 8 + DiscoveryRecord::LEN)]
     pub discovery_record: Account<'info, DiscoveryRecord>,
     
-    pub material_type_info: Account<'info, MaterialTypeInfo>,
+    #[account(
+        seeds = [b"area".as_ref(), &[exploration_area.area_id]],
+        bump = exploration_area.bump
+    )]
+    pub exploration_area: Account<'info, ExplorationArea>,
+    
+    #[account(
+        init_if_needed,
+        payer = explorer,
+        space = 8 + ExplorerState::LEN,
+        seeds = [b"explorer".as_ref(), explorer.key().as_ref()],
+        bump
+    )]
+    pub explorer_state: Account<'info, ExplorerState>,
+    
+    #[account(
+        seeds = [b"crafter-stats", explorer.key().as_ref()],
+        bump,
+        seeds::program = crate::crafting::ID
+    )]
+    pub crafter_stats: Account<'info, CrafterStats>,
     
     #[account(mut)]
     pub explorer: Signer<'info>,
     
-    pub recent_blockhash: AccountInfo<'info>,
+    // Must be requested for this record before the oracle answers
+    #[account(
+        constraint = randomness_request.requester == explorer.key() @ MaterialError::InvalidRandomnessRequest,
+        constraint = randomness_request.consumer == discovery_record.key() @ MaterialError::InvalidRandomnessRequest,
+        constraint = randomness_request.status == RequestStatus::Pending @ MaterialError::RandomnessAlreadyFulfilled,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
     
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct ResolveDiscovery<'info> {
+    #[account(
+        mut,
+        has_one = explorer @ MaterialError::InvalidOwner,
+        has_one = randomness_request @ MaterialError::InvalidRandomnessRequest,
+        constraint = discovery_record.area == exploration_area.key() @ MaterialError::InvalidArea,
+        constraint = !discovery_record.resolved @ MaterialError::AlreadyResolved,
+    )]
+    pub discovery_record: Account<'info, DiscoveryRecord>,
+    
+    pub exploration_area: Account<'info, ExplorationArea>,
+    
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
+    #[account(mut)]
+    pub explorer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDiscovery<'info> {
+    #[account(
+        mut,
+        has_one = explorer @ MaterialError::InvalidOwner,
+        constraint = discovery_record.material_type == material_type_info.key() @ MaterialError::IncompatibleMaterials,
+        constraint = discovery_record.resolved && discovery_record.success @ MaterialError::DiscoveryFailed,
+        constraint = !discovery_record.claimed @ MaterialError::AlreadyClaimed,
+    )]
+    pub discovery_record: Account<'info, DiscoveryRecord>,
+    
+    pub material_type_info: Account<'info, MaterialTypeInfo>,
+    
+    // Required for seasonal materials
+    #[account(
+        seeds = [b"season".as_ref(), &[season.season_id]],
+        bump = season.bump
+    )]
+    pub season: Option<Account<'info, Season>>,
+    
+    #[account(init, payer = explorer, space = 8 + MaterialInstance::LEN)]
+    pub material_instance: Account<'info, MaterialInstance>,
+    
+    #[account(
+        init,
+        payer = explorer,
+        mint::decimals = 0,
+        mint::authority = mint,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = explorer,
+        token::mint = mint,
+        token::authority = explorer,
+    )]
+    pub explorer_token: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub explorer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
 #[derive(Accounts)]
 pub struct TransferMaterial<'info> {
     #[account(mut, has_one = owner @ MaterialError::InvalidOwner)]
@@ -470,50 +1437,331 @@ pub struct ConsumeMaterial<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct CreateSynthesisRecipe<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SynthesisRecipe::LEN,
+        seeds = [
+            b"synthesis".as_ref(),
+            input_material_type.key().as_ref(),
+            &[input_material_type.rarity.level()],
+        ],
+        bump
+    )]
+    pub synthesis_recipe: Account<'info, SynthesisRecipe>,
+    
+    #[account(has_one = authority @ MaterialError::InvalidOwner)]
+    pub input_material_type: Account<'info, MaterialTypeInfo>,
+    
+    // Synthesis mints the output, so it must be the same authority's material too
+    #[account(has_one = authority @ MaterialError::InvalidOwner)]
+    pub output_material_type: Account<'info, MaterialTypeInfo>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSynthesisRecipe<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"synthesis".as_ref(),
+            synthesis_recipe.input_material_type.as_ref(),
+            &[synthesis_recipe.input_rarity.level()],
+        ],
+        bump = synthesis_recipe.bump,
+        has_one = authority @ MaterialError::InvalidOwner,
+    )]
+    pub synthesis_recipe: Account<'info, SynthesisRecipe>,
+    
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct SynthesizeMaterials<'info> {
-    #[account(mut, has_one = owner @ MaterialError::InvalidOwner)]
+    #[account(
+        seeds = [
+            b"synthesis".as_ref(),
+            synthesis_recipe.input_material_type.as_ref(),
+            &[synthesis_recipe.input_rarity.level()],
+        ],
+        bump = synthesis_recipe.bump
+    )]
+    pub synthesis_recipe: Account<'info, SynthesisRecipe>,
+    
+    #[account(
+        mut,
+        constraint = material_instance_1.owner == crafter.key() @ MaterialError::InvalidOwner,
+    )]
     pub material_instance_1: Account<'info, MaterialInstance>,
     
-    #[account(mut, has_one = owner @ MaterialError::InvalidOwner)]
+    #[account(
+        mut,
+        constraint = material_instance_2.owner == crafter.key() @ MaterialError::InvalidOwner,
+        constraint = material_instance_2.key() != material_instance_1.key() @ MaterialError::IncompatibleMaterials,
+    )]
     pub material_instance_2: Account<'info, MaterialInstance>,
     
-    pub material_type_info: Account<'info, MaterialTypeInfo>,
-    
     #[account(mut)]
     pub material_instance_1_mint: Account<'info, Mint>,
     
     #[account(mut)]
     pub material_instance_2_mint: Account<'info, Mint>,
     
-    #[account(mut, has_one = mint)]
+    #[account(
+        mut,
+        token::mint = material_instance_1_mint,
+        token::authority = crafter,
+    )]
     pub material_instance_1_token: Account<'info, TokenAccount>,
     
-    #[account(mut, has_one = mint)]
+    #[account(
+        mut,
+        token::mint = material_instance_2_mint,
+        token::authority = crafter,
+    )]
     pub material_instance_2_token: Account<'info, TokenAccount>,
     
+    #[account(init, payer = crafter, space = 8 + SynthesisAttempt::LEN)]
+    pub synthesis_attempt: Account<'info, SynthesisAttempt>,
+    
+    // Must be requested for this attempt before the oracle answers
+    #[account(
+        constraint = randomness_request.requester == crafter.key() @ MaterialError::InvalidRandomnessRequest,
+        constraint = randomness_request.consumer == synthesis_attempt.key() @ MaterialError::InvalidRandomnessRequest,
+        constraint = randomness_request.status == RequestStatus::Pending @ MaterialError::RandomnessAlreadyFulfilled,
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
     #[account(mut)]
     pub crafter: Signer<'info>,
     
-    #[account(constraint = crafter.key() == material_instance_1.owner && 
-                        crafter.key() == material_instance_2.owner)]
-    pub owner: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveSynthesis<'info> {
+    #[account(
+        mut,
+        has_one = crafter @ MaterialError::InvalidOwner,
+        has_one = randomness_request @ MaterialError::InvalidRandomnessRequest,
+        constraint = !synthesis_attempt.resolved @ MaterialError::AlreadyResolved,
+    )]
+    pub synthesis_attempt: Account<'info, SynthesisAttempt>,
+    
+    pub randomness_request: Account<'info, RandomnessRequest>,
+    
+    pub crafter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSynthesisOutput<'info> {
+    #[account(
+        mut,
+        has_one = crafter @ MaterialError::InvalidOwner,
+        constraint = synthesis_attempt.output_material_type == output_material_type.key() @ MaterialError::InvalidSynthesisOutput,
+        constraint = synthesis_attempt.resolved && synthesis_attempt.success @ MaterialError::SynthesisFailed,
+        constraint = !synthesis_attempt.claimed @ MaterialError::AlreadyClaimed,
+    )]
+    pub synthesis_attempt: Account<'info, SynthesisAttempt>,
+    
+    pub output_material_type: Account<'info, MaterialTypeInfo>,
+    
+    #[account(init, payer = crafter, space = 8 + MaterialInstance::LEN)]
+    pub material_instance: Account<'info, MaterialInstance>,
+    
+    #[account(
+        init,
+        payer = crafter,
+        mint::decimals = 0,
+        mint::authority = mint,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = crafter,
+        token::mint = mint,
+        token::authority = crafter,
+    )]
+    pub crafter_token: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub crafter: Signer<'info>,
     
+    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMaterialType<'info> {
+    /// CHECK: Still in the legacy layout, so it is parsed by hand in the instruction
+    #[account(mut, owner = crate::ID)]
+    pub material_type_info: UncheckedAccount<'info>,
+    
+    // Each account can only be migrated once
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MigrationReceipt::LEN,
+        seeds = [b"migration", material_type_info.key().as_ref()],
+        bump
+    )]
+    pub migration_receipt: Account<'info, MigrationReceipt>,
+    
+    // The material type's authority
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMaterialInstance<'info> {
+    /// CHECK: Still in the legacy layout, so it is parsed by hand in the instruction
+    #[account(mut, owner = crate::ID)]
+    pub material_instance: UncheckedAccount<'info>,
+    
+    /// CHECK: The instance's material type, which may itself still be in the legacy layout
+    #[account(owner = crate::ID)]
+    pub material_type_info: UncheckedAccount<'info>,
+    
+    // Each account can only be migrated once
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MigrationReceipt::LEN,
+        seeds = [b"migration", material_instance.key().as_ref()],
+        bump
+    )]
+    pub migration_receipt: Account<'info, MigrationReceipt>,
+    
+    // The authority of the instance's material type
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
+#[instruction(season_id: u8)]
 pub struct InitializeSeason<'info> {
-    #[account(init, payer = authority, space = 8 + 512)]
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Season::LEN,
+        seeds = [b"season".as_ref(), &[season_id]],
+        bump
+    )]
     pub season: Account<'info, Season>,
     
-    #[account(mut)]
+    // Season ids are global, so only the platform authority may claim one
+    #[account(
+        seeds = [b"crafting_config".as_ref()],
+        bump = crafting_config.bump,
+        seeds::program = crate::crafting::ID
+    )]
+    pub crafting_config: Account<'info, CraftingConfig>,
+    
+    #[account(mut, constraint = authority.key() == crafting_config.authority @ MaterialError::InvalidOwner)]
     pub authority: Signer<'info>,
     
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct ManageSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"season".as_ref(), &[season.season_id]],
+        bump = season.bump,
+        has_one = authority @ MaterialError::InvalidOwner,
+    )]
+    pub season: Account<'info, Season>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSeasonEssence<'info> {
+    #[account(
+        mut,
+        seeds = [b"season".as_ref(), &[season.season_id]],
+        bump = season.bump,
+        has_one = authority @ MaterialError::InvalidOwner,
+    )]
+    pub season: Account<'info, Season>,
+    
+    pub essence_material_type: Account<'info, MaterialTypeInfo>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SunsetMaterial<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ MaterialError::InvalidOwner,
+        constraint = material_instance.material_type == material_type_info.key() @ MaterialError::IncompatibleMaterials,
+    )]
+    pub material_instance: Account<'info, MaterialInstance>,
+    
+    pub material_type_info: Account<'info, MaterialTypeInfo>,
+    
+    #[account(
+        seeds = [b"season".as_ref(), &[season.season_id]],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut, has_one = mint, token::authority = owner)]
+    pub owner_token: Account<'info, TokenAccount>,
+    
+    pub essence_material_type: Account<'info, MaterialTypeInfo>,
+    
+    #[account(init, payer = owner, space = 8 + MaterialInstance::LEN)]
+    pub essence_instance: Account<'info, MaterialInstance>,
+    
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = essence_mint,
+    )]
+    pub essence_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = owner,
+        token::mint = essence_mint,
+        token::authority = owner,
+    )]
+    pub essence_token: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
 #[error_code]
 pub enum MaterialError {
     #[msg("Insufficient material amount")]
@@ -525,18 +1773,90 @@ pub enum MaterialError {
     #[msg("Material type has expired")]
     MaterialExpired,
     
+    #[msg("Seasonal materials need their season account")]
+    MissingSeason,
+    
+    #[msg("Season does not match the material's season")]
+    SeasonMismatch,
+    
+    #[msg("Season has already ended")]
+    SeasonOver,
+    
+    #[msg("Season has not ended yet")]
+    SeasonNotOver,
+    
+    #[msg("Season has no essence material, or a different one")]
+    EssenceNotConfigured,
+    
+    #[msg("Essence material cannot itself be seasonal")]
+    SeasonalEssence,
+    
+    #[msg("Essence per unit must be greater than zero")]
+    InvalidEssenceRate,
+    
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    
     #[msg("Material type is disabled")]
     MaterialDisabled,
     
     #[msg("Discovery attempt failed")]
     DiscoveryFailed,
     
+    #[msg("Exploration area is disabled")]
+    AreaDisabled,
+    
+    #[msg("Discovery record belongs to a different area")]
+    InvalidArea,
+    
+    #[msg("Material is not in this area's drop table")]
+    AreaDropNotFound,
+    
+    #[msg("Area drop table is full")]
+    TooManyAreaDrops,
+    
+    #[msg("Area drop weight and amount must be greater than zero")]
+    InvalidAreaDrop,
+    
+    #[msg("Explorer level is too low for this area")]
+    ExplorerLevelTooLow,
+    
+    #[msg("Explorer is still on cooldown")]
+    ExplorationCooldown,
+    
+    #[msg("Not enough stamina to explore")]
+    InsufficientStamina,
+    
+    #[msg("Cooldown cannot be negative")]
+    InvalidCooldown,
+    
+    #[msg("Stamina cost exceeds maximum stamina")]
+    InvalidStaminaCost,
+    
     #[msg("Materials are incompatible for synthesis")]
     IncompatibleMaterials,
     
     #[msg("Material has reached maximum rarity level")]
     MaxRarityReached,
     
+    #[msg("Synthesis output must be of a higher rarity than its input")]
+    InvalidSynthesisOutput,
+    
+    #[msg("Synthesis input count and output amount must be greater than zero")]
+    InvalidSynthesisAmount,
+    
+    #[msg("Synthesis success chance must be between 1 and 100")]
+    InvalidSynthesisChance,
+    
+    #[msg("Synthesis entry is disabled")]
+    SynthesisDisabled,
+    
+    #[msg("Synthesis did not succeed")]
+    SynthesisFailed,
+    
+    #[msg("Synthesis output has already been claimed")]
+    AlreadyClaimed,
+    
     #[msg("Rarity must be between 1 and 5")]
     InvalidRarity,
     
@@ -545,4 +1865,31 @@ pub enum MaterialError {
     
     #[msg("Discovery difficulty must be between 1 and 100")]
     InvalidDiscoveryDifficulty,
+    
+    #[msg("Randomness request is not bound to this record")]
+    InvalidRandomnessRequest,
+    
+    #[msg("Randomness was fulfilled before the attempt started")]
+    RandomnessAlreadyFulfilled,
+    
+    #[msg("Attempt has already been resolved")]
+    AlreadyResolved,
+    
+    #[msg("Account is not in the legacy layout")]
+    InvalidMigrationAccount,
+    
+    #[msg("Legacy material type code is not a known category")]
+    UnrecognizedMaterialType,
+    
+    #[msg("Name exceeds the maximum length")]
+    NameTooLong,
+    
+    #[msg("Description exceeds the maximum length")]
+    DescriptionTooLong,
+    
+    #[msg("URI exceeds the maximum length")]
+    UriTooLong,
+    
+    #[msg("Too many material attributes")]
+    TooManyAttributes,
 } 
\ No newline at end of file
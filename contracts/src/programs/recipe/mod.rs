@@ -1,4 +1,7 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use crate::crafting::{grow_account, CrafterStats, CraftingConfig};
+use crate::material::MigrationReceipt;
 
 declare_id!("recXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
 
@@ -21,11 +24,30 @@ pub mod magic_vial_recipe {
         result_weights: Vec<u8>,
         is_secret: bool, // Whether this recipe is initially hidden (must be discovered)
         hints: Vec<String>, // Optional hints for discovery
+        ingredient_commitment: Option<IngredientCommitment>, // Required for secret recipes instead of materials
     ) -> Result<()> {
         require!(material_types.len() == material_amounts.len(), RecipeError::InputMismatch);
         require!(result_types.len() == result_weights.len(), RecipeError::OutputMismatch);
-        require!(!material_types.is_empty(), RecipeError::NoMaterials);
+        
+        // Secret recipes only store a commitment to their ingredients, never the ingredients
+        if is_secret {
+            require!(ingredient_commitment.is_some(), RecipeError::MissingCommitment);
+            require!(material_types.is_empty(), RecipeError::SecretIngredientsExposed);
+        } else {
+            require!(ingredient_commitment.is_none(), RecipeError::RecipeNotSecret);
+            require!(!material_types.is_empty(), RecipeError::NoMaterials);
+        }
         require!(!result_types.is_empty(), RecipeError::NoResults);
+        require!(material_types.len() <= Recipe::MAX_MATERIALS, RecipeError::TooManyMaterials);
+        require!(result_types.len() <= Recipe::MAX_RESULTS, RecipeError::TooManyResults);
+        require!(name.len() <= Recipe::MAX_NAME_LEN, RecipeError::NameTooLong);
+        require!(description.len() <= Recipe::MAX_DESCRIPTION_LEN, RecipeError::DescriptionTooLong);
+        require!(uri.len() <= Recipe::MAX_URI_LEN, RecipeError::UriTooLong);
+        require!(hints.len() <= Recipe::MAX_HINTS, RecipeError::TooManyHints);
+        require!(
+            hints.iter().all(|hint| hint.len() <= Recipe::MAX_HINT_LEN),
+            RecipeError::HintTooLong
+        );
 
         let recipe = &mut ctx.accounts.recipe;
         recipe.name = name;
@@ -42,73 +64,87 @@ pub mod magic_vial_recipe {
         recipe.result_types = result_types;
         recipe.result_weights = result_weights;
         recipe.is_secret = is_secret;
+        recipe.ingredient_commitment = ingredient_commitment;
         recipe.hints = hints;
-        recipe.discoverers = Vec::new();
+        recipe.discoverer_count = 0;
         recipe.times_crafted = 0;
         recipe.successful_crafts = 0;
         recipe.failed_crafts = 0;
+        recipe.creator_royalty_bps = 0;
+        recipe.current_version = 0;
         
         // Calculate discovery threshold based on recipe difficulty and type
         recipe.discovery_threshold = calculate_discovery_threshold(recipe_type, difficulty);
+        
+        write_initial_version(
+            &mut ctx.accounts.recipe_version,
+            recipe.key(),
+            success_rate,
+            difficulty,
+            ctx.accounts.creator.key(),
+            ctx.bumps.recipe_version,
+        )?;
 
         Ok(())
     }
 
-    // Discover a recipe
+    // Discover a secret recipe by guessing its exact ingredients
     pub fn discover_recipe(
         ctx: Context<DiscoverRecipe>,
-        explorer_level: u8,
-        material_combinations: Vec<Pubkey>,
+        material_types: Vec<Pubkey>,
+        material_amounts: Vec<u64>,
     ) -> Result<()> {
         let recipe = &mut ctx.accounts.recipe;
         
-        // Check if recipe is secret and not already discovered by this user
+        // Check if recipe is secret; the discovery record PDA rules out a second discovery
         require!(recipe.is_secret, RecipeError::RecipeNotSecret);
-        require!(
-            !recipe.discoverers.contains(&ctx.accounts.explorer.key()),
-            RecipeError::AlreadyDiscovered
-        );
+        require!(material_types.len() == material_amounts.len(), RecipeError::InputMismatch);
+        require!(!material_types.is_empty(), RecipeError::NoMaterials);
+        require!(material_types.len() <= Recipe::MAX_MATERIALS, RecipeError::TooManyMaterials);
         
-        // Validate material combinations
-        let discovery_score = calculate_discovery_score(
-            explorer_level,
-            &material_combinations,
-            &recipe.material_types,
+        // Explorer level comes from the on-chain crafting profile
+        require!(
+            ctx.accounts.crafter_stats.experience_level >= recipe.discovery_level(),
+            RecipeError::ExplorerLevelTooLow
         );
         
-        // Check if discovery score meets threshold
+        // The guess must hash to the recipe's commitment
+        let commitment = recipe.ingredient_commitment.as_ref().ok_or(RecipeError::MissingCommitment)?;
+        let (material_types, material_amounts) = sort_ingredients(material_types, material_amounts);
         require!(
-            discovery_score >= recipe.discovery_threshold,
+            hash_ingredients(&commitment.salt, &material_types, &material_amounts) == commitment.hash,
             RecipeError::DiscoveryFailed
         );
         
         // Record discoverer
-        recipe.discoverers.push(ctx.accounts.explorer.key());
+        recipe.discoverer_count += 1;
         
-        // If this is first discovery, give a bonus
-        if recipe.discoverers.len() == 1 {
-            // In a real implementation, would award a special token or achievement
-            // This would be done through a cross-program invocation
-        }
-        
-        // Create discovery record
+        // Create discovery record, which lets this discoverer craft the recipe; a first
+        // discoverer presents it to achievement::claim_achievement for the first-discovery badge
         let discovery_record = &mut ctx.accounts.discovery_record;
         discovery_record.explorer = ctx.accounts.explorer.key();
-        discovery_record.recipe = ctx.accounts.recipe.key();
+        discovery_record.recipe = recipe.key();
         discovery_record.timestamp = Clock::get()?.unix_timestamp;
-        discovery_record.is_first = recipe.discoverers.len() == 1;
+        discovery_record.is_first = recipe.discoverer_count == 1;
+        discovery_record.bump = ctx.bumps.discovery_record;
         
         Ok(())
     }
 
-    // Approve a community-created recipe
+    // Approve a community-created recipe and set its creator's share of crafting fees
     pub fn approve_recipe(
         ctx: Context<ApproveRecipe>,
+        creator_royalty_bps: u16,
     ) -> Result<()> {
         require!(!ctx.accounts.recipe.approved, RecipeError::AlreadyApproved);
+        require!(
+            creator_royalty_bps <= Recipe::MAX_CREATOR_ROYALTY_BPS,
+            RecipeError::RoyaltyTooHigh
+        );
         
         let recipe = &mut ctx.accounts.recipe;
         recipe.approved = true;
+        recipe.creator_royalty_bps = creator_royalty_bps;
         
         Ok(())
     }
@@ -123,17 +159,44 @@ pub mod magic_vial_recipe {
         Ok(())
     }
 
-    // Update recipe success rate (for balancing)
+    // Update recipe success rate (for balancing); each change becomes a new recipe version
     pub fn update_success_rate(
         ctx: Context<UpdateRecipe>,
         new_success_rate: u8,
+        reason: String,
     ) -> Result<()> {
         require!(new_success_rate <= 100, RecipeError::InvalidSuccessRate);
         
-        let recipe = &mut ctx.accounts.recipe;
-        recipe.success_rate = new_success_rate;
+        let previous_value = ctx.accounts.recipe.success_rate;
+        ctx.accounts.recipe.success_rate = new_success_rate;
         
-        Ok(())
+        record_recipe_version(
+            ctx,
+            RecipeField::SuccessRate,
+            previous_value,
+            new_success_rate,
+            reason,
+        )
+    }
+    
+    // Update recipe difficulty (for balancing); each change becomes a new recipe version
+    pub fn update_difficulty(
+        ctx: Context<UpdateRecipe>,
+        new_difficulty: u8,
+        reason: String,
+    ) -> Result<()> {
+        require!(new_difficulty >= 1 && new_difficulty <= 100, RecipeError::InvalidDifficulty);
+        
+        let previous_value = ctx.accounts.recipe.difficulty;
+        ctx.accounts.recipe.difficulty = new_difficulty;
+        
+        record_recipe_version(
+            ctx,
+            RecipeField::Difficulty,
+            previous_value,
+            new_difficulty,
+            reason,
+        )
     }
     
     // Mark recipe as crafted (called from crafting program)
@@ -158,21 +221,178 @@ pub mod magic_vial_recipe {
         ctx: Context<AddRecipeHint>,
         hint: String,
     ) -> Result<()> {
-        require!(hint.len() <= 100, RecipeError::HintTooLong);
+        require!(hint.len() <= Recipe::MAX_HINT_LEN, RecipeError::HintTooLong);
         
         let recipe = &mut ctx.accounts.recipe;
+        require!(recipe.hints.len() < Recipe::MAX_HINTS, RecipeError::TooManyHints);
         
-        // Check if user has discovered the recipe
-        require!(
-            recipe.discoverers.contains(&ctx.accounts.contributor.key()),
-            RecipeError::NotDiscovered
-        );
+        // Only discoverers can add hints, which AddRecipeHint checks through
+        // the contributor's discovery record
         
         // Add the hint
         recipe.hints.push(hint);
         
         Ok(())
     }
+    
+    // Convert a recipe created while discoverers were stored on the recipe itself
+    pub fn migrate_recipe(
+        ctx: Context<MigrateRecipe>,
+        ingredient_salt: [u8; 32],
+    ) -> Result<()> {
+        let account_info = ctx.accounts.recipe.to_account_info();
+        
+        let legacy = {
+            let data = account_info.try_borrow_data()?;
+            require!(
+                data.len() > 8 && data[..8] == Recipe::discriminator(),
+                RecipeError::InvalidMigrationAccount
+            );
+            LegacyRecipe::deserialize(&mut &data[8..])
+                .map_err(|_| error!(RecipeError::InvalidMigrationAccount))?
+        };
+        require!(legacy.creator == ctx.accounts.creator.key(), RecipeError::UnauthorizedCaller);
+        
+        // Secret recipes swap their plaintext ingredients for a commitment. Earlier
+        // discoverers stay counted but need a discovery record to craft the recipe
+        let (material_types, material_amounts, ingredient_commitment) = if legacy.is_secret {
+            let (material_types, material_amounts) =
+                sort_ingredients(legacy.material_types, legacy.material_amounts);
+            let commitment = IngredientCommitment {
+                salt: ingredient_salt,
+                hash: hash_ingredients(&ingredient_salt, &material_types, &material_amounts),
+            };
+            (Vec::new(), Vec::new(), Some(commitment))
+        } else {
+            (legacy.material_types, legacy.material_amounts, None)
+        };
+        
+        let recipe = Recipe {
+            name: legacy.name,
+            description: legacy.description,
+            recipe_type: legacy.recipe_type,
+            difficulty: legacy.difficulty,
+            success_rate: legacy.success_rate,
+            uri: legacy.uri,
+            creator: legacy.creator,
+            approved: legacy.approved,
+            disabled: legacy.disabled,
+            created_at: legacy.created_at,
+            material_types,
+            material_amounts,
+            result_types: legacy.result_types,
+            result_weights: legacy.result_weights,
+            is_secret: legacy.is_secret,
+            ingredient_commitment,
+            hints: legacy.hints,
+            discoverer_count: legacy.discoverers.len() as u64,
+            discovery_threshold: legacy.discovery_threshold,
+            times_crafted: legacy.times_crafted,
+            successful_crafts: legacy.successful_crafts,
+            failed_crafts: legacy.failed_crafts,
+            creator_royalty_bps: 0,
+            current_version: 0,
+        };
+        
+        grow_account(
+            &account_info,
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            recipe.space_with(account_info.data_len(), 0),
+        )?;
+        
+        let mut data = account_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        recipe.try_serialize(&mut writer)?;
+        
+        // Secret recipes shrink, so clear what is left of the legacy plaintext ingredients
+        let unused_len = writer.len();
+        let serialized_len = data.len() - unused_len;
+        data[serialized_len..].fill(0);
+        
+        // Legacy recipes were never rebalanced through versions, so they start at version 0
+        write_initial_version(
+            &mut ctx.accounts.recipe_version,
+            account_info.key(),
+            recipe.success_rate,
+            recipe.difficulty,
+            ctx.accounts.creator.key(),
+            ctx.bumps.recipe_version,
+        )?;
+        
+        let migration_receipt = &mut ctx.accounts.migration_receipt;
+        migration_receipt.account = account_info.key();
+        migration_receipt.migrated_at = Clock::get()?.unix_timestamp;
+        migration_receipt.bump = ctx.bumps.migration_receipt;
+        
+        Ok(())
+    }
+}
+
+// Helper function to write the recipe version created by a balancing change and log it
+fn record_recipe_version(
+    ctx: Context<UpdateRecipe>,
+    field: RecipeField,
+    previous_value: u8,
+    new_value: u8,
+    reason: String,
+) -> Result<()> {
+    require!(reason.len() <= RecipeVersion::MAX_REASON_LEN, RecipeError::ReasonTooLong);
+    
+    let recipe = &mut ctx.accounts.recipe;
+    recipe.current_version = recipe
+        .current_version
+        .checked_add(1)
+        .ok_or(RecipeError::ArithmeticOverflow)?;
+    
+    let recipe_version = &mut ctx.accounts.recipe_version;
+    recipe_version.recipe = recipe.key();
+    recipe_version.version = recipe.current_version;
+    recipe_version.success_rate = recipe.success_rate;
+    recipe_version.difficulty = recipe.difficulty;
+    recipe_version.field = field;
+    recipe_version.previous_value = previous_value;
+    recipe_version.new_value = new_value;
+    recipe_version.changed_by = ctx.accounts.authority.key();
+    recipe_version.reason = reason.clone();
+    recipe_version.created_at = Clock::get()?.unix_timestamp;
+    recipe_version.bump = ctx.bumps.recipe_version;
+    
+    emit!(RecipeBalanced {
+        recipe: recipe.key(),
+        version: recipe.current_version,
+        field,
+        previous_value,
+        new_value,
+        changed_by: ctx.accounts.authority.key(),
+        reason,
+    });
+    
+    Ok(())
+}
+
+// Helper function to write version 0, the recipe as created or migrated
+fn write_initial_version(
+    recipe_version: &mut Account<RecipeVersion>,
+    recipe: Pubkey,
+    success_rate: u8,
+    difficulty: u8,
+    changed_by: Pubkey,
+    bump: u8,
+) -> Result<()> {
+    recipe_version.recipe = recipe;
+    recipe_version.version = 0;
+    recipe_version.success_rate = success_rate;
+    recipe_version.difficulty = difficulty;
+    recipe_version.field = RecipeField::Initial;
+    recipe_version.previous_value = 0;
+    recipe_version.new_value = 0;
+    recipe_version.changed_by = changed_by;
+    recipe_version.reason = String::new();
+    recipe_version.created_at = Clock::get()?.unix_timestamp;
+    recipe_version.bump = bump;
+    
+    Ok(())
 }
 
 // Helper function to calculate discovery threshold
@@ -190,38 +410,33 @@ fn calculate_discovery_threshold(recipe_type: u8, difficulty: u8) -> u8 {
     base_threshold.saturating_add(difficulty_factor).min(95) // Cap at 95%
 }
 
-// Helper function to calculate discovery score from materials
-fn calculate_discovery_score(
-    explorer_level: u8,
-    provided_materials: &Vec<Pubkey>,
-    recipe_materials: &Vec<Pubkey>,
-) -> u8 {
-    // Base score based on explorer level
-    let level_bonus = explorer_level.saturating_div(2); // 1 point per 2 levels
-    
-    // Calculate match percentage for materials
-    let mut matched_materials = 0;
-    
-    for material in provided_materials {
-        if recipe_materials.contains(material) {
-            matched_materials += 1;
-        }
-    }
-    
-    // Calculate percentage of correct materials
-    let mut match_score = if !recipe_materials.is_empty() {
-        ((matched_materials as f32 / recipe_materials.len() as f32) * 100.0) as u8
-    } else {
-        0
-    };
-    
-    // Bonus for providing exactly the right materials (no extras)
-    if matched_materials == recipe_materials.len() && provided_materials.len() == recipe_materials.len() {
-        match_score = match_score.saturating_add(10);
+// Helper function to order ingredients by material type, so any guess order hashes the same
+pub fn sort_ingredients(material_types: Vec<Pubkey>, material_amounts: Vec<u64>) -> (Vec<Pubkey>, Vec<u64>) {
+    let mut ingredients: Vec<(Pubkey, u64)> = material_types.into_iter().zip(material_amounts).collect();
+    ingredients.sort_by(|a, b| a.0.cmp(&b.0));
+    ingredients.into_iter().unzip()
+}
+
+// Helper function to hash a salted, sorted ingredient set for a secret recipe's commitment
+pub fn hash_ingredients(salt: &[u8; 32], material_types: &[Pubkey], material_amounts: &[u64]) -> [u8; 32] {
+    let mut preimage = Vec::with_capacity(32 + material_types.len() * 40);
+    preimage.extend_from_slice(salt);
+    for (material_type, amount) in material_types.iter().zip(material_amounts) {
+        preimage.extend_from_slice(material_type.as_ref());
+        preimage.extend_from_slice(&amount.to_le_bytes());
     }
-    
-    // Apply level bonus
-    match_score.saturating_add(level_bonus)
+    keccak::hash(&preimage).to_bytes()
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct IngredientCommitment {
+    pub salt: [u8; 32],             // Public salt, unique per recipe
+    pub hash: [u8; 32],             // keccak(salt || sorted (material type, amount) pairs)
+}
+
+impl IngredientCommitment {
+    pub const LEN: usize = 32 + // salt
+                           32;  // hash
 }
 
 #[account]
@@ -241,12 +456,110 @@ pub struct Recipe {
     pub result_types: Vec<Pubkey>,  // Possible result types
     pub result_weights: Vec<u8>,    // Probability weights for results
     pub is_secret: bool,            // Whether recipe is initially hidden
+    pub ingredient_commitment: Option<IngredientCommitment>, // Secret recipes' hidden ingredients
     pub hints: Vec<String>,         // Discovery hints
-    pub discoverers: Vec<Pubkey>,   // Users who discovered this recipe
-    pub discovery_threshold: u8,    // Threshold score needed for discovery
+    pub discoverer_count: u64,      // Users who discovered this recipe
+    pub discovery_threshold: u8,    // Difficulty of discovery; a tenth of it is the level needed
     pub times_crafted: u64,         // Total times this recipe was used
     pub successful_crafts: u64,     // Successful crafting attempts
     pub failed_crafts: u64,         // Failed crafting attempts
+    pub creator_royalty_bps: u16,   // Creator's share of each crafting fee, set on approval
+    pub current_version: u32,       // Latest balancing version; 0 is the recipe as created
+}
+
+impl Recipe {
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_DESCRIPTION_LEN: usize = 256;
+    pub const MAX_URI_LEN: usize = 200;
+    pub const MAX_MATERIALS: usize = 10;
+    pub const MAX_RESULTS: usize = 10;
+    pub const MAX_HINTS: usize = 10;
+    pub const MAX_HINT_LEN: usize = 100;
+    pub const MAX_CREATOR_ROYALTY_BPS: u16 = 5_000; // Platform keeps at least half of each fee
+    
+    // Fixed part of the layout; hints are reallocated as they grow
+    pub const LEN: usize = 4 + Self::MAX_NAME_LEN + // name
+                           4 + Self::MAX_DESCRIPTION_LEN + // description
+                           1 +  // recipe_type
+                           1 +  // difficulty
+                           1 +  // success_rate
+                           4 + Self::MAX_URI_LEN + // uri
+                           32 + // creator
+                           1 +  // approved
+                           1 +  // disabled
+                           8 +  // created_at
+                           4 + Self::MAX_MATERIALS * 32 + // material_types
+                           4 + Self::MAX_MATERIALS * 8 +  // material_amounts
+                           4 + Self::MAX_RESULTS * 32 +   // result_types
+                           4 + Self::MAX_RESULTS +        // result_weights
+                           1 +  // is_secret
+                           1 + IngredientCommitment::LEN + // ingredient_commitment
+                           4 +  // hints (contents sized by hints_len)
+                           8 +  // discoverer_count
+                           1 +  // discovery_threshold
+                           8 +  // times_crafted
+                           8 +  // successful_crafts
+                           8 +  // failed_crafts
+                           2 +  // creator_royalty_bps
+                           4;   // current_version
+    
+    pub fn hints_len(hints: &[String]) -> usize {
+        hints.iter().map(|hint| 4 + hint.len()).sum()
+    }
+    
+    // Account size needed for the current hints plus `extra` bytes,
+    // never less than the current size so accounts created with slack keep it
+    pub fn space_with(&self, current_len: usize, extra: usize) -> usize {
+        let needed = 8 + Self::LEN
+            + Self::hints_len(&self.hints)
+            + extra;
+        needed.max(current_len)
+    }
+    
+    // Crafting level an explorer needs before attempting to discover this recipe
+    pub fn discovery_level(&self) -> u8 {
+        self.discovery_threshold / 10
+    }
+}
+
+// Recipe fields that balancing changes can touch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RecipeField {
+    SuccessRate,
+    Difficulty,
+    Initial, // Version 0; no field changed
+}
+
+// Immutable snapshot of a recipe's balancing values after a change
+#[account]
+pub struct RecipeVersion {
+    pub recipe: Pubkey,             // Recipe this version belongs to
+    pub version: u32,               // Version number; 0 is the recipe as created or migrated
+    pub success_rate: u8,           // Base success rate in this version
+    pub difficulty: u8,             // Difficulty in this version
+    pub field: RecipeField,         // Field the change touched
+    pub previous_value: u8,         // Value before the change
+    pub new_value: u8,              // Value after the change
+    pub changed_by: Pubkey,         // Creator or admin who made the change
+    pub reason: String,             // Why the change was made
+    pub created_at: i64,            // When the version took effect
+    pub bump: u8,
+}
+
+impl RecipeVersion {
+    pub const MAX_REASON_LEN: usize = 200;
+    
+    pub const LEN: usize = 32 + // recipe
+                           4 +  // version
+                           1 +  // success_rate
+                           1 +  // difficulty
+                           1 +  // field enum
+                           1 +  // previous_value
+                           1 +  // new_value
+                           32 + // changed_by
+                           4 + Self::MAX_REASON_LEN + // reason
+                           8 +  // created_at
+                           1;   // bump
 }
 
 #[account]
@@ -255,13 +568,73 @@ pub struct RecipeDiscoveryRecord {
     pub recipe: Pubkey,             // Which recipe was discovered
     pub timestamp: i64,             // When it was discovered
     pub is_first: bool,             // Whether this was the first discovery
+    pub bump: u8,
+}
+
+impl RecipeDiscoveryRecord {
+    pub const LEN: usize = 32 + // explorer
+                           32 + // recipe
+                           8 +  // timestamp
+                           1 +  // is_first
+                           1;   // bump
+}
+
+// Recipe layout before secret ingredients were committed and discoverers got their own records
+#[derive(AnchorDeserialize)]
+struct LegacyRecipe {
+    name: String,
+    description: String,
+    recipe_type: u8,
+    difficulty: u8,
+    success_rate: u8,
+    uri: String,
+    creator: Pubkey,
+    approved: bool,
+    disabled: bool,
+    created_at: i64,
+    material_types: Vec<Pubkey>,
+    material_amounts: Vec<u64>,
+    result_types: Vec<Pubkey>,
+    result_weights: Vec<u8>,
+    is_secret: bool,
+    hints: Vec<String>,
+    discoverers: Vec<Pubkey>,
+    discovery_threshold: u8,
+    times_crafted: u64,
+    successful_crafts: u64,
+    failed_crafts: u64,
 }
 
 #[derive(Accounts)]
+#[instruction(
+    name: String,
+    description: String,
+    recipe_type: u8,
+    difficulty: u8,
+    success_rate: u8,
+    uri: String,
+    material_types: Vec<Pubkey>,
+    material_amounts: Vec<u64>,
+    result_types: Vec<Pubkey>,
+    result_weights: Vec<u8>,
+    is_secret: bool,
+    hints: Vec<String>,
+    ingredient_commitment: Option<IngredientCommitment>,
+)]
 pub struct CreateRecipe<'info> {
-    #[account(init, payer = creator, space = 8 + 2048)]
+    #[account(init, payer = creator, space = 8 + Recipe::LEN + Recipe::hints_len(&hints))]
     pub recipe: Account<'info, Recipe>,
     
+    // Version 0, the recipe as created
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + RecipeVersion::LEN,
+        seeds = [b"recipe_version".as_ref(), recipe.key().as_ref(), &0u32.to_le_bytes()],
+        bump
+    )]
+    pub recipe_version: Account<'info, RecipeVersion>,
+    
     #[account(mut)]
     pub creator: Signer<'info>,
     
@@ -278,9 +651,23 @@ pub struct DiscoverRecipe<'info> {
     #[account(mut)]
     pub recipe: Account<'info, Recipe>,
     
-    #[account(init, payer = explorer, space = 8 + 128)]
+    // One per discoverer, so a recipe can be discovered any number of times but once per wallet
+    #[account(
+        init,
+        payer = explorer,
+        space = 8 + RecipeDiscoveryRecord::LEN,
+        seeds = [b"recipe_discovery".as_ref(), recipe.key().as_ref(), explorer.key().as_ref()],
+        bump
+    )]
     pub discovery_record: Account<'info, RecipeDiscoveryRecord>,
     
+    #[account(
+        seeds = [b"crafter-stats", explorer.key().as_ref()],
+        bump,
+        seeds::program = crate::crafting::ID
+    )]
+    pub crafter_stats: Account<'info, CrafterStats>,
+    
     #[account(mut)]
     pub explorer: Signer<'info>,
     
@@ -293,7 +680,19 @@ pub struct ApproveRecipe<'info> {
     #[account(mut)]
     pub recipe: Account<'info, Recipe>,
     
-    #[account(mut, constraint = authority.key() != recipe.creator @ RecipeError::SelfApproval)]
+    // Approval sets the creator's share of every crafting fee, so only the platform authority approves
+    #[account(
+        seeds = [b"crafting_config".as_ref()],
+        bump = crafting_config.bump,
+        seeds::program = crate::crafting::ID
+    )]
+    pub crafting_config: Account<'info, CraftingConfig>,
+    
+    #[account(
+        mut,
+        constraint = authority.key() == crafting_config.authority @ RecipeError::UnauthorizedCaller,
+        constraint = authority.key() != recipe.creator @ RecipeError::SelfApproval
+    )]
     pub authority: Signer<'info>,
 }
 
@@ -314,11 +713,38 @@ pub struct UpdateRecipe<'info> {
     #[account(mut)]
     pub recipe: Account<'info, Recipe>,
     
-    #[account(mut, constraint = authority.key() == recipe.creator || authority_type.is_admin)]
+    // The version this change creates
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RecipeVersion::LEN,
+        // Saturates so the address still derives; the instruction rejects the overflow
+        seeds = [
+            b"recipe_version".as_ref(),
+            recipe.key().as_ref(),
+            &recipe.current_version.saturating_add(1).to_le_bytes(),
+        ],
+        bump
+    )]
+    pub recipe_version: Account<'info, RecipeVersion>,
+    
+    #[account(
+        mut,
+        constraint = authority.key() == recipe.creator ||
+            authority_type.as_ref().map_or(false, |authority_type| authority_type.is_admin)
+            @ RecipeError::UnauthorizedCaller
+    )]
     pub authority: Signer<'info>,
     
-    // Account to check if the authority has admin privileges
-    pub authority_type: Account<'info, AuthorityType>,
+    // Admin status of the signer; only needed when the signer is not the recipe's creator
+    #[account(
+        seeds = [b"authority_type".as_ref(), authority.key().as_ref()],
+        bump,
+        constraint = authority_type.authority == authority.key() @ RecipeError::UnauthorizedCaller
+    )]
+    pub authority_type: Option<Account<'info, AuthorityType>>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -332,12 +758,59 @@ pub struct RecordCrafting<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(hint: String)]
 pub struct AddRecipeHint<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        realloc = recipe.space_with(recipe.to_account_info().data_len(), 4 + hint.len()),
+        realloc::payer = contributor,
+        realloc::zero = false,
+    )]
     pub recipe: Account<'info, Recipe>,
     
+    #[account(
+        seeds = [b"recipe_discovery".as_ref(), recipe.key().as_ref(), contributor.key().as_ref()],
+        bump = discovery_record.bump,
+        constraint = discovery_record.explorer == contributor.key() @ RecipeError::NotDiscovered,
+    )]
+    pub discovery_record: Account<'info, RecipeDiscoveryRecord>,
+    
     #[account(mut)]
     pub contributor: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateRecipe<'info> {
+    /// CHECK: Still in the legacy layout, so it is parsed by hand in the instruction
+    #[account(mut, owner = crate::ID)]
+    pub recipe: UncheckedAccount<'info>,
+    
+    // Each account can only be migrated once
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + MigrationReceipt::LEN,
+        seeds = [b"migration", recipe.key().as_ref()],
+        bump
+    )]
+    pub migration_receipt: Account<'info, MigrationReceipt>,
+    
+    // Version 0, the recipe as migrated
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + RecipeVersion::LEN,
+        seeds = [b"recipe_version".as_ref(), recipe.key().as_ref(), &0u32.to_le_bytes()],
+        bump
+    )]
+    pub recipe_version: Account<'info, RecipeVersion>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 // Simple account to track admin status
@@ -347,6 +820,17 @@ pub struct AuthorityType {
     pub is_admin: bool,
 }
 
+#[event]
+pub struct RecipeBalanced {
+    pub recipe: Pubkey,
+    pub version: u32,
+    pub field: RecipeField,
+    pub previous_value: u8,
+    pub new_value: u8,
+    pub changed_by: Pubkey,
+    pub reason: String,
+}
+
 // Keep track of all known crafting program IDs
 const crafting_program_id: Pubkey = pubkey!("craXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
 
@@ -390,4 +874,46 @@ pub enum RecipeError {
     
     #[msg("You have not discovered this recipe yet")]
     NotDiscovered,
+    
+    #[msg("Recipe has too many materials")]
+    TooManyMaterials,
+    
+    #[msg("Recipe has too many possible results")]
+    TooManyResults,
+    
+    #[msg("Recipe name is too long")]
+    NameTooLong,
+    
+    #[msg("Recipe description is too long")]
+    DescriptionTooLong,
+    
+    #[msg("Recipe URI is too long")]
+    UriTooLong,
+    
+    #[msg("Recipe has too many hints")]
+    TooManyHints,
+    
+    #[msg("Secret recipes need an ingredient commitment")]
+    MissingCommitment,
+    
+    #[msg("Secret recipes cannot store their ingredients in plaintext")]
+    SecretIngredientsExposed,
+    
+    #[msg("Explorer level is too low to discover this recipe")]
+    ExplorerLevelTooLow,
+    
+    #[msg("Account is not in the legacy layout")]
+    InvalidMigrationAccount,
+    
+    #[msg("Creator royalty exceeds the maximum")]
+    RoyaltyTooHigh,
+    
+    #[msg("Difficulty must be between 1 and 100")]
+    InvalidDifficulty,
+    
+    #[msg("Balancing reason is too long")]
+    ReasonTooLong,
+    
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
 } 
\ No newline at end of file
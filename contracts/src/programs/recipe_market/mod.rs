@@ -0,0 +1,435 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+// Recipe knowledge is proven by the discovery records written by the recipe program
+use crate::recipe::{Recipe, RecipeDiscoveryRecord};
+use crate::crafting::CraftingConfig;
+
+declare_id!("rmkXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
+
+#[program]
+pub mod magic_vial_recipe_market {
+    use super::*;
+
+    // Initialize the recipe market
+    pub fn initialize_market(
+        ctx: Context<InitializeMarket>,
+        platform_fee_bps: u16, // Share of each sale sent to the treasury
+        creator_fee_bps: u16,  // Share of each sale sent to the recipe's creator
+    ) -> Result<()> {
+        validate_fees(platform_fee_bps, creator_fee_bps)?;
+        
+        let market_config = &mut ctx.accounts.market_config;
+        market_config.authority = ctx.accounts.authority.key();
+        market_config.payment_mint = ctx.accounts.payment_mint.key();
+        market_config.treasury = ctx.accounts.treasury.key();
+        market_config.platform_fee_bps = platform_fee_bps;
+        market_config.creator_fee_bps = creator_fee_bps;
+        market_config.bump = ctx.bumps.market_config;
+        
+        Ok(())
+    }
+
+    // Change the fee split and treasury
+    pub fn update_market(
+        ctx: Context<UpdateMarket>,
+        platform_fee_bps: u16,
+        creator_fee_bps: u16,
+    ) -> Result<()> {
+        validate_fees(platform_fee_bps, creator_fee_bps)?;
+        
+        let market_config = &mut ctx.accounts.market_config;
+        market_config.treasury = ctx.accounts.treasury.key();
+        market_config.platform_fee_bps = platform_fee_bps;
+        market_config.creator_fee_bps = creator_fee_bps;
+        
+        Ok(())
+    }
+
+    // List a license for a secret recipe the seller has discovered
+    pub fn create_listing(
+        ctx: Context<CreateListing>,
+        price: u64,
+        license_duration: Option<i64>, // Seconds a license lasts; None for a permanent license
+    ) -> Result<()> {
+        require!(ctx.accounts.recipe.is_secret, MarketError::RecipeNotSecret);
+        validate_listing(price, license_duration)?;
+        
+        let listing = &mut ctx.accounts.listing;
+        listing.seller = ctx.accounts.seller.key();
+        listing.recipe = ctx.accounts.recipe.key();
+        listing.price = price;
+        listing.license_duration = license_duration;
+        listing.active = true;
+        listing.licenses_sold = 0;
+        listing.created_at = Clock::get()?.unix_timestamp;
+        listing.bump = ctx.bumps.listing;
+        
+        Ok(())
+    }
+
+    // Change a listing's price, license duration or availability
+    pub fn update_listing(
+        ctx: Context<UpdateListing>,
+        price: u64,
+        license_duration: Option<i64>,
+        active: bool,
+    ) -> Result<()> {
+        validate_listing(price, license_duration)?;
+        
+        let listing = &mut ctx.accounts.listing;
+        listing.price = price;
+        listing.license_duration = license_duration;
+        listing.active = active;
+        
+        Ok(())
+    }
+
+    // Buy a license to craft a listed recipe
+    pub fn purchase_license(
+        ctx: Context<PurchaseLicense>,
+        max_price: u64, // Guards against the seller raising the price before the purchase lands
+    ) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        require!(listing.active, MarketError::ListingInactive);
+        require!(listing.price <= max_price, MarketError::PriceChanged);
+        
+        // Split the payment between the treasury, the recipe's creator and the seller
+        let (platform_fee, creator_fee, seller_amount) = split_payment(
+            listing.price,
+            ctx.accounts.market_config.platform_fee_bps,
+            ctx.accounts.market_config.creator_fee_bps,
+        )?;
+        
+        for (to, amount) in [
+            (ctx.accounts.treasury.to_account_info(), platform_fee),
+            (ctx.accounts.creator_token.to_account_info(), creator_fee),
+            (ctx.accounts.seller_token.to_account_info(), seller_amount),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.buyer_token.to_account_info(),
+                        to,
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        }
+        
+        // Only a permanent license cannot be bought again
+        let now = Clock::get()?.unix_timestamp;
+        let recipe_license = &mut ctx.accounts.recipe_license;
+        if recipe_license.holder != Pubkey::default() {
+            require!(recipe_license.expires_at.is_some(), MarketError::AlreadyLicensed);
+        } else {
+            recipe_license.holder = ctx.accounts.buyer.key();
+            recipe_license.recipe = listing.recipe;
+            recipe_license.bump = ctx.bumps.recipe_license;
+        }
+        
+        // A permanent listing makes the license permanent; a time-limited one extends it
+        // from whichever is later, now or its expiry
+        let current_expiry = recipe_license.expires_at;
+        recipe_license.expires_at = listing.license_duration.map(|duration| {
+            current_expiry.map_or(now, |expires_at| expires_at.max(now)) + duration
+        });
+        recipe_license.seller = listing.seller;
+        recipe_license.purchased_at = now;
+        
+        let listing = &mut ctx.accounts.listing;
+        listing.licenses_sold += 1;
+        
+        Ok(())
+    }
+}
+
+// Helper function to validate the market's fee split
+fn validate_fees(platform_fee_bps: u16, creator_fee_bps: u16) -> Result<()> {
+    require!(
+        platform_fee_bps as u32 + creator_fee_bps as u32 <= MarketConfig::MAX_TOTAL_FEE_BPS as u32,
+        MarketError::FeesTooHigh
+    );
+    
+    Ok(())
+}
+
+// Helper function to validate a listing's terms
+fn validate_listing(price: u64, license_duration: Option<i64>) -> Result<()> {
+    require!(price > 0, MarketError::InvalidPrice);
+    if let Some(duration) = license_duration {
+        require!(duration > 0, MarketError::InvalidLicenseDuration);
+    }
+    
+    Ok(())
+}
+
+// Helper function to split a sale into platform fee, creator fee and seller proceeds
+fn split_payment(price: u64, platform_fee_bps: u16, creator_fee_bps: u16) -> Result<(u64, u64, u64)> {
+    let platform_fee = (price as u128 * platform_fee_bps as u128 / 10_000) as u64;
+    let creator_fee = (price as u128 * creator_fee_bps as u128 / 10_000) as u64;
+    let seller_amount = price
+        .checked_sub(platform_fee)
+        .and_then(|remaining| remaining.checked_sub(creator_fee))
+        .ok_or(MarketError::FeesTooHigh)?;
+    
+    Ok((platform_fee, creator_fee, seller_amount))
+}
+
+#[account]
+pub struct MarketConfig {
+    pub authority: Pubkey,        // Admin authority
+    pub payment_mint: Pubkey,     // Platform token licenses are paid in
+    pub treasury: Pubkey,         // Platform token account receiving platform fees
+    pub platform_fee_bps: u16,    // Platform share of each sale
+    pub creator_fee_bps: u16,     // Recipe creator share of each sale
+    pub bump: u8,
+}
+
+impl MarketConfig {
+    pub const MAX_TOTAL_FEE_BPS: u16 = 5_000; // Sellers keep at least half of each sale
+    
+    pub const LEN: usize = 32 + // authority
+                           32 + // payment_mint
+                           32 + // treasury
+                           2 +  // platform_fee_bps
+                           2 +  // creator_fee_bps
+                           1;   // bump
+}
+
+#[account]
+pub struct RecipeListing {
+    pub seller: Pubkey,           // Discoverer selling licenses
+    pub recipe: Pubkey,           // Secret recipe being licensed
+    pub price: u64,               // Price in the platform token
+    pub license_duration: Option<i64>, // Seconds a license lasts; None if permanent
+    pub active: bool,             // Whether licenses can be bought
+    pub licenses_sold: u64,       // Licenses sold through this listing
+    pub created_at: i64,          // Creation timestamp
+    pub bump: u8,
+}
+
+impl RecipeListing {
+    pub const LEN: usize = 32 + // seller
+                           32 + // recipe
+                           8 +  // price
+                           9 +  // license_duration (Option<i64>)
+                           1 +  // active
+                           8 +  // licenses_sold
+                           8 +  // created_at
+                           1;   // bump
+}
+
+#[account]
+pub struct RecipeLicense {
+    pub holder: Pubkey,           // Wallet allowed to craft the recipe
+    pub recipe: Pubkey,           // Licensed recipe
+    pub seller: Pubkey,           // Discoverer the latest license was bought from
+    pub purchased_at: i64,        // Latest purchase timestamp
+    pub expires_at: Option<i64>,  // End of the license; None if permanent
+    pub bump: u8,
+}
+
+impl RecipeLicense {
+    pub const LEN: usize = 32 + // holder
+                           32 + // recipe
+                           32 + // seller
+                           8 +  // purchased_at
+                           9 +  // expires_at (Option<i64>)
+                           1;   // bump
+    
+    pub fn is_valid(&self, now: i64) -> bool {
+        self.expires_at.map_or(true, |expires_at| now < expires_at)
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeMarket<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MarketConfig::LEN,
+        seeds = [b"market_config".as_ref()],
+        bump
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    
+    pub payment_mint: Account<'info, Mint>,
+    
+    #[account(token::mint = payment_mint)]
+    pub treasury: Account<'info, TokenAccount>,
+    
+    // The market is a singleton, so only the platform authority may set it up
+    #[account(
+        seeds = [b"crafting_config".as_ref()],
+        bump = crafting_config.bump,
+        seeds::program = crate::crafting::ID
+    )]
+    pub crafting_config: Account<'info, CraftingConfig>,
+    
+    #[account(mut, constraint = authority.key() == crafting_config.authority @ MarketError::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market_config".as_ref()],
+        bump = market_config.bump,
+        has_one = authority @ MarketError::Unauthorized,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    
+    #[account(token::mint = market_config.payment_mint)]
+    pub treasury: Account<'info, TokenAccount>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateListing<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + RecipeListing::LEN,
+        seeds = [b"listing".as_ref(), recipe.key().as_ref(), seller.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, RecipeListing>,
+    
+    pub recipe: Account<'info, Recipe>,
+    
+    // Only discoverers can sell; licensees hold a license rather than a discovery record
+    #[account(
+        seeds = [b"recipe_discovery".as_ref(), recipe.key().as_ref(), seller.key().as_ref()],
+        bump = seller_discovery.bump,
+        seeds::program = crate::recipe::ID
+    )]
+    pub seller_discovery: Account<'info, RecipeDiscoveryRecord>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing".as_ref(), listing.recipe.as_ref(), seller.key().as_ref()],
+        bump = listing.bump,
+        has_one = seller @ MarketError::Unauthorized,
+    )]
+    pub listing: Account<'info, RecipeListing>,
+    
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseLicense<'info> {
+    #[account(
+        seeds = [b"market_config".as_ref()],
+        bump = market_config.bump
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing".as_ref(), recipe.key().as_ref(), listing.seller.as_ref()],
+        bump = listing.bump,
+        constraint = listing.seller != buyer.key() @ MarketError::SelfPurchase,
+    )]
+    pub listing: Account<'info, RecipeListing>,
+    
+    pub recipe: Account<'info, Recipe>,
+    
+    // The seller must still hold the discovery record they listed under
+    #[account(
+        seeds = [b"recipe_discovery".as_ref(), recipe.key().as_ref(), listing.seller.as_ref()],
+        bump = seller_discovery.bump,
+        seeds::program = crate::recipe::ID
+    )]
+    pub seller_discovery: Account<'info, RecipeDiscoveryRecord>,
+    
+    // Accepted by crafting::craft in place of a discovery record
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + RecipeLicense::LEN,
+        seeds = [b"license".as_ref(), recipe.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub recipe_license: Account<'info, RecipeLicense>,
+    
+    #[account(
+        mut,
+        token::mint = market_config.payment_mint,
+        token::authority = buyer,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = market_config.payment_mint,
+        token::authority = listing.seller,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = market_config.payment_mint,
+        token::authority = recipe.creator,
+    )]
+    pub creator_token: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = market_config.treasury @ MarketError::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[error_code]
+pub enum MarketError {
+    #[msg("Unauthorized access")]
+    Unauthorized,
+    
+    #[msg("Platform and creator fees exceed the maximum")]
+    FeesTooHigh,
+    
+    #[msg("Only secret recipes can be licensed")]
+    RecipeNotSecret,
+    
+    #[msg("Price must be greater than zero")]
+    InvalidPrice,
+    
+    #[msg("License duration must be greater than zero")]
+    InvalidLicenseDuration,
+    
+    #[msg("Listing is not active")]
+    ListingInactive,
+    
+    #[msg("Listing price is above the buyer's maximum")]
+    PriceChanged,
+    
+    #[msg("Sellers cannot buy their own listing")]
+    SelfPurchase,
+    
+    #[msg("Buyer already holds a permanent license")]
+    AlreadyLicensed,
+    
+    #[msg("Treasury does not match the market config")]
+    InvalidTreasury,
+}
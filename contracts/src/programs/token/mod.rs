@@ -15,6 +15,10 @@ pub mod magic_vial_token {
         uri: String,
         total_supply: u64,
     ) -> Result<()> {
+        require!(name.len() <= TokenInfo::MAX_NAME_LEN, TokenError::NameTooLong);
+        require!(symbol.len() <= TokenInfo::MAX_SYMBOL_LEN, TokenError::SymbolTooLong);
+        require!(uri.len() <= TokenInfo::MAX_URI_LEN, TokenError::UriTooLong);
+        
         let token_info = &mut ctx.accounts.token_info;
         token_info.name = name;
         token_info.symbol = symbol;
@@ -84,9 +88,22 @@ pub struct TokenInfo {
     pub total_supply: u64,  // Total supply
 }
 
+impl TokenInfo {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const MAX_SYMBOL_LEN: usize = 10;
+    pub const MAX_URI_LEN: usize = 200;
+    
+    pub const LEN: usize = 4 + Self::MAX_NAME_LEN + // name
+                           4 + Self::MAX_SYMBOL_LEN + // symbol
+                           4 + Self::MAX_URI_LEN + // uri
+                           1 +  // decimals
+                           32 + // authority
+                           8;   // total_supply
+}
+
 #[derive(Accounts)]
 pub struct Initialize<'info> {
-    #[account(init, payer = authority, space = 8 + 256)]
+    #[account(init, payer = authority, space = 8 + TokenInfo::LEN)]
     pub token_info: Account<'info, TokenInfo>,
     
     #[account(
@@ -139,4 +156,16 @@ pub struct Burn<'info> {
     pub authority: Signer<'info>,
     
     pub token_program: Program<'info, Token>,
-} 
\ No newline at end of file
+} 
+
+#[error_code]
+pub enum TokenError {
+    #[msg("Token name is too long")]
+    NameTooLong,
+    
+    #[msg("Token symbol is too long")]
+    SymbolTooLong,
+    
+    #[msg("Token URI is too long")]
+    UriTooLong,
+}
@@ -24,15 +24,15 @@ pub fn create_token(
         return Err(MagicVialError::InvalidTokenParameters.into());
     }
     
-    if name.len() > 64 {
+    if name.len() > TokenConfig::MAX_NAME_LEN {
         return Err(MagicVialError::DataSizeExceeded.into());
     }
     
-    if symbol.len() > 16 {
+    if symbol.len() > TokenConfig::MAX_SYMBOL_LEN {
         return Err(MagicVialError::DataSizeExceeded.into());
     }
     
-    if uri.len() > 128 {
+    if uri.len() > TokenConfig::MAX_URI_LEN {
         return Err(MagicVialError::DataSizeExceeded.into());
     }
     
@@ -23,19 +23,19 @@ pub fn submit_for_verification(
         return Err(MagicVialError::InvalidParameters.into());
     }
     
-    if project_name.len() > 64 {
+    if project_name.len() > ProjectVerification::MAX_PROJECT_NAME_LEN {
         return Err(MagicVialError::DataSizeExceeded.into());
     }
     
-    if project_description.len() > 256 {
+    if project_description.len() > ProjectVerification::MAX_DESCRIPTION_LEN {
         return Err(MagicVialError::DataSizeExceeded.into());
     }
     
-    if github_repo.len() > 128 {
+    if github_repo.len() > ProjectVerification::MAX_GITHUB_REPO_LEN {
         return Err(MagicVialError::DataSizeExceeded.into());
     }
     
-    if documentation_uri.len() > 128 {
+    if documentation_uri.len() > ProjectVerification::MAX_DOCUMENTATION_URI_LEN {
         return Err(MagicVialError::DataSizeExceeded.into());
     }
     